@@ -0,0 +1,161 @@
+//! Benchmark the conversion of long glob patterns.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fnmatch_regex::GlobOptions;
+
+/// Build an alternation with the specified number of branches.
+fn alternation(count: usize) -> String {
+    format!(
+        "dir/{{{}}}",
+        (0..count)
+            .map(|idx| format!("item{}.txt", idx))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Build a character class with the specified number of characters.
+fn class(count: u32) -> String {
+    format!(
+        "[{}]",
+        (0..count)
+            .filter_map(|idx| char::from_u32(0x100 + idx))
+            .collect::<String>()
+    )
+}
+
+/// Measure the conversion time of long alternations and character classes;
+/// it should grow linearly with the length of the pattern.
+fn bench_long(crit: &mut Criterion) {
+    let mut group = crit.benchmark_group("long");
+    for count in [1_000, 10_000] {
+        let pattern = alternation(count);
+        group.bench_with_input(
+            BenchmarkId::new("alternation", count),
+            &pattern,
+            |bencher, pattern| bencher.iter(|| fnmatch_regex::glob_to_regex_string(pattern)),
+        );
+
+        let pattern = class(u32::try_from(count).unwrap_or(u32::MAX));
+        group.bench_with_input(
+            BenchmarkId::new("class", count),
+            &pattern,
+            |bencher, pattern| bencher.iter(|| fnmatch_regex::glob_to_regex_string(pattern)),
+        );
+    }
+    group.finish();
+}
+
+/// Build a set of literal words with many common prefixes.
+fn words(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|idx| format!("{}{}", ["config", "cont", "conv", "data"][idx % 4], idx))
+        .collect()
+}
+
+/// Compare the compilation and matching speed of the flat and
+/// the prefix-merged alternations of a large set of literal strings.
+fn bench_compact(crit: &mut Criterion) {
+    let words = words(1_000);
+    let pattern = format!("{{{}}}", words.join(","));
+    let mut group = crit.benchmark_group("compact");
+    for compact in [false, true] {
+        let options = GlobOptions::default().compact_alternations(compact);
+        group.bench_with_input(
+            BenchmarkId::new("compile", compact),
+            &options,
+            |bencher, options| {
+                bencher.iter(|| fnmatch_regex::glob_to_regex_with(&pattern, options))
+            },
+        );
+
+        let re = fnmatch_regex::glob_to_regex_with(&pattern, &options).unwrap();
+        group.bench_with_input(BenchmarkId::new("match", compact), &re, |bencher, re| {
+            bencher.iter(|| words.iter().filter(|word| re.is_match(word)).count())
+        });
+    }
+    group.finish();
+}
+
+/// Build an alternation of file name extensions, e.g. for a file type map.
+fn extensions(count: usize) -> String {
+    format!(
+        "*.{{{}}}",
+        (0..count)
+            .map(|idx| format!("ext{}.{}", idx, ["gz", "tar", "c++"][idx % 3]))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Measure the conversion time of an alternation with thousands of
+/// literal branches that need escaping, with and without merging prefixes.
+fn bench_extensions(crit: &mut Criterion) {
+    let pattern = extensions(5_000);
+    let mut group = crit.benchmark_group("extensions");
+    for compact in [false, true] {
+        let options = GlobOptions::default().compact_alternations(compact);
+        group.bench_with_input(
+            BenchmarkId::new("convert", compact),
+            &options,
+            |bencher, options| {
+                bencher.iter(|| fnmatch_regex::glob_to_regex_string_with(&pattern, options))
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Measure the compilation time of many short patterns that share a single
+/// set of options, e.g. the rules of a configuration file.
+fn bench_shared_options(crit: &mut Criterion) {
+    let patterns: Vec<String> = (0..1_000)
+        .map(|idx| format!("src/dir{}/**/*.{{rs,c{}}}", idx, idx % 7))
+        .collect();
+    let options = GlobOptions::default()
+        .globstar(true)
+        .literal_leading_dot(true);
+    crit.bench_function("shared_options", |bencher| {
+        bencher.iter(|| {
+            patterns
+                .iter()
+                .filter(|pattern| fnmatch_regex::glob_to_regex_with(pattern, &options).is_ok())
+                .count()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_long,
+    bench_compact,
+    bench_extensions,
+    bench_shared_options
+);
+criterion_main!(benches);
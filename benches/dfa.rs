@@ -0,0 +1,63 @@
+//! Benchmark matching using a deterministic automaton.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fnmatch_regex::Glob;
+
+/// Build a list of paths, some of them matched by the literal-heavy pattern.
+fn paths() -> Vec<String> {
+    (0..1_000)
+        .map(|idx| {
+            format!(
+                "src/{}/module{}/{}",
+                ["alpha", "beta", "gamma", "delta"][idx % 4],
+                idx,
+                ["lib.rs", "main.rs", "mod.rs", "README.md"][idx % 7 % 4]
+            )
+        })
+        .collect()
+}
+
+/// Compare matching a literal-heavy pattern using the regular expression and
+/// using the automaton built for it.
+fn bench_is_match(crit: &mut Criterion) {
+    let glob = Glob::new("src/{alpha,gamma}/module*/{lib,main}.rs").unwrap();
+    let dfa = glob.to_dfa().unwrap();
+    let paths = paths();
+    let mut group = crit.benchmark_group("is_match");
+    group.bench_function("regex", |bencher| {
+        bencher.iter(|| paths.iter().filter(|path| glob.is_match(path)).count())
+    });
+    group.bench_function("dfa", |bencher| {
+        bencher.iter(|| paths.iter().filter(|path| dfa.is_match(path)).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_is_match);
+criterion_main!(benches);
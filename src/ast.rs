@@ -0,0 +1,731 @@
+//! The parsed representation of a glob pattern.
+//!
+//! The [`crate::glob::parse`] function splits a glob pattern into
+//! a sequence of [`Node`] elements; the regular expression is then built
+//! from those, and they may also be examined directly, e.g. to find out
+//! why a text string did not match the pattern.
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::GlobOptions;
+//! use fnmatch_regex::ast::Node;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let ast = fnmatch_regex::glob::parse("a?", &GlobOptions::default())?;
+//! assert_eq!(ast.nodes, vec![Node::Literal("a".to_owned()), Node::AnyChar]);
+//! # Ok(())
+//! # }
+//! ```
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#[cfg(feature = "regex")]
+use std::cmp::Reverse;
+#[cfg(feature = "regex")]
+use std::mem;
+use std::ops::Range;
+
+use itertools::Itertools;
+
+#[cfg(feature = "regex")]
+use crate::matcher::SegmentKind;
+use crate::options::GlobOptions;
+
+/// Something that may appear in a character class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClassItem {
+    /// A character may appear in a character class.
+    Char(char),
+    /// A range of characters may appear in a character class.
+    Range(char, char),
+    /// A named class of characters may appear in a character class, e.g.
+    /// `[:alpha:]` for a POSIX one or `[:Letter:]` for a Unicode general category.
+    Named {
+        /// The name of the class, e.g. `alpha` or `Letter`.
+        name: String,
+        /// Is the named class negated (i.e. was `^` the first character of its name).
+        negated: bool,
+    },
+}
+
+/// A character class, e.g. `[!a-z_]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Class {
+    /// Is the class negated (i.e. was `!` the first character).
+    pub negated: bool,
+    /// The characters or ranges in the class, in order of appearance.
+    pub items: Vec<ClassItem>,
+    /// Other classes that a character must also match, e.g. `[^aeiou]` for
+    /// `[a-z&&[^aeiou]]` if the `class_intersection` option is enabled.
+    pub intersections: Vec<Class>,
+}
+
+/// A single element of a parsed glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Node {
+    /// Text that must be matched literally, with any escape sequences resolved;
+    /// the adjacent literal characters in the pattern form a single element.
+    Literal(String),
+    /// A `?` wildcard: any single character except a slash.
+    AnyChar,
+    /// A `?{N}` wildcard: exactly that many characters, none of them a slash.
+    AnyChars(usize),
+    /// A `*` wildcard: any sequence of characters that does not contain a slash.
+    Star,
+    /// A `**/` globstar component: any number of leading directories.
+    GlobstarDirs,
+    /// A `**` globstar component at the end of the pattern: any text.
+    GlobstarTail,
+    /// A `*` wildcard at the end of the pattern with
+    /// the `trailing_star_is_recursive` option enabled: any text.
+    StarTail,
+    /// A `[...]` character class.
+    Class(Class),
+    /// A `{...}` alternation: each branch is a sequence of elements.
+    Alternation(Vec<Vec<Node>>),
+}
+
+/// Get the text matched by a sequence of elements if they are all literal.
+pub(crate) fn literal_text(nodes: &[Node]) -> Option<String> {
+    nodes
+        .iter()
+        .map(|node| match *node {
+            Node::Literal(ref text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the elements before a `/**` at the end of a sequence, e.g. `foo` for
+/// `foo/**`, unless the separator is the only thing before the globstar.
+pub(crate) fn globstar_parent(nodes: &[Node], sep: char) -> Option<Vec<Node>> {
+    match *nodes {
+        [ref head @ .., Node::Literal(ref text), Node::GlobstarTail] => {
+            let parent = text.strip_suffix(sep)?;
+            if parent.is_empty() && head.is_empty() {
+                return None;
+            }
+            Some(
+                head.iter()
+                    .cloned()
+                    .chain((!parent.is_empty()).then(|| Node::Literal(parent.to_owned())))
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Count the separators that any text matched by a sequence of elements
+/// must contain, i.e. the ones in the literal text, taking the branch with
+/// the fewest of them for an alternation.
+#[cfg(feature = "regex")]
+pub(crate) fn min_separators(nodes: &[Node], sep: char) -> usize {
+    nodes
+        .iter()
+        .map(|node| match *node {
+            Node::Literal(ref text) => text.matches(sep).count(),
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .map(|branch| min_separators(branch, sep))
+                .min()
+                .unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Check whether a sequence of elements contains a separator in its literal
+/// text, possibly within an alternation branch, or a globstar, so that it may
+/// match a text that contains a separator.
+#[cfg(feature = "regex")]
+pub(crate) fn spans_separator(nodes: &[Node], sep: char) -> bool {
+    nodes.iter().any(|node| match *node {
+        Node::Literal(ref text) => text.contains(sep),
+        Node::GlobstarDirs | Node::GlobstarTail | Node::StarTail => true,
+        Node::Alternation(ref branches) => {
+            branches.iter().any(|branch| spans_separator(branch, sep))
+        }
+        _ => false,
+    })
+}
+
+/// Add the path component collected so far to a list of segments, if there is one.
+#[cfg(feature = "regex")]
+fn push_segment(res: &mut Vec<SegmentKind>, literal: &mut String, wildcard: &mut bool) {
+    if mem::take(wildcard) {
+        literal.clear();
+        res.push(SegmentKind::Wildcard);
+    } else if !literal.is_empty() {
+        res.push(SegmentKind::Literal(mem::take(literal)));
+    }
+}
+
+/// Split a sequence of elements into path components at the separator
+/// characters within the literal text, skipping any empty ones.
+#[cfg(feature = "regex")]
+pub(crate) fn segments(nodes: &[Node], sep: char) -> Vec<SegmentKind> {
+    let mut res = Vec::new();
+    let mut literal = String::new();
+    let mut wildcard = false;
+    for node in nodes {
+        match *node {
+            Node::Literal(ref text) => {
+                for chr in text.chars() {
+                    if chr == sep {
+                        push_segment(&mut res, &mut literal, &mut wildcard);
+                    } else {
+                        literal.push(chr);
+                    }
+                }
+            }
+            Node::GlobstarDirs | Node::GlobstarTail | Node::StarTail => {
+                push_segment(&mut res, &mut literal, &mut wildcard);
+                res.push(SegmentKind::Globstar);
+            }
+            _ => wildcard = true,
+        }
+    }
+    push_segment(&mut res, &mut literal, &mut wildcard);
+    res
+}
+
+/// Count the literal characters and the wildcards in a sequence of elements.
+/// An alternation counts as one wildcard along with the least specific one of
+/// its branches, i.e. the one with the fewest literal characters.
+#[cfg(feature = "regex")]
+pub(crate) fn specificity(nodes: &[Node]) -> (usize, usize) {
+    nodes.iter().fold((0, 0), |(literal, wildcards), node| {
+        let (node_literal, node_wildcards) = match *node {
+            Node::Literal(ref text) => (text.chars().count(), 0),
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .map(|branch| specificity(branch))
+                .min_by_key(|&(branch_literal, branch_wildcards)| {
+                    (branch_literal, Reverse(branch_wildcards))
+                })
+                .map_or((0, 1), |(branch_literal, branch_wildcards)| {
+                    (branch_literal, branch_wildcards + 1)
+                }),
+            _ => (0, 1),
+        };
+        (literal + node_literal, wildcards + node_wildcards)
+    })
+}
+
+/// Expand a sequence of elements into all the strings it may match, if it
+/// only contains literal characters and alternations and if there are at most
+/// `max` such strings; otherwise, return `None`.
+#[cfg(feature = "regex")]
+pub(crate) fn finite_texts(nodes: &[Node], max: usize) -> Option<Vec<String>> {
+    nodes
+        .iter()
+        .try_fold(vec![String::new()], |prefixes, node| {
+            let choices = match *node {
+                Node::Literal(ref text) => vec![text.clone()],
+                Node::Alternation(ref branches) => {
+                    let mut res = Vec::new();
+                    for branch in branches {
+                        res.extend(finite_texts(branch, max)?);
+                        if res.len() > max {
+                            return None;
+                        }
+                    }
+                    res
+                }
+                _ => return None,
+            };
+            if prefixes.len().checked_mul(choices.len())? > max {
+                return None;
+            }
+            Some(
+                prefixes
+                    .iter()
+                    .cartesian_product(&choices)
+                    .map(|(prefix, choice)| format!("{}{}", prefix, choice))
+                    .collect(),
+            )
+        })
+}
+
+/// The byte offsets of an element within the original glob pattern,
+/// e.g. for highlighting its syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Span {
+    /// The offset of the first byte of the element.
+    pub start: usize,
+    /// The offset of the byte right after the element.
+    pub end: usize,
+}
+
+impl Span {
+    /// The range of bytes that the element occupies within the pattern,
+    /// e.g. for slicing the pattern string.
+    #[inline]
+    #[must_use]
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// A parsed glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GlobAst {
+    /// The elements of the pattern, in order.
+    pub nodes: Vec<Node>,
+    /// The byte offsets of the elements within the original pattern, one for
+    /// each of the `nodes`; the branches of an alternation are covered by
+    /// the span of the whole alternation. An element added by an option,
+    /// e.g. the implicit `*` of `treat_as_prefix`, has an empty span at
+    /// the end of the pattern. If the `unclosed_class_literal` option is
+    /// enabled, the spans after an unclosed `[` are off by one byte for each
+    /// such `[`, as are the positions of any errors.
+    pub spans: Vec<Span>,
+    /// Was the pattern anchored to the root by a leading slash?
+    /// This may only be true if the `root_relative` option was enabled.
+    pub anchored_root: bool,
+    /// May the pattern match at any directory level?
+    pub any_level: bool,
+    /// Was the `i` flag specified in a leading `(?flags)` group?
+    pub case_insensitive: bool,
+    /// Was the `s` flag specified in a leading `(?flags)` group?
+    pub dot_matches_newline: bool,
+}
+
+impl GlobAst {
+    /// Render the parsed pattern back as a glob pattern in a canonical form,
+    /// e.g. for detecting duplicate rules in a configuration file: the branches
+    /// of the alternations are sorted and deduplicated, the characters and
+    /// ranges in the character classes are merged and sorted, and only
+    /// the special characters are escaped. Thus e.g. `{b,a}` and `{a,b,a}`
+    /// are both rendered as `{a,b}`, and `[ca-b]` as `[a-c]`. The default
+    /// options are assumed, e.g. a slash separator and a backslash escape
+    /// character; see [`GlobAst::to_glob_string_with`].
+    ///
+    /// Only the syntax is normalized, so two patterns that match the same
+    /// text strings may still be rendered differently, e.g. `a{b,c}` and
+    /// `{ab,ac}`. The result should be parsed using the same options as
+    /// the original pattern, e.g. with `inline_flags` enabled if it starts
+    /// with a `(?i)` group.
+    #[inline]
+    #[must_use]
+    pub fn to_glob_string(&self) -> String {
+        self.to_glob_string_with(&GlobOptions::default())
+    }
+
+    /// Render the parsed pattern back as a glob pattern in a canonical form,
+    /// using the separator of the specified options for the `**` components
+    /// and for the leading one of a pattern anchored to the root, and their
+    /// escaping mechanism for the special characters: the `escape_char` one,
+    /// or else doubling them if `escape_by_doubling` is enabled, or else
+    /// a character class that only contains them, e.g. `[*]`.
+    #[inline]
+    #[must_use]
+    pub fn to_glob_string_with(&self, options: &GlobOptions) -> String {
+        let flags: String = [
+            (self.case_insensitive, 'i'),
+            (self.dot_matches_newline, 's'),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect();
+        let rendered = render_nodes(&self.nodes, options, false);
+        // A literal `(` that would start a flags group must be escaped.
+        let body = match rendered.strip_prefix('(') {
+            Some(rest)
+                if flags.is_empty() && !self.anchored_root && looks_like_flags(&rendered) =>
+            {
+                match options.escape_char {
+                    Some(escape) => format!("{}({}", escape, rest),
+                    None => format!("[(]{}", rest),
+                }
+            }
+            _ => rendered,
+        };
+        format!(
+            "{}{}{}",
+            if flags.is_empty() {
+                String::new()
+            } else {
+                format!("(?{})", flags)
+            },
+            if self.anchored_root {
+                render_separator(options)
+            } else {
+                String::new()
+            },
+            body
+        )
+    }
+}
+
+/// Check whether a pattern starts with something that looks like a leading
+/// `(?flags)` group, e.g. `(?i)`, if the `inline_flags` option is enabled.
+pub(crate) fn looks_like_flags(pattern: &str) -> bool {
+    pattern
+        .strip_prefix("(?")
+        .and_then(|rest| rest.split_once(')'))
+        .is_some_and(|(spec, _)| {
+            !spec.is_empty() && spec.chars().all(|chr| chr.is_ascii_alphabetic())
+        })
+}
+
+/// Render a separator character in glob syntax; it must be escaped if it is
+/// also the escape character, e.g. a backslash by default.
+fn render_separator(options: &GlobOptions) -> String {
+    let sep = options.separator;
+    if options.escape_char == Some(sep) {
+        format!("{}{}", sep, sep)
+    } else {
+        sep.to_string()
+    }
+}
+
+/// Render a literal character in glob syntax, escaping it if needed; within
+/// an alternation branch, commas and closing braces must also be escaped.
+fn render_literal_char(chr: char, options: &GlobOptions, branch: bool) -> String {
+    let special = "[]{}*?".contains(chr)
+        || options.escape_char == Some(chr)
+        || (branch && chr == ',')
+        || (options.dot_is_wildcard && chr == '.');
+    match options.escape_char {
+        _ if !special => chr.to_string(),
+        Some(escape) => format!("{}{}", escape, chr),
+        None if options.escape_by_doubling && "*?[{".contains(chr) => format!("{}{}", chr, chr),
+        // Only special within an alternation branch.
+        None if chr == ']' || (chr == '}' && !branch) => chr.to_string(),
+        None => format!("[{}]", chr),
+    }
+}
+
+/// Render a sequence of elements in glob syntax; within an alternation
+/// branch, commas must also be escaped. Outside of one, a wildcard is
+/// enclosed in braces if it would otherwise be taken together with what
+/// follows it, e.g. `{?}?` if `escape_by_doubling` is enabled. Adjacent
+/// stars match the same texts as a single one, so they are rendered as
+/// a single one, lest `**` be taken as a globstar component.
+fn render_nodes(nodes: &[Node], options: &GlobOptions, branch: bool) -> String {
+    let collapsed: Vec<&Node> = nodes
+        .iter()
+        .coalesce(|prev, next| match (prev, next) {
+            (&Node::Star, &(Node::Star | Node::StarTail)) => Ok(next),
+            _ => Err((prev, next)),
+        })
+        .collect();
+    let rendered: Vec<String> = collapsed
+        .iter()
+        .map(|node| match **node {
+            Node::Literal(ref text) => text
+                .chars()
+                .map(|chr| render_literal_char(chr, options, branch))
+                .collect(),
+            Node::AnyChar => "?".to_owned(),
+            Node::AnyChars(count) => format!("?{{{}}}", count),
+            Node::Star | Node::StarTail => "*".to_owned(),
+            Node::GlobstarDirs => format!("**{}", render_separator(options)),
+            Node::GlobstarTail => "**".to_owned(),
+            Node::Class(ref class) => render_class(class, options.escape_char),
+            Node::Alternation(ref branches) => {
+                let items: Vec<String> = branches
+                    .iter()
+                    .map(|branch_nodes| render_nodes(branch_nodes, options, true))
+                    .sorted_unstable()
+                    .dedup()
+                    .collect();
+                format!("{{{}}}", items.join(","))
+            }
+        })
+        .collect();
+    rendered
+        .iter()
+        .zip(collapsed)
+        .enumerate()
+        .map(|(idx, (text, node))| {
+            let next = rendered.get(idx + 1).and_then(|other| other.chars().next());
+            let merged = match *node {
+                Node::AnyChar => {
+                    (options.escape_by_doubling && next == Some('?'))
+                        || (options.counted_repetition && next == Some('{'))
+                }
+                Node::Star => options.escape_by_doubling && next == Some('*'),
+                _ => false,
+            };
+            if merged && !branch {
+                format!("{{{}}}", text)
+            } else {
+                text.clone()
+            }
+        })
+        .collect()
+}
+
+/// Render the merged characters and ranges of a character class without
+/// an escape character: a `]` must come first, a `!` or `^` may not come
+/// first, and a `-` must come first or last, or right after a named class.
+fn render_class_ranges_unescaped(ranges: &[(u32, u32)], named: &str) -> String {
+    let placed = ['!', '-', ']', '^'].map(u32::from);
+    let has = |chr: char| {
+        ranges
+            .iter()
+            .any(|&(start, end)| start <= u32::from(chr) && u32::from(chr) <= end)
+    };
+    let render = |code: u32| char::from_u32(code).map_or_else(String::new, String::from);
+    let pieces: Vec<String> = ranges
+        .iter()
+        .flat_map(|&(start, end)| {
+            // Split the range around the characters that are placed apart.
+            let mut bounds: Vec<u32> = placed
+                .iter()
+                .copied()
+                .filter(|&code| start <= code && code <= end)
+                .collect();
+            bounds.push(end.saturating_add(1));
+            let mut from = start;
+            bounds
+                .into_iter()
+                .filter_map(move |stop| {
+                    let span = (from < stop).then_some((from, stop - 1));
+                    from = stop.saturating_add(1);
+                    span
+                })
+                .collect::<Vec<_>>()
+        })
+        .map(|(start, end)| match end - start {
+            0 => render(start),
+            1 => format!("{}{}", render(start), render(end)),
+            _ => format!("{}-{}", render(start), render(end)),
+        })
+        .collect();
+    let mut first = String::new();
+    let mut dash_first = false;
+    if has(']') {
+        first.push(']');
+    } else if has('-') {
+        first.push('-');
+        dash_first = true;
+    }
+    let mut rest = pieces.into_iter();
+    if first.is_empty() {
+        first.extend(rest.next());
+    }
+    let negators: String = ['!', '^'].into_iter().filter(|&chr| has(chr)).collect();
+    let dash = if has('-') && !dash_first { "-" } else { "" };
+    format!(
+        "{}{}{}{}{}",
+        first,
+        negators,
+        rest.collect::<String>(),
+        named,
+        dash
+    )
+}
+
+/// Render a character class in glob syntax with its characters and ranges
+/// merged and sorted, followed by its named classes and its intersections.
+fn render_class(class: &Class, escape_char: Option<char>) -> String {
+    let ranges = merge_ranges(
+        class
+            .items
+            .iter()
+            .filter_map(|item| match *item {
+                ClassItem::Char(chr) => Some((u32::from(chr), u32::from(chr))),
+                ClassItem::Range(start, end) => Some((u32::from(start), u32::from(end))),
+                ClassItem::Named { .. } => None,
+            })
+            .collect(),
+    );
+    let named: String = class
+        .items
+        .iter()
+        .filter_map(|item| match *item {
+            ClassItem::Named { ref name, negated } => {
+                Some(format!("[:{}{}:]", if negated { "^" } else { "" }, name))
+            }
+            _ => None,
+        })
+        .sorted_unstable()
+        .dedup()
+        .collect();
+    let intersections: String = class
+        .intersections
+        .iter()
+        .map(|nested| format!("&&{}", render_class(nested, escape_char)))
+        .sorted_unstable()
+        .dedup()
+        .collect();
+    let items = match escape_char {
+        Some(escape_with) => {
+            let escape = |code: u32| {
+                char::from_u32(code).map_or_else(String::new, |chr| {
+                    if "!^-[]&".contains(chr) || chr == escape_with {
+                        format!("{}{}", escape_with, chr)
+                    } else {
+                        chr.to_string()
+                    }
+                })
+            };
+            let rendered: String = ranges
+                .into_iter()
+                .map(|(start, end)| match end - start {
+                    0 => escape(start),
+                    1 => format!("{}{}", escape(start), escape(end)),
+                    _ => format!("{}-{}", escape(start), escape(end)),
+                })
+                .collect();
+            format!("{}{}", rendered, named)
+        }
+        None => render_class_ranges_unescaped(&ranges, &named),
+    };
+    format!(
+        "[{}{}{}]",
+        if class.negated { "!" } else { "" },
+        items,
+        intersections
+    )
+}
+
+/// The highest Unicode code point, the end of the full range of characters.
+#[cfg(feature = "regex")]
+const MAX_CODE_POINT: u32 = 0x0010_FFFF;
+
+/// Sort a list of inclusive code point ranges and merge the overlapping or
+/// adjacent ones.
+pub(crate) fn merge_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut res: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match res.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => res.push((start, end)),
+        }
+    }
+    res
+}
+
+/// Get the code points not covered by a sorted list of merged ranges.
+#[cfg(feature = "regex")]
+fn complement_ranges(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut res = Vec::with_capacity(ranges.len() + 1);
+    let mut next = 0_u32;
+    for &(start, end) in ranges {
+        if start > next {
+            res.push((next, start - 1));
+        }
+        next = end.saturating_add(1);
+    }
+    if next <= MAX_CODE_POINT {
+        res.push((next, MAX_CODE_POINT));
+    }
+    res
+}
+
+/// Get the code points covered by both lists of ranges.
+#[cfg(feature = "regex")]
+fn intersect_ranges(first: &[(u32, u32)], second: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    merge_ranges(
+        first
+            .iter()
+            .flat_map(|&(start, end)| {
+                second.iter().filter_map(move |&(other_start, other_end)| {
+                    let (low, high) = (start.max(other_start), end.min(other_end));
+                    (low <= high).then_some((low, high))
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Get the code points that a character class matches, or `None` if it or
+/// any of its intersections contain named classes, which are not analyzed.
+#[cfg(feature = "regex")]
+fn class_ranges(class: &Class) -> Option<Vec<(u32, u32)>> {
+    let listed = merge_ranges(
+        class
+            .items
+            .iter()
+            .map(|item| match *item {
+                ClassItem::Char(chr) => Some((u32::from(chr), u32::from(chr))),
+                ClassItem::Range(start, end) => Some((u32::from(start), u32::from(end))),
+                ClassItem::Named { .. } => None,
+            })
+            .collect::<Option<_>>()?,
+    );
+    let own = if class.negated {
+        complement_ranges(&listed)
+    } else {
+        listed
+    };
+    class.intersections.iter().try_fold(own, |acc, nested| {
+        Some(intersect_ranges(&acc, &class_ranges(nested)?))
+    })
+}
+
+/// Check whether a sequence of elements can certainly never match anything,
+/// e.g. because of a character class that only contains the separator or
+/// intersections that have no characters in common; `start` is set if
+/// the sequence is at the start of a path component.
+#[cfg(feature = "regex")]
+pub(crate) fn unsatisfiable(nodes: &[Node], options: &GlobOptions, start: bool) -> bool {
+    let sep = options.separator;
+    let mut segment_start = start;
+    for node in nodes {
+        let dead = match *node {
+            Node::Class(ref class) => class_ranges(class).is_some_and(|ranges| {
+                let exclude: Vec<(u32, u32)> = (segment_start && options.literal_leading_dot)
+                    .then_some('.')
+                    .into_iter()
+                    .chain(
+                        (!options.treat_slash_as_ordinary && !options.classes_match_separator)
+                            .then_some(sep),
+                    )
+                    .map(|chr| (u32::from(chr), u32::from(chr)))
+                    .collect();
+                intersect_ranges(&ranges, &complement_ranges(&merge_ranges(exclude))).is_empty()
+            }),
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .all(|branch| unsatisfiable(branch, options, segment_start)),
+            _ => false,
+        };
+        if dead {
+            return true;
+        }
+        segment_start = match *node {
+            Node::Literal(ref text) if text.is_empty() => segment_start,
+            Node::Literal(ref text) => text.ends_with(sep),
+            Node::AnyChars(0) => segment_start,
+            Node::GlobstarDirs => true,
+            _ => false,
+        };
+    }
+    false
+}
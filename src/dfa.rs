@@ -0,0 +1,116 @@
+//! Match glob patterns using a precompiled deterministic automaton.
+//!
+//! If the `dfa` feature is enabled, the [`crate::Glob::to_dfa`] method
+//! compiles the regular expression built for a glob pattern into a dense
+//! deterministic finite automaton using the `regex-automata` crate:
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::Glob;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let dfa = Glob::new("src/{main,lib}.rs")?.to_dfa()?;
+//! assert!(dfa.is_match("src/lib.rs"));
+//! assert!(!dfa.is_match("src/mod.rs"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The automaton is built in full up front, so matching a text string only
+//! needs a single pass over its bytes with no further bookkeeping; this may
+//! be faster than [`crate::Glob::is_match`] in a hot loop that matches
+//! a lot of text strings against the same pattern. The trade-off is that
+//! building a dense automaton may take a lot longer than compiling
+//! the regular expression, and it may need a lot more memory, especially
+//! for patterns with many wildcards and Unicode character classes; thus
+//! the feature is not enabled by default, and the automaton is only built
+//! on request.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use regex_automata::dfa::regex::Regex as DfaRegex;
+use regex_automata::util::syntax;
+
+use crate::error::Error as FError;
+use crate::matcher::{self, Glob};
+use crate::options::GlobOptions;
+
+/// A glob pattern compiled into a dense deterministic automaton.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct GlobDfa {
+    /// The glob pattern as specified.
+    pattern: String,
+    /// The automaton built for the pattern's regular expression.
+    regex: DfaRegex,
+    /// The options the pattern was compiled with, e.g. the normalization
+    /// form that the text strings are converted to.
+    options: GlobOptions,
+}
+
+impl GlobDfa {
+    /// Build the automaton for an already compiled glob pattern.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::InvalidRegex`] if the automaton could not be
+    /// built, e.g. if it would exceed the configured size limits.
+    #[inline]
+    pub fn new(glob: &Glob) -> Result<Self, FError> {
+        let source = glob.as_regex_str();
+        let regex = DfaRegex::builder()
+            .syntax(syntax::Config::new().unicode(glob.options().unicode))
+            .build(source)
+            .map_err(|err| {
+                FError::InvalidRegex(
+                    glob.pattern().to_owned(),
+                    source.to_owned(),
+                    err.to_string(),
+                )
+            })?;
+        Ok(Self {
+            pattern: glob.pattern().to_owned(),
+            regex,
+            options: *glob.options(),
+        })
+    }
+
+    /// The glob pattern as specified.
+    #[inline]
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Check whether the pattern matches the specified text, converting it
+    /// to a normalization form first if the `normalize` option is set.
+    #[inline]
+    #[must_use]
+    pub fn is_match<T: AsRef<str>>(&self, text: T) -> bool {
+        self.regex
+            .is_match(&*matcher::normalized(text.as_ref(), &self.options))
+    }
+}
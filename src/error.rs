@@ -26,40 +26,188 @@
  * SUCH DAMAGE.
  */
 
-use quick_error::quick_error;
+use std::error::Error as StdError;
+use std::fmt;
 
-quick_error! {
-    /// An error that occurred during the processing of a pattern.
-    #[derive(Debug)]
-    #[non_exhaustive]
-    pub enum Error {
-        /// A bare escape character at the end of the pattern.
-        BareEscape {
-            display("Bare escape character")
-        }
-        /// The resulting regex was invalid.
-        InvalidRegex(pattern: String, error: String) {
-            display("Could not compile the resulting pattern {:?}: {}", pattern, error)
-        }
-        /// Some known missing functionality.
-        NotImplemented(message: String) {
-            display("Not implemented yet: {}", message)
-        }
-        /// An invalid combination of ranges ([a-b-c]) within a character class.
-        RangeAfterRange(start: char, end: char) {
-            display("Range following a {:?}-{:?} range", start, end)
-        }
-        /// A reversed range within a character class.
-        ReversedRange(start: char, end: char) {
-            display("Reversed range from {:?} to {:?}", start, end)
+/// An error that occurred during the processing of a pattern.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A bare escape character at the end of the pattern.
+    BareEscape,
+    /// A bare escape character at the end of the pattern within
+    /// an unclosed character class, e.g. `[abc\`.
+    BareEscapeInClass,
+    /// Some of the specified options may not be used together.
+    ConflictingOptions(&'static str),
+    /// A `*` or `?` wildcard at the specified character position within
+    /// the pattern, while the `disallow_wildcards` option is enabled.
+    DisallowedWildcard(char, usize),
+    /// A character class, starting at the specified character position
+    /// within the pattern, that would never match anything, e.g. `[/]`.
+    EmptyClass(usize),
+    /// Unknown flags in a leading `(?flags)` group.
+    InvalidFlags(String),
+    /// An invalid `?{N}` repetition count.
+    InvalidRepetition(String),
+    /// The resulting regex was invalid: the glob pattern, the regex, and
+    /// the regex engine's error message.
+    InvalidRegex(String, String, String),
+    /// The `single_char_class` option does not look like a single
+    /// regular expression character class, e.g. `[a-z]` or `\w`, or it
+    /// is too long to be stored.
+    InvalidSingleCharClass(String),
+    /// A brace alternation, starting at the specified character position
+    /// within the pattern, that is nested too deeply within other ones.
+    NestingTooDeep(usize),
+    /// An ad-hoc error message, e.g. one reported by a downstream crate.
+    Other(String),
+    /// An invalid combination of ranges ([a-b-c]) within a character class.
+    RangeAfterRange(char, char),
+    /// A reversed range within a character class starting at
+    /// the specified character position within the pattern.
+    ReversedRange(char, char, usize),
+    /// The brace expansion would produce too many patterns.
+    TooManyExpansions(usize),
+    /// An alternation, starting at the specified character position
+    /// within the pattern, that was not closed before its end.
+    UnclosedAlternation(usize),
+    /// A character class, starting at the specified character position
+    /// within the pattern, that was not closed before its end.
+    UnclosedClass(usize),
+    /// An unknown name of a POSIX character class or a Unicode general
+    /// category within a character class, e.g. `[[:nosuch:]]`.
+    UnknownClass(String),
+    /// Some known missing functionality, identified by a stable name in
+    /// the `feature` field, e.g. `nested_alternation` or
+    /// `class_items_after_intersection`.
+    Unsupported {
+        /// The stable name of the missing functionality.
+        feature: &'static str,
+    },
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::BareEscape => write!(f, "Bare escape character"),
+            Self::BareEscapeInClass => write!(f, "Bare escape character in a character class"),
+            Self::ConflictingOptions(message) => write!(f, "Conflicting options: {}", message),
+            Self::DisallowedWildcard(wildcard, position) => write!(
+                f,
+                "The {:?} wildcard at position {} is not allowed",
+                wildcard, position
+            ),
+            Self::EmptyClass(position) => write!(
+                f,
+                "The character class at position {} would never match anything",
+                position
+            ),
+            Self::InvalidFlags(ref flags) => write!(f, "Invalid inline flags {:?}", flags),
+            Self::InvalidRepetition(ref spec) => write!(f, "Invalid repetition count {:?}", spec),
+            Self::InvalidRegex(ref glob, ref pattern, ref error) => write!(
+                f,
+                "Glob `{}` produced invalid regex `{}`: {}",
+                glob, pattern, error
+            ),
+            Self::InvalidSingleCharClass(ref class) => {
+                write!(f, "Invalid single character class {:?}", class)
+            }
+            Self::NestingTooDeep(position) => write!(
+                f,
+                "The alternation at position {} is nested too deeply",
+                position
+            ),
+            Self::Other(ref message) => write!(f, "{}", message),
+            Self::RangeAfterRange(start, end) => {
+                write!(f, "Range following a {:?}-{:?} range", start, end)
+            }
+            Self::ReversedRange(start, end, position) => write!(
+                f,
+                "Reversed range from {:?} to {:?} in the character class at position {}",
+                start, end, position
+            ),
+            Self::TooManyExpansions(max) => write!(
+                f,
+                "Brace expansion would produce more than {} patterns",
+                max
+            ),
+            Self::UnclosedAlternation(position) => {
+                write!(f, "Unclosed alternation at position {}", position)
+            }
+            Self::UnclosedClass(position) => {
+                write!(f, "Unclosed character class at position {}", position)
+            }
+            Self::UnknownClass(ref name) => write!(f, "Unknown character class name {:?}", name),
+            Self::Unsupported { feature } => write!(f, "Unsupported feature: {}", feature),
         }
-        /// An alternation that was not closed before the end of the pattern.
-        UnclosedAlternation {
-            display("Unclosed alternation")
+    }
+}
+
+impl StdError for Error {}
+
+impl Error {
+    /// Build an error with an ad-hoc message, so that downstream crates may
+    /// report their own problems using the same error type.
+    #[inline]
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Self::Other(message.into())
+    }
+
+    /// The character position within the pattern that the error refers to,
+    /// if known, e.g. that of the `[` starting an unclosed character class.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> Option<usize> {
+        match *self {
+            Self::DisallowedWildcard(_, position)
+            | Self::EmptyClass(position)
+            | Self::NestingTooDeep(position)
+            | Self::ReversedRange(_, _, position)
+            | Self::UnclosedAlternation(position)
+            | Self::UnclosedClass(position) => Some(position),
+            _ => None,
         }
-        /// A character class that was not closed before the end of the pattern.
-        UnclosedClass {
-            display("Unclosed character class")
+    }
+
+    /// The error message without the position, e.g. for comparing errors
+    /// or for reporting them in a different format.
+    #[inline]
+    #[must_use]
+    pub fn message(&self) -> String {
+        match *self {
+            Self::DisallowedWildcard(wildcard, _) => {
+                format!("The {:?} wildcard is not allowed", wildcard)
+            }
+            Self::EmptyClass(_) => "The character class would never match anything".to_owned(),
+            Self::NestingTooDeep(_) => "The alternation is nested too deeply".to_owned(),
+            Self::ReversedRange(start, end, _) => {
+                format!("Reversed range from {:?} to {:?}", start, end)
+            }
+            Self::UnclosedAlternation(_) => "Unclosed alternation".to_owned(),
+            Self::UnclosedClass(_) => "Unclosed character class".to_owned(),
+            _ => self.to_string(),
         }
     }
+
+    /// Build an error for a regular expression that could not be compiled,
+    /// along with the glob pattern that it was built from.
+    #[cfg(feature = "regex")]
+    #[inline]
+    #[must_use]
+    pub fn invalid_regex(glob: &str, pattern: &str, err: &regex::Error) -> Self {
+        Self::InvalidRegex(glob.to_owned(), pattern.to_owned(), err.to_string())
+    }
+}
+
+/// Since a [`regex::Error`] does not carry the pattern that could not be
+/// compiled, it is converted to an [`Error::Other`] one with the same message;
+/// use [`Error::invalid_regex`] if the pattern is known.
+#[cfg(feature = "regex")]
+impl From<regex::Error> for Error {
+    #[inline]
+    fn from(err: regex::Error) -> Self {
+        Self::Other(err.to_string())
+    }
 }
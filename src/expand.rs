@@ -0,0 +1,195 @@
+//! Expand the brace alternations in a glob pattern.
+//!
+//! The [`expand_braces`] function performs the shell-like expansion of
+//! all the `{a,b,c}` alternations in a pattern, including nested ones,
+//! and returns the resulting glob patterns in the order that a shell
+//! would produce them:
+//!
+//! ```rust
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let patterns = fnmatch_regex::expand_braces("img-[0-9]*.{png,jp{e,}g}")?;
+//! assert_eq!(patterns, ["img-[0-9]*.png", "img-[0-9]*.jpeg", "img-[0-9]*.jpg"]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The wildcards, character classes, and escaped characters are left
+//! intact, so each of the returned strings is itself a glob pattern.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use std::iter::Peekable;
+use std::mem;
+
+use itertools::Itertools;
+
+use crate::error::Error as FError;
+
+/// The default maximum number of patterns that [`expand_braces`] will produce.
+pub const DEFAULT_MAX_BRACE_EXPANSIONS: usize = 10_000;
+
+/// The maximum nesting depth of the brace alternations that [`expand_braces`]
+/// will handle.
+pub const MAX_BRACE_NESTING: usize = 64;
+
+/// Copy an escaped character to the output verbatim.
+fn copy_escape<I>(pattern: &mut I, res: &mut String) -> Result<(), FError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let (_, chr) = pattern.next().ok_or(FError::BareEscape)?;
+    res.push('\\');
+    res.push(chr);
+    Ok(())
+}
+
+/// Copy a character class to the output verbatim; the `[` at the specified
+/// position was already copied.
+fn copy_class<I>(pattern: &mut Peekable<I>, start: usize, res: &mut String) -> Result<(), FError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    if let Some((_, chr)) = pattern.next_if(|&(_, chr)| chr == '!') {
+        res.push(chr);
+    }
+    if let Some((_, chr)) = pattern.next_if(|&(_, chr)| chr == ']') {
+        res.push(chr);
+    }
+    loop {
+        match pattern.next() {
+            Some((_, '\\')) => {
+                copy_escape(pattern, res).map_err(|_| FError::UnclosedClass(start))?;
+            }
+            Some((_, ']')) => {
+                res.push(']');
+                return Ok(());
+            }
+            Some((_, chr)) => res.push(chr),
+            None => return Err(FError::UnclosedClass(start)),
+        }
+    }
+}
+
+/// Append each of the alternatives to each of the already expanded prefixes.
+fn product(
+    prefixes: &[String],
+    alternatives: &[String],
+    max: usize,
+) -> Result<Vec<String>, FError> {
+    match prefixes.len().checked_mul(alternatives.len()) {
+        Some(total) if total <= max => Ok(prefixes
+            .iter()
+            .cartesian_product(alternatives)
+            .map(|(prefix, alt)| format!("{}{}", prefix, alt))
+            .collect()),
+        _ => Err(FError::TooManyExpansions(max)),
+    }
+}
+
+/// Expand a sequence of literal text and alternations.
+/// Return the expanded strings and the character that ended the sequence:
+/// a comma or a closing brace if `nested` holds the position of the `{`
+/// that started the alternation, or `None` at the end of the pattern.
+/// The `depth` is the number of alternations that enclose the sequence.
+fn expand_seq<I>(
+    pattern: &mut Peekable<I>,
+    nested: Option<usize>,
+    depth: usize,
+    max: usize,
+) -> Result<(Vec<String>, Option<char>), FError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let mut expanded = vec![String::new()];
+    let mut current = String::new();
+    let end = loop {
+        match pattern.next() {
+            None => break None,
+            Some((_, chr @ (',' | '}'))) if nested.is_some() => break Some(chr),
+            Some((_, '\\')) => copy_escape(pattern, &mut current)
+                .map_err(|err| nested.map_or(err, FError::UnclosedAlternation))?,
+            Some((start, '[')) => {
+                current.push('[');
+                copy_class(pattern, start, &mut current)?;
+            }
+            Some((_, '{')) if pattern.next_if(|&(_, chr)| chr == '}').is_some() => {
+                current.push_str("{}");
+            }
+            Some((start, '{')) if depth >= MAX_BRACE_NESTING => {
+                return Err(FError::NestingTooDeep(start));
+            }
+            Some((start, '{')) => {
+                let mut alternatives = Vec::new();
+                loop {
+                    let (mut branch, branch_end) =
+                        expand_seq(pattern, Some(start), depth + 1, max)?;
+                    alternatives.append(&mut branch);
+                    match branch_end {
+                        Some('}') => break,
+                        Some(_) => {}
+                        None => return Err(FError::UnclosedAlternation(start)),
+                    }
+                }
+                let prefixes = product(&expanded, &[mem::take(&mut current)], max)?;
+                expanded = product(&prefixes, &alternatives, max)?;
+            }
+            Some((_, chr)) => current.push(chr),
+        }
+    };
+    Ok((product(&expanded, &[current], max)?, end))
+}
+
+/// Expand all the brace alternations in a glob pattern.
+///
+/// See the module-level documentation for more information.
+///
+/// # Errors
+/// [`crate::error::Error::TooManyExpansions`] if the pattern would expand to
+/// more than [`DEFAULT_MAX_BRACE_EXPANSIONS`] strings;
+/// [`crate::error::Error::NestingTooDeep`] if the alternations are nested
+/// more than [`MAX_BRACE_NESTING`] levels deep; syntax errors in
+/// the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn expand_braces(pattern: &str) -> Result<Vec<String>, FError> {
+    expand_braces_limit(pattern, DEFAULT_MAX_BRACE_EXPANSIONS)
+}
+
+/// Expand all the brace alternations in a glob pattern, producing at most
+/// `max` strings.
+///
+/// # Errors
+/// [`crate::error::Error::TooManyExpansions`] if the pattern would expand to
+/// more than `max` strings; [`crate::error::Error::NestingTooDeep`] if
+/// the alternations are nested more than [`MAX_BRACE_NESTING`] levels deep;
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn expand_braces_limit(pattern: &str, max: usize) -> Result<Vec<String>, FError> {
+    let (expanded, _) = expand_seq(&mut pattern.chars().enumerate().peekable(), None, 0, max)?;
+    Ok(expanded)
+}
@@ -2,27 +2,170 @@
 //!
 //! The glob-style pattern features currently supported are:
 //! - any character except `?`, `*`, `[`, `\`, or `{` is matched literally
-//! - `?` matches any single character except a slash (`/`)
+//! - `?` matches any single character except a slash (`/`); if
+//!   the `counted_repetition` option is enabled, `?{N}` matches exactly
+//!   `N` such characters
 //! - `*` matches any sequence of zero or more characters that does not
 //!   contain a slash (`/`)
+//! - if the `globstar` option is enabled, a `**` path component matches
+//!   any number of directory levels, possibly limited by
+//!   the `max_globstar_depth` option; otherwise it behaves like `*`.
+//!   A leading `**/` or a `/**/` in the middle may match zero levels, e.g.
+//!   `**/foo` matches `foo` and `a/b/foo`; a trailing `/**` only matches
+//!   the directory itself, e.g. `foo/**` matching `foo`, if
+//!   the `collapse_globstar_edges` option is also enabled
 //! - a backslash allows the next character to be matched literally, except
 //!   for the `\a`, `\b`, `\e`, `\n`, `\r`, and `\v` sequences
 //! - a `[...]` character class supports ranges, negation if the very first
 //!   character is `!`, backslash-escaping, and also matching
 //!   a `]` character if it is the very first character possibly after
-//!   the `!` one (e.g. `[]]` would only match a single `]` character)
-//! - an `{a,bbb,cc}` alternation supports backslash-escaping, but not
-//!   nested alternations or character classes yet
+//!   the `!` one (e.g. `[]]` would only match a single `]` character);
+//!   thus `[]` and `[!]` do not close the class, and if no other `]`
+//!   follows, they are reported as an `UnclosedClass` error
+//! - a character class may also contain POSIX named classes, e.g.
+//!   `[[:alpha:]_]`, that only match ASCII characters, and Unicode general
+//!   categories, e.g. `[[:Letter:][:Nd:]]`, either of them negated by
+//!   a `^` before the name, e.g. `[[:^digit:]]`; an unknown name is
+//!   reported as an `UnknownClass` error
+//! - if the `class_intersection` option is enabled, a character class may
+//!   end with one or more `&&[...]` intersections, e.g. `[a-z&&[^aeiou]]`
+//!   would only match lowercase consonants; the nested classes may be
+//!   negated by `^` or `!` and they may contain characters, ranges, and
+//!   escaped characters, but no named or further nested classes
+//! - an `{a,bbb,cc}` alternation supports backslash-escaping; each branch
+//!   may contain wildcards, character classes, `?{N}` repetition counts,
+//!   and empty `{}` brace pairs, e.g. `{*.txt,README}`, but not nested
+//!   alternations yet
+//! - an empty `{}` brace pair is matched literally, unless the `empty_brace`
+//!   option is set to [`crate::options::EmptyBrace::Empty`], in which case
+//!   it matches the empty string, e.g. `a{}b` would only match `ab`
+//! - if the `inline_flags` option is enabled, the pattern may start with
+//!   a `(?i)`, `(?s)`, or `(?is)` group that makes the whole regular
+//!   expression case-insensitive or lets the `.*` constructs used for
+//!   `**` and for matching at any level also match newline characters;
+//!   the group is only recognized at the very start of the pattern, before
+//!   any leading slash, and a `(` anywhere else is matched literally;
+//!   a group of other letters, e.g. `(?x)`, is reported as an error, while
+//!   one that does not consist of letters only, e.g. `(?)`, is not treated
+//!   as a group at all
 //!
 //! Note that the `*` and `?` wildcard patterns, as well as the character
-//! classes, will never match a slash.
+//! classes, will never match a slash unless the `treat_slash_as_ordinary`
+//! option is enabled; the `classes_match_separator` option only lets
+//! the character classes match it. Thus, by default, `[!a-z]` and `[!a-z/]`
+//! are equivalent, neither of them matching a slash; if the character
+//! classes may match a slash, an explicitly listed one is still honored, so
+//! `[!a-z]` would match a slash, but `[!a-z/]` would not.
+//!
+//! Thus the patterns that only consist of wildcards match whole path
+//! components: `*` matches a single component, even an empty one, e.g. `a`
+//! or an empty string, but not `a/b`; `?` matches a single character that is
+//! not a slash; `*/*` matches exactly two components, e.g. `a/b`, but also
+//! `a/` or even `/`. A lone `**` behaves like `*` unless the `globstar`
+//! option is enabled, in which case it matches any path at all, except that,
+//! like the other `**` components, it does not match a newline character
+//! unless the `(?s)` flag is set.
+//!
+//! The `separator` option specifies a different path separator character
+//! that all the rules above and below apply to instead of the slash, e.g.
+//! `:` or `\`; since the backslash is still the escape character by default,
+//! a backslash separator must be written as `\\` in the pattern, both at
+//! the top level and within an alternation, e.g. `src\\*.{c,h}` or
+//! `{a\\b,c}`.
+//!
+//! If the `escape_by_doubling` option is enabled, a `*`, `?`, `[`, or `{`
+//! character outside of a character class may also be matched literally by
+//! doubling it, e.g. `a??` would only match `a?` and `{{*}` would match `{a`;
+//! since `**` is then a literal `*`, the option may not be used together
+//! with the `globstar` one.
+//!
+//! If the `escape_char` option is set to a different character, e.g. `%`,
+//! that character is used for escaping instead of the backslash, e.g. `100%%`
+//! would only match `100%` and `%*` would only match `*`, while a backslash is
+//! matched literally; if it is set to `None`, there is no escape character at
+//! all, as with the `FNM_NOESCAPE` flag of `fnmatch(3)`. The escape character
+//! may not be a glob special character, e.g. `*` or `{`.
+//!
+//! If the `optional_class` option is enabled, a `?` right after the end of
+//! a character class makes the class optional instead of matching any
+//! character, e.g. `v[0-9]?` would match `v` and `v5`, but not `v5a`;
+//! a `?` wildcard may still follow a class if it is enclosed in braces,
+//! e.g. `v[0-9]{?}`.
+//!
+//! If the `optional_trailing_separator` option is enabled, a text string
+//! that ends with a slash is also matched as if it did not, e.g. `foo/bar`
+//! would match both `foo/bar` and `foo/bar/`, so that the matching does not
+//! depend on whether directory paths are normalized; this has no effect if
+//! the pattern may match anywhere within the text string.
+//!
+//! If the `unclosed_class_literal` option is enabled, a `[` that does not
+//! start a properly closed character class is matched literally instead of
+//! being reported as an `UnclosedClass` error, e.g. `file[1.txt` would only
+//! match `file[1.txt`, as in many shells.
+//!
+//! If the `single_char_class` option is set to a regular expression character
+//! class, e.g. `[A-Za-z0-9_]` or `\w`, the `?` wildcard matches a single
+//! character of that class instead, e.g. `id_??` would match `id_a1`, but not
+//! `id_a-`; the class is used as it is, so it may even match a slash.
+//!
+//! If the `dot_is_wildcard` option is enabled, a `.` in the pattern matches
+//! any single character, like the `?` wildcard, e.g. `1.2.3` would match both
+//! `1.2.3` and `1x2y3`; this departs from the usual glob semantics, but may be
+//! useful for matching e.g. version strings. An escaped `\.` still only
+//! matches a dot. This option may not be used together with
+//! the `literal_leading_dot` one.
+//!
+//! If the `disallow_wildcards` option is enabled, an unescaped `*` or `?`
+//! wildcard anywhere in the pattern, even within an alternation, is reported
+//! as a `DisallowedWildcard` error along with its position, so that only
+//! literal text, character classes, and alternations may be used, e.g.
+//! `[a-z].txt` or `{a,b}.txt`, but not `*.txt`; this may be useful for
+//! configuration files where catch-all rules should not be allowed.
+//!
+//! If the `trailing_star_is_recursive` option is enabled, a `*` at the very
+//! end of the pattern also matches slashes, so that e.g. `logs/*` would match
+//! both `logs/a` and `logs/a/b`; a `*` anywhere else, e.g. in `*/*.log` or
+//! in `{a,b*}`, still only matches within a path component.
+//!
+//! If the `treat_as_prefix` option is enabled, the pattern is treated as if
+//! an implicit `*` were appended to it, so that e.g. `abc` would match any text
+//! that starts with `abc`, such as `abcdef`; as with any other `*`, the implicit
+//! one does not match a slash, so `abc` would not match `abc/def`. A pattern
+//! that already ends with a `*` is not changed.
+//!
+//! If the `literal_leading_dot` option is enabled, a dot at the start of
+//! a path component, e.g. in `.git` or `src/.hidden`, may only be matched by
+//! a literal dot at the start of a pattern component, so that the wildcards
+//! skip hidden files: `*.txt` would match `a.txt`, but not `.txt`, while
+//! `.*` would match `.txt`, and `**/*` would not match `.git/config`.
+//!
+//! By default, the pattern must match the whole text string, e.g. `foo`
+//! will only match `foo`, but not `a/foo`; if the `anchoring` option is set
+//! to [`crate::options::Anchoring::Unanchored`], the pattern may match
+//! anywhere within the text string, and if it is set to
+//! [`crate::options::Anchoring::Start`], the pattern must match at the start
+//! of the text string, but anything may follow, e.g. `ERROR*` would match
+//! `ERROR: boom`, but not `WARN: ERROR`. If the `match_at_any_level`
+//! option is enabled, a pattern that contains no slashes, except possibly
+//! a trailing one, may match at any directory level, e.g. `*.log` would
+//! match both `app.log` and `sub/dir/app.log`; a pattern that contains
+//! a slash, e.g. `a/*.log`, must still match the whole text string.
+//!
+//! If the `root_relative` option is enabled, the pattern is treated
+//! similarly to a gitignore rule:
+//! - a pattern with a leading slash, e.g. `/foo`, is anchored to the root;
+//!   the slash is stripped, so it will only match `foo`
+//! - a pattern with no other slashes except possibly a trailing one,
+//!   e.g. `foo`, may match at any directory level, e.g. `foo` or `a/b/foo`
+//! - a pattern with a slash in the middle, e.g. `a/foo`, is relative to
+//!   the root, so it will only match `a/foo`, but not `b/a/foo`
 //!
 //! Examples:
 //! - `abc.txt` would only match `abc.txt`
 //! - `foo/test?.txt` would match e.g. `foo/test1.txt` or `foo/test".txt`,
 //!   but not `foo/test/.txt`
 //! - `/etc/c[--9].conf` would match e.g. `/etc/c-.conf`, `/etc/c..conf`,
-//!    or `/etc/7.conf`, but not `/etc/c/.conf`
+//!   or `/etc/7.conf`, but not `/etc/c/.conf`
 //! - `linux-[0-9]*-{generic,aws}` would match `linux-5.2.27b1-generic`
 //!   and `linux-4.0.12-aws`, but not `linux-unsigned-5.2.27b1-generic`
 //!
@@ -79,86 +222,218 @@
  * SUCH DAMAGE.
  */
 
+use std::iter::Peekable;
 use std::mem;
 use std::vec::IntoIter as VecIntoIter;
 
 use itertools::{Either, Itertools};
 #[cfg(feature = "regex")]
-use regex::Regex;
+use regex::bytes::{Regex as BytesRegex, RegexBuilder as BytesRegexBuilder};
+#[cfg(feature = "regex")]
+use regex::{Regex, RegexBuilder};
 
+use crate::ast::{self, Class, ClassItem, GlobAst, Node, Span};
 use crate::error::Error as FError;
-
-/// Something that may appear in a character class.
-#[derive(Debug)]
-enum ClassItem {
-    /// A character may appear in a character class.
-    Char(char),
-    /// A range of characters may appear in a character class.
-    Range(char, char),
-}
-
-/// An accumulator for building the representation of a character class.
-#[derive(Debug)]
-struct ClassAccumulator {
-    /// Is the class negated (i.e. was `^` the first character).
-    negated: bool,
-    /// The characters or ranges in the class, in order of appearance.
-    items: Vec<ClassItem>,
-}
+#[cfg(feature = "regex")]
+use crate::options::FeatureSet;
+use crate::options::{Anchoring, EmptyBrace, GlobOptions};
 
 /// The current state of the glob pattern parser.
-#[derive(Debug)]
+// We need the default so we can use mem::take() later.
+#[derive(Debug, Default)]
 enum State {
-    /// The very start of the pattern.
-    Start,
     /// The end of the pattern, nothing more to do.
     End,
     /// The next item can be a literal character.
+    #[default]
     Literal,
     /// The next item will signify a character escape, e.g. `\t`, `\n`, etc.
     Escape,
     /// The next item will be the first character of a class, possibly `^`.
     ClassStart,
     /// The next item will either be a character or a range, both within a class.
-    Class(ClassAccumulator),
+    Class(Class),
     /// A character class range was completed; check whether the next character is
     /// a dash.
-    ClassRange(ClassAccumulator, char),
+    ClassRange(Class, char),
     /// There was a dash following a character range; let's hope this is the end of
     /// the class definition.
-    ClassRangeDash(ClassAccumulator),
+    ClassRangeDash(Class),
     /// The next item will signify a character escape within a character class.
-    ClassEscape(ClassAccumulator),
+    ClassEscape(Class),
     /// We are building a collection of alternatives.
     Alternate(String, Vec<String>),
     /// The next item will signify a character escape within a collection of alternatives.
     AlternateEscape(String, Vec<String>),
 }
 
-// We need this so we can use mem::take() later.
-impl Default for State {
-    fn default() -> Self {
-        Self::Start
-    }
-}
-
 /// Escape a character in a character class if necessary.
-/// This only escapes the backslash itself and the closing bracket.
+/// This escapes the backslash itself, the brackets, and the characters that
+/// may start a negation or a class set operation.
 fn escape_in_class(chr: char) -> String {
-    if chr == ']' || chr == '\\' {
+    if "[]^&~-\\".contains(chr) {
         format!("\\{}", chr)
     } else {
         chr.to_string()
     }
 }
 
+/// The names of the POSIX character classes, e.g. `[:alpha:]`, that are
+/// passed on to the regular expression as ASCII ones.
+const POSIX_CLASSES: [&str; 14] = [
+    "alnum", "alpha", "ascii", "blank", "cntrl", "digit", "graph", "lower", "print", "punct",
+    "space", "upper", "word", "xdigit",
+];
+
+/// The long and short names of the Unicode general categories that may be
+/// used as named classes, e.g. `[:Letter:]` or `[:Lu:]`; the surrogate code
+/// points may never appear in a string, so that category is not supported.
+const UNICODE_CATEGORIES: [&str; 74] = [
+    "Letter",
+    "L",
+    "Cased_Letter",
+    "LC",
+    "Uppercase_Letter",
+    "Lu",
+    "Lowercase_Letter",
+    "Ll",
+    "Titlecase_Letter",
+    "Lt",
+    "Modifier_Letter",
+    "Lm",
+    "Other_Letter",
+    "Lo",
+    "Mark",
+    "M",
+    "Nonspacing_Mark",
+    "Mn",
+    "Spacing_Mark",
+    "Mc",
+    "Enclosing_Mark",
+    "Me",
+    "Number",
+    "N",
+    "Decimal_Number",
+    "Nd",
+    "Letter_Number",
+    "Nl",
+    "Other_Number",
+    "No",
+    "Punctuation",
+    "P",
+    "Connector_Punctuation",
+    "Pc",
+    "Dash_Punctuation",
+    "Pd",
+    "Open_Punctuation",
+    "Ps",
+    "Close_Punctuation",
+    "Pe",
+    "Initial_Punctuation",
+    "Pi",
+    "Final_Punctuation",
+    "Pf",
+    "Other_Punctuation",
+    "Po",
+    "Symbol",
+    "S",
+    "Math_Symbol",
+    "Sm",
+    "Currency_Symbol",
+    "Sc",
+    "Modifier_Symbol",
+    "Sk",
+    "Other_Symbol",
+    "So",
+    "Separator",
+    "Z",
+    "Space_Separator",
+    "Zs",
+    "Line_Separator",
+    "Zl",
+    "Paragraph_Separator",
+    "Zp",
+    "Other",
+    "C",
+    "Control",
+    "Cc",
+    "Format",
+    "Cf",
+    "Private_Use",
+    "Co",
+    "Unassigned",
+    "Cn",
+];
+
+/// Build the regular expression for a named class within a character class,
+/// e.g. `[:^alpha:]` or `\p{Letter}`; return `None` if the name is not known.
+pub(crate) fn named_class(name: &str, negated: bool) -> Option<String> {
+    if POSIX_CLASSES.contains(&name) {
+        Some(format!("[:{}{}:]", if negated { "^" } else { "" }, name))
+    } else if UNICODE_CATEGORIES.contains(&name) {
+        Some(format!("\\{}{{{}}}", if negated { 'P' } else { 'p' }, name))
+    } else {
+        None
+    }
+}
+
+/// Append a character to a regular expression, escaping it if necessary,
+/// as outside of a character class.
+fn push_escaped(res: &mut String, chr: char) {
+    if "[]{}()|^$.*?+\\".contains(chr) {
+        res.push('\\');
+    }
+    res.push(chr);
+}
+
 /// Escape a character outside of a character class if necessary.
 fn escape(chr: char) -> String {
-    if "[{(|^$.*?+\\".contains(chr) {
-        format!("\\{}", chr)
-    } else {
-        chr.to_string()
+    let mut res = String::with_capacity(chr.len_utf8() + 1);
+    push_escaped(&mut res, chr);
+    res
+}
+
+/// Escape the characters of a literal string outside of a character class.
+fn escape_text<I: IntoIterator<Item = char>>(chars: I, capacity: usize) -> String {
+    let mut res = String::with_capacity(capacity);
+    for chr in chars {
+        push_escaped(&mut res, chr);
+    }
+    res
+}
+
+/// Escape the glob pattern special characters in a literal string, so that
+/// the result is a pattern that only matches that string, e.g. `a\*b` for
+/// `a*b`. The `,` character is not escaped, so the result should not be
+/// placed within a `{...}` alternation as it is.
+#[allow(clippy::missing_inline_in_public_items)]
+#[must_use]
+pub fn escape_literal(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for chr in text.chars() {
+        if "[]{}*?\\".contains(chr) {
+            res.push('\\');
+        }
+        res.push(chr);
+    }
+    res
+}
+
+/// Escape the characters that are special within a character class, so that
+/// the result may be placed between `[` and `]` to build a class that
+/// matches any of the characters of the specified string, e.g. `[a\-z]` for
+/// `a-z`. The string should not be empty, since `[]` is not a valid class.
+#[allow(clippy::missing_inline_in_public_items)]
+#[must_use]
+pub fn escape_for_class(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for chr in text.chars() {
+        if "[]\\-!&".contains(chr) {
+            res.push('\\');
+        }
+        res.push(chr);
     }
+    res
 }
 
 /// Interpret an escaped character: return the one that was meant.
@@ -176,11 +451,6 @@ const fn map_letter_escape(chr: char) -> char {
     }
 }
 
-/// Unescape a character and escape it if needed.
-fn escape_special(chr: char) -> String {
-    escape(map_letter_escape(chr))
-}
-
 /// Remove a slash from characters and classes.
 struct ExcIter<I>
 where
@@ -188,6 +458,8 @@ where
 {
     /// The items to remove slashes from.
     it: I,
+    /// The separator character to remove.
+    sep: char,
 }
 
 impl<I> Iterator for ExcIter<I>
@@ -197,39 +469,59 @@ where
     type Item = VecIntoIter<ClassItem>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let sep = self.sep;
         self.it.next().map(|cls| {
             match cls {
-                ClassItem::Char('/') => vec![],
-                ClassItem::Char(_) => vec![cls],
-                ClassItem::Range('.', '/') => vec![ClassItem::Char('.')],
-                ClassItem::Range(start, '/') => vec![ClassItem::Range(start, '.')],
-                ClassItem::Range('/', '0') => vec![ClassItem::Char('0')],
-                ClassItem::Range('/', end) => vec![ClassItem::Range('0', end)],
-                ClassItem::Range(start, end) if start > '/' || end < '/' => vec![cls],
-                ClassItem::Range(start, end) => vec![
-                    if start == '.' {
-                        ClassItem::Char('.')
-                    } else {
-                        ClassItem::Range(start, '.')
-                    },
-                    if end == '0' {
-                        ClassItem::Char('0')
-                    } else {
-                        ClassItem::Range('0', end)
-                    },
-                ],
+                ClassItem::Char(chr) if chr == sep => vec![],
+                ClassItem::Char(_) | ClassItem::Named { .. } => vec![cls],
+                ClassItem::Range(start, end) if start > sep || end < sep => vec![cls],
+                ClassItem::Range(start, end) => {
+                    let before = char_before(sep)
+                        .filter(|_| start < sep)
+                        .map(|last| class_span(start, last));
+                    let after = char_after(sep)
+                        .filter(|_| end > sep)
+                        .map(|first| class_span(first, end));
+                    before.into_iter().chain(after).collect()
+                }
             }
             .into_iter()
         })
     }
 }
 
-/// Exclude the slash character from classes that would include it.
-fn handle_slash_exclude(acc: ClassAccumulator) -> ClassAccumulator {
+/// The character immediately preceding the specified one, skipping
+/// the surrogate code points.
+fn char_before(chr: char) -> Option<char> {
+    u32::from(chr)
+        .checked_sub(1)
+        .map(|code| char::from_u32(code).unwrap_or('\u{d7ff}'))
+}
+
+/// The character immediately following the specified one, skipping
+/// the surrogate code points.
+fn char_after(chr: char) -> Option<char> {
+    u32::from(chr)
+        .checked_add(1)
+        .and_then(|code| char::from_u32(code).or((code <= 0xdfff).then_some('\u{e000}')))
+}
+
+/// A class item for the characters from `start` to `end`.
+const fn class_span(start: char, end: char) -> ClassItem {
+    if start == end {
+        ClassItem::Char(start)
+    } else {
+        ClassItem::Range(start, end)
+    }
+}
+
+/// Exclude the separator character from classes that would include it.
+fn handle_slash_exclude(acc: Class, sep: char) -> Class {
     assert!(!acc.negated);
-    ClassAccumulator {
+    Class {
         items: ExcIter {
             it: acc.items.into_iter(),
+            sep,
         }
         .flatten()
         .collect(),
@@ -237,16 +529,17 @@ fn handle_slash_exclude(acc: ClassAccumulator) -> ClassAccumulator {
     }
 }
 
-/// Make sure a character class will match a slash.
-fn handle_slash_include(mut acc: ClassAccumulator) -> ClassAccumulator {
+/// Make sure a negated character class will not match the separator;
+/// if it is already listed explicitly, the class is left unchanged.
+fn handle_slash_include(mut acc: Class, sep: char) -> Class {
     assert!(acc.negated);
     let slash_found = acc.items.iter().any(|item| match *item {
-        ClassItem::Char('/') => true,
-        ClassItem::Char(_) => false,
-        ClassItem::Range(start, end) => start <= '/' && end >= '/',
+        ClassItem::Char(chr) => chr == sep,
+        ClassItem::Range(start, end) => start <= sep && end >= sep,
+        ClassItem::Named { .. } => false,
     });
     if !slash_found {
-        acc.items.push(ClassItem::Char('/'));
+        acc.items.push(ClassItem::Char(sep));
     }
     acc
 }
@@ -254,25 +547,57 @@ fn handle_slash_include(mut acc: ClassAccumulator) -> ClassAccumulator {
 /// Character classes should never match a slash when used in filenames.
 /// Thus, make sure that a negated character class will include the slash
 /// character and that a non-negated one will not include it.
-fn handle_slash(acc: ClassAccumulator) -> ClassAccumulator {
+fn handle_slash(acc: Class, sep: char) -> Class {
     if acc.negated {
-        handle_slash_include(acc)
+        handle_slash_include(acc, sep)
     } else {
-        handle_slash_exclude(acc)
+        handle_slash_exclude(acc, sep)
     }
 }
 
 /// Convert a glob character class to a regular expression one.
-/// Make sure none of the classes will allow a slash to be matched in
-/// a filename, make sure the dash is at the end of the regular expression
+/// Make sure none of the classes will allow the `exclude` characters, e.g.
+/// the slash unless it is treated as an ordinary character, to be matched;
+/// a named class is intersected with a class that does not contain them.
+/// Make sure the dash is at the end of the regular expression
 /// class pattern (e.g. `[A-Za-z0-9-]`), sort the characters and the classes.
-fn close_class(glob_acc: ClassAccumulator) -> String {
-    let acc = handle_slash(glob_acc);
-    let (chars_vec, classes_vec): (Vec<_>, Vec<_>) =
-        acc.items.into_iter().partition_map(|item| match item {
-            ClassItem::Char(chr) => Either::Left(chr),
-            ClassItem::Range(start, end) => Either::Right((start, end)),
-        });
+///
+/// The ranges are normalized so that the output is deterministic and
+/// compact: a range of a single character, e.g. `[a-a]`, becomes that
+/// character, a range of two characters, e.g. `[a-b]`, becomes the two
+/// characters `[ab]`, and only the longer ones are kept as ranges.
+fn close_class(class: &Class, exclude: &[char]) -> String {
+    let acc = exclude
+        .iter()
+        .fold(class.clone(), |acc, chr| handle_slash(acc, *chr));
+    if acc.items.is_empty() && !acc.negated {
+        // Only possible if a leading dot was the only character in the class.
+        return NEVER.to_owned();
+    }
+    let named: String = acc
+        .items
+        .iter()
+        .filter_map(|item| match *item {
+            ClassItem::Named { ref name, negated } => named_class(name, negated),
+            _ => None,
+        })
+        .sorted_unstable()
+        .dedup()
+        .collect();
+    let (chars_vec, classes_vec): (Vec<_>, Vec<_>) = acc
+        .items
+        .into_iter()
+        .flat_map(|item| match item {
+            ClassItem::Char(chr) => vec![Either::Left(chr)],
+            ClassItem::Range(start, end)
+                if u32::from(end).saturating_sub(u32::from(start)) <= 1 =>
+            {
+                vec![Either::Left(start), Either::Left(end)]
+            }
+            ClassItem::Range(start, end) => vec![Either::Right((start, end))],
+            ClassItem::Named { .. } => Vec::new(),
+        })
+        .partition_map(|item| item);
 
     let (chars, final_dash) = {
         let mut has_dash = false;
@@ -298,116 +623,798 @@ fn close_class(glob_acc: ClassAccumulator) -> String {
         .dedup()
         .map(|cls| format!("{}-{}", escape_in_class(cls.0), escape_in_class(cls.1)));
 
-    format!(
-        "[{}{}{}]",
+    let res = format!(
+        "[{}{}{}{}]",
         if acc.negated { "^" } else { "" },
         chars.chain(classes).collect::<String>(),
+        named,
         final_dash,
-    )
+    );
+    let exclusion = if acc.negated || named.is_empty() || exclude.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "--[{}]",
+            exclude
+                .iter()
+                .map(|chr| escape_in_class(*chr))
+                .collect::<String>()
+        )
+    };
+    let intersect = format!(
+        "{}{}",
+        exclusion,
+        acc.intersections
+            .iter()
+            .map(|nested| format!("&&{}", close_class(nested, &[])))
+            .collect::<String>()
+    );
+    if intersect.is_empty() {
+        res
+    } else {
+        format!("[{}{}]", res, intersect)
+    }
 }
 
-/// Convert a glob alternatives list to a regular expression pattern.
-fn close_alternate(gathered: Vec<String>) -> String {
-    let items = gathered
-        .into_iter()
-        .map(|item| item.chars().map(escape).collect::<String>())
+/// Build a regular expression matching exactly the specified sorted and
+/// deduplicated strings, merging their common prefixes, e.g. `(?:alp(?:ha|s)|beta)`.
+fn merge_prefixes(items: &[&[char]]) -> String {
+    let (terminal, mut remaining) = match items.split_first() {
+        Some((&&[], rest)) => (true, rest),
+        _ => (false, items),
+    };
+    let mut branches = Vec::new();
+    while let Some(&first) = remaining.first().and_then(|item| item.first()) {
+        let count = remaining
+            .iter()
+            .take_while(|item| item.first() == Some(&first))
+            .count();
+        let (group, others) = remaining.split_at(count);
+        if let (Some(head), Some(last)) = (group.first(), group.last()) {
+            // The strings are sorted, so this is the common prefix of the whole group.
+            let prefix_len = head
+                .iter()
+                .zip(last.iter())
+                .take_while(|&(left, right)| left == right)
+                .count();
+            let suffixes: Vec<&[char]> = group.iter().map(|item| &item[prefix_len..]).collect();
+            let mut branch = escape_text(head[..prefix_len].iter().copied(), prefix_len);
+            branch.push_str(&merge_prefixes(&suffixes));
+            branches.push(branch);
+        }
+        remaining = others;
+    }
+
+    match (terminal, branches.len()) {
+        (_, 0) => String::new(),
+        (false, 1) => branches.join(""),
+        (false, _) => format!("(?:{})", branches.join("|")),
+        (true, _) => format!("(?:{})?", branches.join("|")),
+    }
+}
+
+/// A regular expression that never matches anything.
+const NEVER: &str = "[^\\x00-\\x{10FFFF}]";
+
+/// The bytes that are not ASCII characters, e.g. a Latin-1 `\xe9` in
+/// a file name that is not valid UTF-8.
+const HIGH_BYTES: &str = r"(?-u:[\x80-\xff])";
+
+/// The bytes that may never start a valid UTF-8 sequence: stray continuation
+/// bytes and the ones that may not appear in UTF-8 text at all.
+const STRAY_BYTES: &str = r"(?-u:[\x80-\xbf\xc0\xc1\xf5-\xff])";
+
+/// Make sure a regular expression that may match any single character
+/// except a few still does so if the `unicode` option is disabled, and let it
+/// also match a single non-ASCII byte if it is meant to be used for byte
+/// strings. Only the stray bytes are matched if the separator is not
+/// an ASCII character, so that a part of it is never matched.
+fn wildcard_unit(unit: String, options: &GlobOptions, bytes: bool) -> String {
+    let any = if options.unicode {
+        unit
+    } else {
+        format!("(?u:{})", unit)
+    };
+    if bytes {
+        format!(
+            "(?:{}|{})",
+            any,
+            if options.separator.is_ascii() {
+                HIGH_BYTES
+            } else {
+                STRAY_BYTES
+            }
+        )
+    } else {
+        any
+    }
+}
+
+/// Convert a glob alternatives list to a regular expression pattern;
+/// `start` is set if the alternation is at the start of a path component.
+fn close_alternate(
+    branches: &[Vec<Node>],
+    options: &GlobOptions,
+    bytes: bool,
+    start: bool,
+) -> String {
+    if options.compact_alternations {
+        if let Some(texts) = branches
+            .iter()
+            .map(|branch| ast::literal_text(branch))
+            .collect::<Option<Vec<_>>>()
+        {
+            let items: Vec<Vec<char>> = texts
+                .iter()
+                .map(|item| item.chars().collect())
+                .sorted_unstable()
+                .dedup()
+                .collect();
+            return merge_prefixes(&items.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        }
+    }
+
+    let items: Vec<String> = branches
+        .iter()
+        .map(|branch| nodes_to_regex(branch, options, bytes, start))
         .sorted_unstable()
         .dedup()
-        .join("|");
+        .collect();
+    let mut res = String::with_capacity(items.iter().map(|item| item.len() + 1).sum::<usize>() + 1);
+    res.push('(');
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            res.push('|');
+        }
+        res.push_str(item);
+    }
+    res.push(')');
+    res
+}
+
+/// Build the regular expression for a single character that the wildcards
+/// may match; if `leading` is set, it is the first one in a path component,
+/// so it may not be a dot if the `literal_leading_dot` option is enabled.
+/// If `bytes` is set, single non-ASCII bytes are also matched.
+fn any_char(options: &GlobOptions, bytes: bool, leading: bool) -> String {
+    // Let the wildcards match any character at all, even a newline, if
+    // the slash is not special.
+    wildcard_unit(
+        if options.treat_slash_as_ordinary {
+            "(?s:.)".to_owned()
+        } else if leading && options.literal_leading_dot {
+            format!("[^{}.]", escape_in_class(options.separator))
+        } else {
+            format!("[^{}]", escape_in_class(options.separator))
+        },
+        options,
+        bytes,
+    )
+}
+
+/// Build the regular expression for a non-empty path component that is
+/// matched by a globstar.
+fn globstar_component(options: &GlobOptions, bytes: bool) -> String {
+    if options.literal_leading_dot {
+        format!(
+            "{}{}*",
+            any_char(options, bytes, true),
+            any_char(options, bytes, false)
+        )
+    } else {
+        format!("{}+", any_char(options, bytes, false))
+    }
+}
+
+/// Build the regular expression for a `**/` globstar component.
+fn globstar_dirs(options: &GlobOptions, bytes: bool) -> String {
+    let (comp, sep) = (
+        globstar_component(options, bytes),
+        escape(options.separator),
+    );
+    match options.max_globstar_depth {
+        Some(depth) => format!("(?:{}{}){{0,{}}}", comp, sep, depth),
+        None => format!("(?:{}{})*", comp, sep),
+    }
+}
+
+/// Build the regular expression for a `**` globstar component at the end of the pattern.
+fn globstar_tail(options: &GlobOptions, bytes: bool) -> String {
+    let (comp, sep) = (
+        globstar_component(options, bytes),
+        escape(options.separator),
+    );
+    match options.max_globstar_depth {
+        Some(0) => String::new(),
+        Some(depth) => format!(
+            "(?:{0})?(?:{1}{0}){{0,{2}}}",
+            comp,
+            sep,
+            depth.saturating_sub(1)
+        ),
+        None if options.literal_leading_dot => format!("(?:{0})?(?:{1}(?:{0})?)*", comp, sep),
+        None => format!("{}*", wildcard_unit(".".to_owned(), options, bytes)),
+    }
+}
+
+/// Build the regular expression for a trailing `*` that may also match
+/// path separators; if `leading` is set, it is at the start of a path
+/// component, so it may not match a leading dot there.
+fn star_tail(options: &GlobOptions, bytes: bool, leading: bool) -> String {
+    if options.treat_slash_as_ordinary {
+        format!("{}*", any_char(options, bytes, false))
+    } else if options.literal_leading_dot {
+        let comp = globstar_component(options, bytes);
+        format!(
+            "{}(?:{}(?:{})?)*",
+            if leading {
+                format!("(?:{})?", comp)
+            } else {
+                format!("{}*", any_char(options, bytes, false))
+            },
+            escape(options.separator),
+            comp
+        )
+    } else {
+        format!("{}*", wildcard_unit(".".to_owned(), options, bytes))
+    }
+}
+
+/// Build the regular expression for a single element of a glob pattern;
+/// if `bytes` is set, the wildcards may also match single non-ASCII bytes.
+/// If `start` is set, the element is at the start of a path component, so
+/// its first character may not be a dot if the `literal_leading_dot` option
+/// is enabled.
+fn node_to_regex(node: &Node, options: &GlobOptions, bytes: bool, start: bool) -> String {
+    let leading = start && options.literal_leading_dot;
+    let any = any_char(options, bytes, false);
+    match *node {
+        Node::Literal(ref text) => escape_text(text.chars(), text.len()),
+        Node::AnyChar => options.single_char_class.map_or_else(
+            || any_char(options, bytes, leading),
+            |class| format!("(?:{})", class),
+        ),
+        Node::AnyChars(count) => match (options.single_char_class, count.checked_sub(1)) {
+            (Some(class), _) => format!("(?:{}){{{}}}", class, count),
+            (None, Some(more)) if leading => {
+                format!("{}{}{{{}}}", any_char(options, bytes, true), any, more)
+            }
+            (None, _) => format!("{}{{{}}}", any, count),
+        },
+        Node::Star if leading => format!("(?:{}{}*)?", any_char(options, bytes, true), any),
+        Node::Star => format!("{}*", any),
+        Node::GlobstarDirs => globstar_dirs(options, bytes),
+        Node::GlobstarTail => globstar_tail(options, bytes),
+        Node::StarTail => star_tail(options, bytes, leading),
+        Node::Class(ref class) => {
+            let exclude: Vec<char> = leading
+                .then_some('.')
+                .into_iter()
+                .chain(
+                    (!options.treat_slash_as_ordinary && !options.classes_match_separator)
+                        .then_some(options.separator),
+                )
+                .collect();
+            let res = close_class(class, &exclude);
+            if class.negated && class.intersections.iter().all(|nested| nested.negated) {
+                wildcard_unit(res, options, bytes)
+            } else {
+                res
+            }
+        }
+        Node::Alternation(ref branches) => close_alternate(branches, options, bytes, start),
+    }
+}
+
+/// Where an element is relative to the start of a path component when
+/// the `literal_leading_dot` option is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Lead {
+    /// Within a path component.
+    Inside,
+    /// At the start of a path component, so a dot may only be matched literally.
+    Start,
+    /// At the start of a path component, right after a `*` that matched
+    /// nothing, so a dot may not be matched at all.
+    AfterStar,
+}
+
+/// Merge the regular expressions that leave the matching at the same place
+/// relative to the start of a path component.
+fn merge_leads(alts: Vec<(String, Lead)>) -> Vec<(String, Lead)> {
+    alts.into_iter()
+        .into_group_map_by(|&(_, lead)| lead)
+        .into_iter()
+        .sorted_unstable_by_key(|&(lead, _)| lead)
+        .map(|(lead, group)| {
+            let items: Vec<String> = group
+                .into_iter()
+                .map(|(res, _)| res)
+                .sorted_unstable()
+                .dedup()
+                .collect();
+            match *items.as_slice() {
+                [ref single] => (single.clone(), lead),
+                _ => (format!("(?:{})", items.join("|")), lead),
+            }
+        })
+        .collect()
+}
+
+/// Build the regular expressions for a single element of a glob pattern
+/// when the `literal_leading_dot` option is enabled, one for each place
+/// relative to the start of a path component that the element may leave
+/// the matching at.
+fn node_to_leads(
+    node: &Node,
+    options: &GlobOptions,
+    bytes: bool,
+    lead: Lead,
+) -> Vec<(String, Lead)> {
+    let start = lead != Lead::Inside;
+    match *node {
+        Node::Literal(ref text) if text.is_empty() => vec![(String::new(), lead)],
+        Node::Literal(ref text) if lead == Lead::AfterStar && text.starts_with('.') => vec![],
+        Node::Literal(ref text) => vec![(
+            node_to_regex(node, options, bytes, start),
+            if text.ends_with(options.separator) {
+                Lead::Start
+            } else {
+                Lead::Inside
+            },
+        )],
+        Node::AnyChars(0) => vec![(String::new(), lead)],
+        Node::Star if start => vec![
+            (
+                format!(
+                    "{}{}*",
+                    any_char(options, bytes, true),
+                    any_char(options, bytes, false)
+                ),
+                Lead::Inside,
+            ),
+            (String::new(), Lead::AfterStar),
+        ],
+        Node::GlobstarDirs => vec![(node_to_regex(node, options, bytes, start), Lead::Start)],
+        Node::Alternation(ref branches) => {
+            let alts = merge_leads(
+                branches
+                    .iter()
+                    .flat_map(|branch| nodes_to_leads(branch, options, bytes, lead))
+                    .collect(),
+            );
+            match *alts.as_slice() {
+                [(_, Lead::Inside)] if lead == Lead::Inside => vec![(
+                    close_alternate(branches, options, bytes, false),
+                    Lead::Inside,
+                )],
+                _ => alts
+                    .into_iter()
+                    .map(|(res, end)| (format!("({})", res), end))
+                    .collect(),
+            }
+        }
+        _ => vec![(node_to_regex(node, options, bytes, start), Lead::Inside)],
+    }
+}
+
+/// Build the regular expressions for a sequence of glob pattern elements
+/// when the `literal_leading_dot` option is enabled, one for each place
+/// relative to the start of a path component that it may leave the matching at.
+fn nodes_to_leads(
+    nodes: &[Node],
+    options: &GlobOptions,
+    bytes: bool,
+    lead: Lead,
+) -> Vec<(String, Lead)> {
+    let mut done = String::new();
+    let mut alts = vec![(String::new(), lead)];
+    for node in nodes {
+        alts = merge_leads(
+            alts.iter()
+                .flat_map(|&(ref prefix, current)| {
+                    node_to_leads(node, options, bytes, current)
+                        .into_iter()
+                        .map(move |(res, end)| (format!("{}{}", prefix, res), end))
+                })
+                .collect(),
+        );
+        if let [(ref res, end)] = *alts.as_slice() {
+            // Only one way to get here, so there is no need to keep track of it.
+            done.push_str(res);
+            alts = vec![(String::new(), end)];
+        }
+    }
+    alts.into_iter()
+        .map(|(res, end)| (format!("{}{}", done, res), end))
+        .collect()
+}
+
+/// Build the regular expression for a sequence of glob pattern elements;
+/// `start` is set if the sequence is at the start of a path component.
+///
+/// If the `literal_leading_dot` option is enabled, a dot at the start of
+/// a path component may only be matched by a literal dot at the start of
+/// a pattern component. Thus the elements after a `*` that may match nothing
+/// at the start of a component are built twice, e.g. `*.txt` becomes
+/// `[^/.][^/]*\.txt`, and `*?` becomes `(?:[^/.][^/]*[^/]|[^/.])`.
+fn nodes_to_regex(nodes: &[Node], options: &GlobOptions, bytes: bool, start: bool) -> String {
+    if !options.literal_leading_dot {
+        return nodes
+            .iter()
+            .map(|node| node_to_regex(node, options, bytes, false))
+            .collect();
+    }
+    let alts = nodes_to_leads(
+        nodes,
+        options,
+        bytes,
+        if start { Lead::Start } else { Lead::Inside },
+    );
+    match *alts.as_slice() {
+        [] => NEVER.to_owned(),
+        [(ref res, _)] => res.clone(),
+        _ => format!("(?:{})", alts.iter().map(|alt| &alt.0).join("|")),
+    }
+}
+
+/// Build the regular expression for the prefixes of the texts that
+/// a trailing `**` globstar component may match, including the empty one.
+#[cfg(feature = "regex")]
+fn globstar_tail_prefix(options: &GlobOptions) -> String {
+    let (comp, sep) = (
+        globstar_component(options, false),
+        escape(options.separator),
+    );
+    match options.max_globstar_depth {
+        Some(0) => String::new(),
+        Some(1) => format!("(?:{})?", comp),
+        Some(depth) => format!(
+            "(?:{0})?(?:{1}{0}){{0,{2}}}(?:{1}(?:{0})?)?",
+            comp,
+            sep,
+            depth.saturating_sub(2)
+        ),
+        None => globstar_tail(options, false),
+    }
+}
+
+/// Build the regular expression for the prefixes of the texts that a single
+/// element of a glob pattern may match, including the empty one, when
+/// the matching is at the specified place relative to the start of a path
+/// component.
+#[cfg(feature = "regex")]
+fn node_to_prefix_regex(node: &Node, options: &GlobOptions, lead: Lead) -> String {
+    let start = lead != Lead::Inside;
+    let leading = start && options.literal_leading_dot;
+    let (comp, sep) = (
+        globstar_component(options, false),
+        escape(options.separator),
+    );
+    match *node {
+        Node::Literal(ref text) if lead == Lead::AfterStar && text.starts_with('.') => {
+            String::new()
+        }
+        Node::Literal(ref text) => {
+            let chars: Vec<char> = text.chars().collect();
+            format!(
+                "(?:{})?",
+                (1..=chars.len())
+                    .rev()
+                    .map(|len| escape_text(chars.iter().take(len).copied(), len))
+                    .join("|")
+            )
+        }
+        Node::AnyChar | Node::Class(_) => {
+            format!("(?:{})?", node_to_regex(node, options, false, start))
+        }
+        Node::AnyChars(count) => match (options.single_char_class, count.checked_sub(1)) {
+            (Some(class), _) => format!("(?:{}){{0,{}}}", class, count),
+            (None, Some(more)) if leading => format!(
+                "(?:{}{}{{0,{}}})?",
+                any_char(options, false, true),
+                any_char(options, false, false),
+                more
+            ),
+            (None, _) => format!("{}{{0,{}}}", any_char(options, false, false), count),
+        },
+        Node::Star | Node::StarTail => node_to_regex(node, options, false, start),
+        Node::GlobstarDirs => match options.max_globstar_depth {
+            Some(0) => String::new(),
+            Some(depth) => format!(
+                "(?:(?:{0}{1}){{0,{2}}}|(?:{0}{1}){{0,{3}}}{0})",
+                comp,
+                sep,
+                depth,
+                depth.saturating_sub(1)
+            ),
+            None => format!("(?:{0}{1})*(?:{0})?", comp, sep),
+        },
+        Node::GlobstarTail => globstar_tail_prefix(options),
+        Node::Alternation(ref branches) => format!(
+            "(?:{})",
+            branches
+                .iter()
+                .map(|branch| nodes_to_prefix_regex(branch, options, lead))
+                .join("|")
+        ),
+    }
+}
 
-    format!("({})", items)
+/// Build the regular expression for the prefixes of the texts that
+/// a sequence of elements may match, including the empty one: for each
+/// element, the texts matched by the ones before it followed by a prefix of
+/// the ones that it may match. As soon as there is only one way to reach
+/// an element, the rest of the sequence is handled recursively, so that
+/// the elements before it are not repeated.
+#[cfg(feature = "regex")]
+fn nodes_to_prefix_regex(nodes: &[Node], options: &GlobOptions, lead: Lead) -> String {
+    let mut alts = vec![(String::new(), lead)];
+    let mut parts = Vec::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        parts.extend(alts.iter().map(|&(ref res, current)| {
+            format!("{}{}", res, node_to_prefix_regex(node, options, current))
+        }));
+        alts = merge_leads(
+            alts.iter()
+                .flat_map(|&(ref prefix, current)| {
+                    if options.literal_leading_dot {
+                        node_to_leads(node, options, false, current)
+                    } else {
+                        vec![(node_to_regex(node, options, false, false), Lead::Inside)]
+                    }
+                    .into_iter()
+                    .map(move |(res, end)| (format!("{}{}", prefix, res), end))
+                })
+                .collect(),
+        );
+        if let [(ref res, end)] = *alts.as_slice() {
+            let rest = nodes.get(idx.saturating_add(1)..).unwrap_or_default();
+            if !rest.is_empty() {
+                parts.push(format!(
+                    "{}{}",
+                    res,
+                    nodes_to_prefix_regex(rest, options, end)
+                ));
+            }
+            break;
+        }
+    }
+    match *parts.as_slice() {
+        [] => String::new(),
+        [ref single] => single.clone(),
+        _ => format!("(?:{})", parts.join("|")),
+    }
 }
 
-/// Iterate over a glob pattern's characters, build up a regular expression.
-struct GlobIterator<I: Iterator<Item = char>> {
+/// Iterate over a glob pattern's characters, split it into elements.
+struct GlobIterator<'opts, I: Iterator<Item = char>> {
     /// The iterator over the glob pattern's characters.
-    pattern: I,
+    pattern: Peekable<I>,
+    /// The options controlling the conversion.
+    options: &'opts GlobOptions,
     /// The current state of the glob pattern parser.
     state: State,
+    /// Is the next character the first one in a path component?
+    segment_start: bool,
+    /// The position of the next character within the whole pattern.
+    position: usize,
+    /// The byte offset of the next character within the whole pattern.
+    byte_position: usize,
+    /// The byte offset of the first character of the element being parsed.
+    node_start: usize,
+    /// The position of the `[` character that started the current class.
+    class_start: usize,
+    /// The position of the `{` character that started the current alternation.
+    alternate_start: usize,
+    /// Was the `{` that started the current alternation at the start of
+    /// a path component?
+    alternate_segment_start: bool,
 }
 
-/// Either a piece of the regular expression or an error.
-type StringResult = Result<Option<String>, FError>;
+/// Either an element of the glob pattern or an error.
+type NodeResult = Result<Option<Node>, FError>;
 
-impl<I> GlobIterator<I>
+impl<I> GlobIterator<'_, I>
 where
     I: Iterator<Item = char>,
 {
-    /// Output a "^" at the very start of the pattern.
-    fn handle_start(&mut self) -> String {
+    /// Consume the next character of the pattern.
+    fn next_char(&mut self) -> Option<char> {
+        let res = self.pattern.next();
+        self.position += usize::from(res.is_some());
+        self.byte_position += res.map_or(0, char::len_utf8);
+        res
+    }
+
+    /// Check whether a character is the escape one.
+    fn is_escape(&self, chr: char) -> bool {
+        self.options.escape_char == Some(chr)
+    }
+
+    /// Consume the next character of the pattern if it satisfies a condition.
+    fn next_char_if<F: FnOnce(&char) -> bool>(&mut self, func: F) -> Option<char> {
+        let res = self.pattern.next_if(func);
+        self.position += usize::from(res.is_some());
+        self.byte_position += res.map_or(0, char::len_utf8);
+        res
+    }
+
+    /// Consume the next character of the pattern if it is the expected one.
+    fn next_char_if_eq(&mut self, expected: char) -> bool {
+        self.next_char_if(|&chr| chr == expected).is_some()
+    }
+
+    /// Make sure a character class may match something before
+    /// emitting it: no reversed ranges, even ones with escaped ends or
+    /// in an intersection, and not just the separator if the class would
+    /// never match that.
+    fn close_class(&mut self, acc: Class) -> NodeResult {
+        if let Some(&ClassItem::Range(start, end)) = acc
+            .items
+            .iter()
+            .chain(acc.intersections.iter().flat_map(|nested| &nested.items))
+            .find(|item| matches!(**item, ClassItem::Range(start, end) if start > end))
+        {
+            return Err(FError::ReversedRange(start, end, self.class_start));
+        }
+        let sep = self.options.separator;
+        if !acc.negated
+            && !self.options.treat_slash_as_ordinary
+            && !self.options.classes_match_separator
+            && acc
+                .items
+                .iter()
+                .all(|item| matches!(*item, ClassItem::Char(chr) if chr == sep))
+        {
+            return Err(FError::EmptyClass(self.class_start));
+        }
         self.state = State::Literal;
-        "^".to_owned()
+        if self.options.optional_class && self.next_char_if_eq('?') {
+            // An alternation with an empty branch, so that e.g. the regular
+            // expression and the explanations need no special handling.
+            return Ok(Some(Node::Alternation(vec![
+                vec![Node::Class(acc)],
+                Vec::new(),
+            ])));
+        }
+        Ok(Some(Node::Class(acc)))
     }
 
     /// Handle the next character when expecting a literal one.
-    fn handle_literal(&mut self) -> Option<String> {
-        match self.pattern.next() {
+    fn handle_literal(&mut self) -> NodeResult {
+        match self.next_char() {
             None => {
                 self.state = State::End;
-                Some("$".to_owned())
+                Ok(None)
             }
             Some(chr) => {
-                let (new_state, res) = match chr {
-                    '\\' => (State::Escape, None),
-                    '[' => (State::ClassStart, None),
-                    '{' => (State::Alternate(String::new(), Vec::new()), None),
-                    '?' => (State::Literal, Some("[^/]".to_owned())),
-                    '*' => (State::Literal, Some(".*".to_owned())),
-                    ']' | '}' | '.' => (State::Literal, Some(format!("\\{}", chr))),
-                    _ => (State::Literal, Some(format!("{}", chr))),
-                };
-                self.state = new_state;
-                res
+                let segment_start =
+                    mem::replace(&mut self.segment_start, chr == self.options.separator);
+                self.state = State::Literal;
+                Ok(match chr {
+                    special @ ('*' | '?' | '[' | '{')
+                        if self.options.escape_by_doubling && self.next_char_if_eq(special) =>
+                    {
+                        Some(Node::Literal(special.to_string()))
+                    }
+                    escape if self.is_escape(escape) => {
+                        self.state = State::Escape;
+                        None
+                    }
+                    '[' => {
+                        self.state = State::ClassStart;
+                        self.class_start = self.position - 1;
+                        None
+                    }
+                    '{' => {
+                        self.state = State::Alternate(String::new(), Vec::new());
+                        self.alternate_start = self.position - 1;
+                        self.alternate_segment_start = segment_start;
+                        None
+                    }
+                    wildcard @ ('?' | '*') if self.options.disallow_wildcards => {
+                        return Err(FError::DisallowedWildcard(wildcard, self.position - 1));
+                    }
+                    '?' => Some(self.handle_question()?),
+                    '*' => Some(self.handle_star(segment_start)),
+                    '.' if self.options.dot_is_wildcard => Some(Node::AnyChar),
+                    other => Some(Node::Literal(other.to_string())),
+                })
+            }
+        }
+    }
+
+    /// Handle a `?` wildcard, possibly followed by a `{N}` repetition count.
+    fn handle_question(&mut self) -> Result<Node, FError> {
+        if !self.options.counted_repetition || !self.next_char_if_eq('{') {
+            return Ok(Node::AnyChar);
+        }
+        let mut spec = String::new();
+        loop {
+            match self.next_char() {
+                Some('}') => break,
+                Some(chr) => spec.push(chr),
+                None => return Err(FError::InvalidRepetition(spec)),
             }
         }
+        match spec.parse::<usize>() {
+            Ok(count) if spec.chars().all(|chr| chr.is_ascii_digit()) => Ok(Node::AnyChars(count)),
+            _ => Err(FError::InvalidRepetition(spec)),
+        }
+    }
+
+    /// Handle a `*` wildcard, possibly the start of a `**` globstar component.
+    fn handle_star(&mut self, segment_start: bool) -> Node {
+        let sep = self.options.separator;
+        let globstar = self.options.globstar && segment_start && self.next_char_if_eq('*');
+        if !globstar {
+            return Node::Star;
+        }
+        if self.pattern.peek().is_none() {
+            return Node::GlobstarTail;
+        }
+        let dirs = if self.is_escape(sep) {
+            // A separator that is also the escape character must be escaped;
+            // if something else is, this is not a globstar component after all.
+            self.next_char_if_eq(sep)
+                && (self.next_char_if_eq(sep) || {
+                    self.state = State::Escape;
+                    false
+                })
+        } else {
+            self.next_char_if_eq(sep)
+        };
+        if dirs {
+            self.segment_start = true;
+            Node::GlobstarDirs
+        } else {
+            Node::Star
+        }
     }
 
     /// Handle an escaped character.
-    fn handle_escape(&mut self) -> StringResult {
-        match self.pattern.next() {
+    fn handle_escape(&mut self) -> NodeResult {
+        match self.next_char() {
             Some(chr) => {
+                let unescaped = map_letter_escape(chr);
                 self.state = State::Literal;
-                Ok(Some(escape_special(chr)))
+                self.segment_start = unescaped == self.options.separator;
+                Ok(Some(Node::Literal(unescaped.to_string())))
             }
             None => Err(FError::BareEscape),
         }
     }
 
     /// Handle the first character in a character class specification.
-    fn handle_class_start(&mut self) -> StringResult {
-        match self.pattern.next() {
+    fn handle_class_start(&mut self) -> NodeResult {
+        match self.next_char() {
             Some(chr) => {
+                let escape = self.is_escape(chr);
+                let acc = Class {
+                    negated: chr == '!',
+                    items: match chr {
+                        '!' | '[' => Vec::new(),
+                        _ if escape => Vec::new(),
+                        // A leading `-` or `]` is matched literally.
+                        other => vec![ClassItem::Char(other)],
+                    },
+                    intersections: Vec::new(),
+                };
                 self.state = match chr {
-                    '!' => State::Class(ClassAccumulator {
-                        negated: true,
-                        items: Vec::new(),
-                    }),
-                    '-' => State::Class(ClassAccumulator {
-                        negated: false,
-                        items: vec![ClassItem::Char('-')],
-                    }),
-                    ']' => State::Class(ClassAccumulator {
-                        negated: false,
-                        items: vec![ClassItem::Char(']')],
-                    }),
-                    '\\' => State::ClassEscape(ClassAccumulator {
-                        negated: false,
-                        items: Vec::new(),
-                    }),
-                    other => State::Class(ClassAccumulator {
-                        negated: false,
-                        items: vec![ClassItem::Char(other)],
-                    }),
+                    _ if escape => State::ClassEscape(acc),
+                    '[' => return self.handle_class_bracket(acc),
+                    _ => State::Class(acc),
                 };
                 Ok(None)
             }
-            None => Err(FError::UnclosedClass),
+            None => Err(FError::UnclosedClass(self.class_start)),
         }
     }
 
     /// Handle a character in a character class specification.
-    fn handle_class(&mut self, mut acc: ClassAccumulator) -> StringResult {
-        match self.pattern.next() {
+    fn handle_class(&mut self, mut acc: Class) -> NodeResult {
+        match self.next_char() {
             Some(chr) => Ok(match chr {
                 ']' => {
                     if acc.items.is_empty() {
@@ -415,8 +1422,7 @@ where
                         self.state = State::Class(acc);
                         None
                     } else {
-                        self.state = State::Literal;
-                        Some(close_class(acc))
+                        return self.close_class(acc);
                     }
                 }
                 '-' => match acc.items.pop() {
@@ -434,8 +1440,24 @@ where
                         self.state = State::ClassRange(acc, start);
                         None
                     }
+                    Some(named @ ClassItem::Named { .. }) => {
+                        acc.items.push(named);
+                        acc.items.push(ClassItem::Char('-'));
+                        self.state = State::Class(acc);
+                        None
+                    }
                 },
-                '\\' => {
+                '[' => return self.handle_class_bracket(acc),
+                '&' if self.options.class_intersection && self.next_char_if_eq('&') => {
+                    if self.next_char_if_eq('[') {
+                        return self.handle_class_intersection(acc);
+                    }
+                    acc.items.push(ClassItem::Char('&'));
+                    acc.items.push(ClassItem::Char('&'));
+                    self.state = State::Class(acc);
+                    None
+                }
+                escape if self.is_escape(escape) => {
                     self.state = State::ClassEscape(acc);
                     None
                 }
@@ -445,136 +1467,358 @@ where
                     None
                 }
             }),
-            None => Err(FError::UnclosedClass),
+            None => Err(FError::UnclosedClass(self.class_start)),
         }
     }
 
-    /// Escape a character in a class specification.
-    fn handle_class_escape(&mut self, mut acc: ClassAccumulator) -> StringResult {
-        match self.pattern.next() {
-            Some(chr) => {
-                acc.items.push(ClassItem::Char(map_letter_escape(chr)));
+    /// Handle a `[` within a character class: the start of a named class,
+    /// e.g. `[:alpha:]` or `[:^Letter:]`, if followed by a colon and a name,
+    /// or a literal character otherwise.
+    fn handle_class_bracket(&mut self, mut acc: Class) -> NodeResult {
+        let mut text = String::from("[");
+        if self.next_char_if_eq(':') {
+            text.push(':');
+            let (negated, name, colon) = self.scan_class_name();
+            if colon && !name.is_empty() && self.next_char_if_eq(']') {
+                if named_class(&name, negated).is_none() {
+                    return Err(FError::UnknownClass(name));
+                }
+                acc.items.push(ClassItem::Named { name, negated });
                 self.state = State::Class(acc);
-                Ok(None)
+                return Ok(None);
             }
-            None => Err(FError::UnclosedClass),
+            // Not a named class after all, so take the characters literally.
+            text.extend(negated.then_some('^'));
+            text.push_str(&name);
+            text.extend(colon.then_some(':'));
         }
+        acc.items.extend(text.chars().map(ClassItem::Char));
+        self.state = State::Class(acc);
+        Ok(None)
     }
 
-    /// Handle a character within a class range.
-    fn handle_class_range(&mut self, mut acc: ClassAccumulator, start: char) -> StringResult {
-        match self.pattern.next() {
-            Some(chr) => match chr {
-                '\\' => Err(FError::NotImplemented(format!(
-                    "FIXME: handle class range end escape with {:?} start {:?}",
-                    acc, start
-                ))),
-                ']' => {
-                    acc.items.push(ClassItem::Char(start));
-                    acc.items.push(ClassItem::Char('-'));
-                    self.state = State::Literal;
-                    Ok(Some(close_class(acc)))
-                }
-                end if start > end => Err(FError::ReversedRange(start, end)),
-                end if start == end => {
-                    acc.items.push(ClassItem::Char(start));
-                    self.state = State::Class(acc);
-                    Ok(None)
-                }
-                end => {
-                    acc.items.push(ClassItem::Range(start, end));
-                    self.state = State::Class(acc);
-                    Ok(None)
-                }
-            },
-            None => Err(FError::UnclosedClass),
+    /// Consume the name of a named class right after the `[:`, possibly
+    /// negated by a `^`, e.g. `alpha:` or `^Letter:`; return whether it was
+    /// negated, the name, and whether it was followed by a colon.
+    fn scan_class_name(&mut self) -> (bool, String, bool) {
+        let negated = self.next_char_if_eq('^');
+        let mut name = String::new();
+        while let Some(chr) = self.next_char_if(|&chr| chr.is_ascii_alphanumeric() || chr == '_') {
+            name.push(chr);
         }
+        (negated, name, self.next_char_if_eq(':'))
     }
 
-    /// Handle a dash immediately following a range within a character class.
-    #[allow(clippy::panic_in_result_fn)]
-    #[allow(clippy::unreachable)]
-    fn handle_class_range_dash(&mut self, mut acc: ClassAccumulator) -> StringResult {
-        match self.pattern.next() {
-            Some(chr) => {
-                if chr == ']' {
-                    acc.items.push(ClassItem::Char('-'));
-                    self.state = State::Literal;
-                    Ok(Some(close_class(acc)))
-                } else if let Some(ClassItem::Range(start, end)) = acc.items.pop() {
-                    Err(FError::RangeAfterRange(start, end))
+    /// Handle the `&&[...]` intersections at the end of a character class,
+    /// e.g. `[a-z&&[^aeiou]]`, then the closing `]` of the class itself.
+    fn handle_class_intersection(&mut self, mut acc: Class) -> NodeResult {
+        loop {
+            let nested = self.parse_nested_class()?;
+            acc.intersections.push(nested);
+            if self.next_char_if_eq(']') {
+                return self.close_class(acc);
+            }
+            if !(self.next_char_if_eq('&')
+                && self.next_char_if_eq('&')
+                && self.next_char_if_eq('['))
+            {
+                return Err(if self.pattern.peek().is_some() {
+                    FError::Unsupported {
+                        feature: "class_items_after_intersection",
+                    }
                 } else {
-                    // Let's hope the optimizer hears us...
-                    unreachable!()
-                }
+                    FError::UnclosedClass(self.class_start)
+                });
             }
-            None => Err(FError::UnclosedClass),
         }
     }
 
-    /// Start a set of alternatives.
-    fn handle_alternate(&mut self, mut current: String, mut gathered: Vec<String>) -> StringResult {
-        match self.pattern.next() {
-            Some(chr) => match chr {
-                ',' => {
-                    gathered.push(current);
-                    self.state = State::Alternate(String::new(), gathered);
-                    Ok(None)
+    /// Parse a nested class within an intersection, right after its `[`,
+    /// e.g. `^aeiou]`. It may be negated by a leading `^` or `!` and it may
+    /// contain characters, ranges, and escaped characters.
+    fn parse_nested_class(&mut self) -> Result<Class, FError> {
+        let negated = self.next_char_if_eq('^') || self.next_char_if_eq('!');
+        let mut items = Vec::new();
+        loop {
+            let start = match self
+                .next_char()
+                .ok_or(FError::UnclosedClass(self.class_start))?
+            {
+                ']' if !items.is_empty() => break,
+                escape if self.is_escape(escape) => {
+                    let chr = self.next_char().ok_or(FError::BareEscapeInClass)?;
+                    items.push(ClassItem::Char(map_letter_escape(chr)));
+                    continue;
                 }
-                '}' => {
-                    self.state = State::Literal;
-                    if current.is_empty() && gathered.is_empty() {
-                        Ok(Some(r"\{\}".to_owned()))
-                    } else {
-                        gathered.push(current);
-                        Ok(Some(close_alternate(gathered)))
+                other => other,
+            };
+            if !self.next_char_if_eq('-') {
+                items.push(ClassItem::Char(start));
+                continue;
+            }
+            match self
+                .next_char()
+                .ok_or(FError::UnclosedClass(self.class_start))?
+            {
+                ']' => {
+                    items.push(ClassItem::Char(start));
+                    items.push(ClassItem::Char('-'));
+                    break;
+                }
+                escape if self.is_escape(escape) => {
+                    let end = self.next_char().ok_or(FError::BareEscapeInClass)?;
+                    items.push(class_span(start, map_letter_escape(end)));
+                }
+                end => items.push(class_span(start, end)),
+            }
+        }
+        Ok(Class {
+            negated,
+            items,
+            intersections: Vec::new(),
+        })
+    }
+
+    /// Escape a character in a class specification.
+    fn handle_class_escape(&mut self, mut acc: Class) -> NodeResult {
+        match self.next_char() {
+            Some(chr) => {
+                acc.items.push(ClassItem::Char(map_letter_escape(chr)));
+                self.state = State::Class(acc);
+                Ok(None)
+            }
+            None => Err(FError::BareEscapeInClass),
+        }
+    }
+
+    /// Handle a character within a class range.
+    fn handle_class_range(&mut self, mut acc: Class, start: char) -> NodeResult {
+        match self.next_char() {
+            Some(chr) => {
+                let end = match chr {
+                    escape if self.is_escape(escape) => {
+                        map_letter_escape(self.next_char().ok_or(FError::BareEscapeInClass)?)
+                    }
+                    ']' => {
+                        acc.items.push(ClassItem::Char(start));
+                        acc.items.push(ClassItem::Char('-'));
+                        return self.close_class(acc);
                     }
+                    other => other,
+                };
+                // A reversed range is reported by `close_class()`.
+                acc.items.push(class_span(start, end));
+                self.state = State::Class(acc);
+                Ok(None)
+            }
+            None => Err(FError::UnclosedClass(self.class_start)),
+        }
+    }
+
+    /// Handle a dash immediately following a range within a character class.
+    #[allow(clippy::panic_in_result_fn)]
+    #[allow(clippy::unreachable)]
+    fn handle_class_range_dash(&mut self, mut acc: Class) -> NodeResult {
+        match self.next_char() {
+            Some(chr) => {
+                if chr == ']' {
+                    acc.items.push(ClassItem::Char('-'));
+                    self.close_class(acc)
+                } else if let Some(ClassItem::Range(start, end)) = acc.items.pop() {
+                    Err(FError::RangeAfterRange(start, end))
+                } else {
+                    // Let's hope the optimizer hears us...
+                    unreachable!()
                 }
-                '\\' => {
+            }
+            None => Err(FError::UnclosedClass(self.class_start)),
+        }
+    }
+
+    /// Start a set of alternatives.
+    fn handle_alternate(&mut self, mut current: String, mut gathered: Vec<String>) -> NodeResult {
+        match self.next_char() {
+            Some(chr) => match chr {
+                ',' => {
+                    gathered.push(current);
+                    self.state = State::Alternate(String::new(), gathered);
+                    Ok(None)
+                }
+                '}' => {
+                    self.state = State::Literal;
+                    if current.is_empty() && gathered.is_empty() {
+                        Ok(match self.options.empty_brace {
+                            EmptyBrace::Literal => Some(Node::Literal("{}".to_owned())),
+                            EmptyBrace::Empty => None,
+                        })
+                    } else {
+                        gathered.push(current);
+                        let (options, segment_start) = (self.options, self.alternate_segment_start);
+                        let mut position = self.alternate_start + 1;
+                        let branches = gathered
+                            .into_iter()
+                            .map(|branch| {
+                                let start = position;
+                                position += branch.chars().count() + 1;
+                                parse_chars(branch.chars(), start, segment_start, options)
+                            })
+                            .collect::<Result<_, _>>()?;
+                        Ok(Some(Node::Alternation(branches)))
+                    }
+                }
+                escape if self.is_escape(escape) => {
                     self.state = State::AlternateEscape(current, gathered);
                     Ok(None)
                 }
-                '[' => Err(FError::NotImplemented(
-                    "FIXME: alternate character class".to_owned(),
-                )),
+                special @ ('[' | '{')
+                    if self.options.escape_by_doubling && self.next_char_if_eq(special) =>
+                {
+                    current.push(special);
+                    current.push(special);
+                    self.state = State::Alternate(current, gathered);
+                    Ok(None)
+                }
+                '[' => {
+                    self.copy_alternate_class(&mut current)?;
+                    self.state = State::Alternate(current, gathered);
+                    Ok(None)
+                }
+                '{' if self.next_char_if_eq('}') => {
+                    current.push_str("{}");
+                    self.state = State::Alternate(current, gathered);
+                    Ok(None)
+                }
+                '{' if self.options.counted_repetition
+                    && ends_with_question(&current, self.options.escape_char) =>
+                {
+                    self.copy_alternate_repetition(&mut current)?;
+                    self.state = State::Alternate(current, gathered);
+                    Ok(None)
+                }
+                '{' => Err(FError::Unsupported {
+                    feature: "nested_alternation",
+                }),
                 other => {
                     current.push(other);
                     self.state = State::Alternate(current, gathered);
                     Ok(None)
                 }
             },
-            None => Err(FError::UnclosedAlternation),
+            None => Err(FError::UnclosedAlternation(self.alternate_start)),
+        }
+    }
+
+    /// Copy a character class within an alternation branch verbatim, so
+    /// that the branch may be parsed later; the `[` was already consumed.
+    /// Only the end of the class is looked for, any other errors are
+    /// reported when the branch is parsed.
+    fn copy_alternate_class(&mut self, current: &mut String) -> Result<(), FError> {
+        self.class_start = self.position - 1;
+        current.push('[');
+        let mut first = true;
+        if self.next_char_if_eq('!') {
+            current.push('!');
+            first = false;
+        }
+        if self.next_char_if_eq(']') {
+            current.push(']');
+            first = false;
+        }
+        let mut depth = 1_usize;
+        while depth > 0 {
+            let chr = self
+                .next_char()
+                .ok_or(FError::UnclosedClass(self.class_start))?;
+            current.push(chr);
+            match chr {
+                escape if self.is_escape(escape) => {
+                    current.push(self.next_char().ok_or(FError::BareEscapeInClass)?);
+                }
+                '[' if depth == 1 && self.next_char_if_eq(':') => {
+                    let (negated, name, colon) = self.scan_class_name();
+                    current.push(':');
+                    current.extend(negated.then_some('^'));
+                    current.push_str(&name);
+                    current.extend(colon.then_some(':'));
+                    if colon && !name.is_empty() && self.next_char_if_eq(']') {
+                        current.push(']');
+                    }
+                }
+                '&' if depth == 1
+                    && !first
+                    && self.options.class_intersection
+                    && self.next_char_if_eq('&') =>
+                {
+                    current.push('&');
+                    if self.next_char_if_eq('[') {
+                        current.push('[');
+                        depth += 1;
+                        if self.next_char_if_eq('^') {
+                            current.push('^');
+                        } else if self.next_char_if_eq('!') {
+                            current.push('!');
+                        }
+                        if self.next_char_if_eq(']') {
+                            current.push(']');
+                        }
+                    }
+                }
+                ']' => depth -= 1,
+                _ => (),
+            }
+            first = false;
+        }
+        Ok(())
+    }
+
+    /// Copy a `{N}` repetition count following a `?` wildcard within
+    /// an alternation branch verbatim, up to and including the `}`;
+    /// the `{` was already consumed.
+    fn copy_alternate_repetition(&mut self, current: &mut String) -> Result<(), FError> {
+        current.push('{');
+        loop {
+            let chr = self
+                .next_char()
+                .ok_or(FError::UnclosedAlternation(self.alternate_start))?;
+            current.push(chr);
+            if chr == '}' {
+                return Ok(());
+            }
         }
     }
 
-    /// Escape a character within a list of alternatives.
+    /// Escape a character within a list of alternatives; the escape sequence
+    /// is kept as it is, so that the branch may be parsed later.
     fn handle_alternate_escape(
         &mut self,
         mut current: String,
         gathered: Vec<String>,
-    ) -> StringResult {
-        match self.pattern.next() {
+    ) -> NodeResult {
+        match self.next_char() {
             Some(chr) => {
-                current.push(map_letter_escape(chr));
+                current.extend(self.options.escape_char);
+                current.push(chr);
                 self.state = State::Alternate(current, gathered);
                 Ok(None)
             }
-            None => Err(FError::UnclosedAlternation),
+            None => Err(FError::UnclosedAlternation(self.alternate_start)),
         }
     }
 }
 
-impl<I> Iterator for GlobIterator<I>
+impl<I> Iterator for GlobIterator<'_, I>
 where
     I: Iterator<Item = char>,
 {
-    type Item = StringResult;
+    type Item = Result<Option<(Node, Span)>, FError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match mem::take(&mut self.state) {
-            State::Start => Some(Ok(Some(self.handle_start()))),
+        let res = match mem::take(&mut self.state) {
             State::End => None,
-            State::Literal => Some(Ok(self.handle_literal())),
+            State::Literal => {
+                self.node_start = self.byte_position;
+                Some(self.handle_literal())
+            }
             State::Escape => Some(self.handle_escape()),
             State::ClassStart => Some(self.handle_class_start()),
             State::Class(acc) => Some(self.handle_class(acc)),
@@ -585,10 +1829,844 @@ where
             State::AlternateEscape(current, gathered) => {
                 Some(self.handle_alternate_escape(current, gathered))
             }
+        };
+        let span = Span {
+            start: self.node_start,
+            end: self.byte_position,
+        };
+        if matches!(res, Some(Ok(Some(_)))) {
+            self.node_start = self.byte_position;
+        }
+        res.map(|item| item.map(|found| found.map(|node| (node, span))))
+    }
+}
+
+/// Strip the leading slash from a pattern relative to the root.
+/// Return the rest of the pattern and whether it was anchored to the root.
+fn strip_root<'pat>(pattern: &'pat str, options: &GlobOptions) -> (&'pat str, bool) {
+    let sep = options.separator;
+    let stripped = if options.escape_char == Some(sep) {
+        pattern
+            .strip_prefix(sep)
+            .and_then(|rest| rest.strip_prefix(sep))
+    } else {
+        pattern.strip_prefix(sep)
+    };
+    match stripped {
+        Some(body) if options.root_relative => (body, true),
+        _ => (pattern, false),
+    }
+}
+
+/// The flags set by a leading `(?flags)` group.
+#[derive(Debug, Default)]
+struct Flags {
+    /// The `i` flag: match letters case-insensitively.
+    case_insensitive: bool,
+    /// The `s` flag: let `.` match a newline.
+    dot_matches_newline: bool,
+}
+
+impl Flags {
+    /// Build the flags string for a regular expression group, e.g. `is`.
+    fn to_regex(&self) -> String {
+        format!(
+            "{}{}",
+            if self.case_insensitive { "i" } else { "" },
+            if self.dot_matches_newline { "s" } else { "" }
+        )
+    }
+}
+
+/// Strip a leading `(?flags)` group if the `inline_flags` option is enabled.
+/// Only a group containing one or more ASCII letters is recognized, so that
+/// e.g. `(?)` or `(?1)` are still treated as glob pattern characters.
+fn strip_flags<'pat>(
+    pattern: &'pat str,
+    options: &GlobOptions,
+) -> Result<(Flags, &'pat str), FError> {
+    let group = pattern
+        .strip_prefix("(?")
+        .and_then(|rest| rest.split_once(')'))
+        .filter(|&(spec, _)| !spec.is_empty() && spec.chars().all(|chr| chr.is_ascii_alphabetic()));
+    match group {
+        Some((spec, rest)) if options.inline_flags => {
+            let mut flags = Flags::default();
+            for chr in spec.chars() {
+                match chr {
+                    'i' => flags.case_insensitive = true,
+                    's' => flags.dot_matches_newline = true,
+                    _ => return Err(FError::InvalidFlags(spec.to_owned())),
+                }
+            }
+            Ok((flags, rest))
+        }
+        _ => Ok((Flags::default(), pattern)),
+    }
+}
+
+/// Check whether the text collected for an alternation branch so far ends
+/// with a `?` wildcard, not an escaped `\?` character.
+fn ends_with_question(text: &str, escape: Option<char>) -> bool {
+    text.strip_suffix('?').is_some_and(|rest| {
+        rest.chars()
+            .rev()
+            .take_while(|&chr| Some(chr) == escape)
+            .count()
+            % 2
+            == 0
+    })
+}
+
+/// Merge the adjacent literal elements of a sequence again after
+/// it has been simplified.
+#[cfg(feature = "regex")]
+fn merge_literals(nodes: Vec<Node>) -> Vec<Node> {
+    nodes
+        .into_iter()
+        .coalesce(|first, second| match (first, second) {
+            (Node::Literal(mut text), Node::Literal(more)) => {
+                text.push_str(&more);
+                Ok(Node::Literal(text))
+            }
+            (first_node, second_node) => Err((first_node, second_node)),
+        })
+        .collect()
+}
+
+/// Split the body of a glob pattern into its elements along with their spans;
+/// the whole pattern is needed to report the positions of any errors within
+/// it and to compute the byte offsets of the elements.
+fn parse_nodes(
+    pattern: &str,
+    body: &str,
+    options: &GlobOptions,
+) -> Result<(Vec<Node>, Vec<Span>), FError> {
+    let byte_position = pattern.len().saturating_sub(body.len());
+    let position = pattern
+        .get(..byte_position)
+        .map_or(0, |prefix| prefix.chars().count());
+    let mut nodes = if options.unclosed_class_literal {
+        parse_spanned_chars(
+            escape_unclosed_classes(body, options).chars(),
+            (position, byte_position),
+            true,
+            options,
+        )?
+    } else {
+        parse_spanned_chars(body.chars(), (position, byte_position), true, options)?
+    };
+    if options.trailing_star_is_recursive {
+        if let Some(&mut (ref mut last, _)) = nodes.last_mut() {
+            if *last == Node::Star {
+                *last = Node::StarTail;
+            }
+        }
+    }
+    if options.treat_as_prefix
+        && !matches!(
+            nodes.last(),
+            Some(&(Node::Star | Node::StarTail | Node::GlobstarTail, _))
+        )
+    {
+        let end = Span {
+            start: pattern.len(),
+            end: pattern.len(),
+        };
+        nodes.push((Node::Star, end));
+    }
+    Ok(nodes.into_iter().unzip())
+}
+
+/// Split a sequence of pattern characters into its elements; the position
+/// of the first character is used for reporting the positions of errors.
+/// If `segment_start` is set, the characters start a path component, e.g.
+/// the whole pattern or an alternation branch right after a slash.
+fn parse_chars<I>(
+    chars: I,
+    position: usize,
+    segment_start: bool,
+    options: &GlobOptions,
+) -> Result<Vec<Node>, FError>
+where
+    I: Iterator<Item = char>,
+{
+    Ok(
+        parse_spanned_chars(chars, (position, 0), segment_start, options)?
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect(),
+    )
+}
+
+/// Split a sequence of pattern characters into its elements along with their
+/// spans; the character position and the byte offset of the first character
+/// are used for reporting the positions of errors and for the spans.
+/// The adjacent literal characters are merged into a single element, so that
+/// e.g. `generic` is matched and explained as a single literal string.
+fn parse_spanned_chars<I>(
+    chars: I,
+    (position, byte_position): (usize, usize),
+    segment_start: bool,
+    options: &GlobOptions,
+) -> Result<Vec<(Node, Span)>, FError>
+where
+    I: Iterator<Item = char>,
+{
+    let parser = GlobIterator {
+        pattern: chars.peekable(),
+        options,
+        state: State::Literal,
+        segment_start,
+        position,
+        byte_position,
+        node_start: byte_position,
+        class_start: 0,
+        alternate_start: 0,
+        alternate_segment_start: false,
+    };
+    let mut res: Vec<(Node, Span)> = Vec::new();
+    for item in parser {
+        let Some((node, span)) = item? else {
+            continue;
+        };
+        match (res.last_mut(), node) {
+            (Some(&mut (Node::Literal(ref mut text), ref mut last)), Node::Literal(more)) => {
+                text.push_str(&more);
+                last.end = span.end;
+            }
+            (_, other) => res.push((other, span)),
+        }
+    }
+    Ok(res)
+}
+
+/// Quickly check whether a string looks like a glob pattern, i.e. whether
+/// it contains any unescaped `*`, `?`, `[`, or `{` characters, without
+/// parsing it, e.g. so that a command-line argument may be treated as
+/// a literal path otherwise. The check is conservative: a pattern that
+/// contains e.g. an empty `{}` brace pair may still only match a single
+/// string, and a pattern with syntax errors, e.g. `[abc`, is still reported
+/// as containing metacharacters.
+#[allow(clippy::missing_inline_in_public_items)]
+#[must_use]
+pub fn has_glob_metachars(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' | '{' => return true,
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Check whether a regular expression snippet looks like a single character
+/// class: a `[...]` one, a `\w`-like escape, a `\p{...}` Unicode one, or
+/// a `.`. The regular expression engine will still reject an invalid one,
+/// e.g. `[z-a]`, when the pattern is compiled.
+fn is_single_char_class(class: &str) -> bool {
+    let mut chars = class.chars();
+    match (chars.next(), chars.next()) {
+        (Some('.'), None) => true,
+        (Some('\\'), Some('d' | 'D' | 's' | 'S' | 'w' | 'W')) => chars.next().is_none(),
+        (Some('\\'), Some('p' | 'P')) => {
+            let name = chars.as_str();
+            (name.len() == 1 && name.chars().all(|chr| chr.is_ascii_alphabetic()))
+                || name
+                    .strip_prefix('{')
+                    .and_then(|inner| inner.strip_suffix('}'))
+                    .is_some_and(|inner| !inner.is_empty() && !inner.contains(['{', '}']))
+        }
+        (Some('['), _) => {
+            let mut depth: usize = 0;
+            let mut rest = class.chars().peekable();
+            while let Some(chr) = rest.next() {
+                match chr {
+                    '\\' if rest.next().is_none() => return false,
+                    '[' => {
+                        depth = depth.saturating_add(1);
+                        // A `]` right after the opening bracket is literal.
+                        rest.next_if_eq(&'^');
+                        rest.next_if_eq(&']');
+                    }
+                    ']' => {
+                        depth = depth.saturating_sub(1);
+                        if depth == 0 {
+                            return rest.next().is_none();
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Make sure that the `single_char_class` option, if set, looks like
+/// a single character class.
+fn validate_single_char_class(options: &GlobOptions) -> Result<(), FError> {
+    match options.single_char_class {
+        Some(class) if !is_single_char_class(class.as_str()) => {
+            Err(FError::InvalidSingleCharClass(class.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Escape the `[` characters that do not start a properly closed character
+/// class, so that they are matched literally along with what follows them.
+/// Note that the positions of any errors after such a `[` will be off by one
+/// for each escape character added, or by two for each `[[]` class used
+/// instead if the `escape_char` option is not set.
+fn escape_unclosed_classes(body: &str, options: &GlobOptions) -> String {
+    let mut res = String::with_capacity(body.len());
+    let mut chars = body.chars().enumerate().peekable();
+    while let Some((pos, chr)) = chars.next() {
+        match chr {
+            _ if options.escape_char == Some(chr) => {
+                res.push(chr);
+                if let Some((_, next)) = chars.next() {
+                    res.push(next);
+                }
+            }
+            '[' if options.escape_by_doubling
+                && chars.next_if(|&(_, next)| next == '[').is_some() =>
+            {
+                res.push_str("[[");
+            }
+            '[' => {
+                let mut class = String::from("[");
+                let mut ahead = chars.clone();
+                if copy_segment_class(&mut ahead, pos, options.escape_char, &mut class).is_ok() {
+                    res.push_str(&class);
+                    chars = ahead;
+                } else {
+                    match options.escape_char {
+                        Some(escape) => {
+                            res.push(escape);
+                            res.push('[');
+                        }
+                        None if options.escape_by_doubling => res.push_str("[["),
+                        // Without an escape character, a class that only
+                        // contains a `[` is the next best thing.
+                        None => res.push_str("[[]"),
+                    }
+                }
+            }
+            _ => res.push(chr),
+        }
+    }
+    res
+}
+
+/// Copy a character class to the current path component when splitting
+/// a pattern; the opening `[` at the `start` position has already been copied.
+fn copy_segment_class<I>(
+    chars: &mut Peekable<I>,
+    start: usize,
+    escape: Option<char>,
+    res: &mut String,
+) -> Result<(), FError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    if let Some((_, chr)) = chars.next_if(|&(_, chr)| chr == '!') {
+        res.push(chr);
+    }
+    if let Some((_, chr)) = chars.next_if(|&(_, chr)| chr == ']') {
+        res.push(chr);
+    }
+    loop {
+        match chars.next() {
+            Some((_, chr)) if Some(chr) == escape => {
+                res.push(chr);
+                res.push(chars.next().ok_or(FError::UnclosedClass(start))?.1);
+            }
+            Some((_, '[')) if chars.next_if(|&(_, chr)| chr == ':').is_some() => {
+                // A named class, e.g. `[:alpha:]`, may contain a `]` of its own.
+                res.push_str("[:");
+                let mut prev = ':';
+                loop {
+                    let (_, chr) = chars.next().ok_or(FError::UnclosedClass(start))?;
+                    res.push(chr);
+                    if prev == ':' && chr == ']' {
+                        break;
+                    }
+                    prev = chr;
+                }
+            }
+            Some((_, ']')) => {
+                res.push(']');
+                return Ok(());
+            }
+            Some((_, chr)) => res.push(chr),
+            None => return Err(FError::UnclosedClass(start)),
+        }
+    }
+}
+
+/// Split a glob pattern into its path components on the separator, e.g.
+/// `src/*/{a,b}.rs` into `src`, `*`, and `{a,b}.rs`, without parsing
+/// the components themselves. A separator within a character class or
+/// an alternation does not split the pattern, e.g. `{a/b,c}/d` is split
+/// into `{a/b,c}` and `d`, and neither does an escaped one unless
+/// the separator is a backslash, which is written as `\\` in the pattern.
+/// The escape sequences are kept as they are. A leading or trailing
+/// separator produces an empty component, e.g. `/etc/` is split into
+/// an empty string, `etc`, and another empty string. The default syntax
+/// is assumed, e.g. a `[[` is always the start of a character class.
+///
+/// # Errors
+/// [`crate::error::Error::UnclosedClass`] or
+/// [`crate::error::Error::UnclosedAlternation`] if the pattern ends within
+/// a character class or an alternation.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn split_segments(pattern: &str, sep: char) -> Result<Vec<String>, FError> {
+    let mut res = Vec::new();
+    let mut current = String::new();
+    let mut braces = Vec::new();
+    let mut chars = pattern.chars().enumerate().peekable();
+    while let Some((pos, chr)) = chars.next() {
+        match chr {
+            '\\' => match chars.next() {
+                Some((_, '\\')) if sep == '\\' && braces.is_empty() => {
+                    res.push(mem::take(&mut current));
+                }
+                Some((_, next)) => {
+                    current.push(chr);
+                    current.push(next);
+                }
+                None => current.push(chr),
+            },
+            '[' => {
+                current.push(chr);
+                copy_segment_class(&mut chars, pos, Some('\\'), &mut current)?;
+            }
+            '{' => {
+                braces.push(pos);
+                current.push(chr);
+            }
+            '}' => {
+                braces.pop();
+                current.push(chr);
+            }
+            _ if chr == sep && braces.is_empty() => res.push(mem::take(&mut current)),
+            _ => current.push(chr),
+        }
+    }
+    if let Some(&start) = braces.first() {
+        return Err(FError::UnclosedAlternation(start));
+    }
+    res.push(current);
+    Ok(res)
+}
+
+/// Note the features used by a character class; the opening `[` at
+/// the `start` position has already been consumed.
+#[cfg(feature = "regex")]
+fn scan_class_features<I>(
+    chars: &mut Peekable<I>,
+    start: usize,
+    features: &mut FeatureSet,
+) -> Result<(), FError>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    chars.next_if(|&(_, chr)| chr == '!');
+    let mut empty = chars.next_if(|&(_, chr)| chr == ']').is_none();
+    loop {
+        match chars.next() {
+            Some((_, '\\')) => {
+                chars.next().ok_or(FError::UnclosedClass(start))?;
+            }
+            Some((_, '[')) if chars.next_if(|&(_, chr)| chr == ':').is_some() => {
+                features.named_class = true;
+                let mut prev = ':';
+                loop {
+                    let (_, chr) = chars.next().ok_or(FError::UnclosedClass(start))?;
+                    if prev == ':' && chr == ']' {
+                        break;
+                    }
+                    prev = chr;
+                }
+            }
+            Some((_, '&'))
+                if !empty
+                    && chars.next_if(|&(_, chr)| chr == '&').is_some()
+                    && chars.next_if(|&(_, chr)| chr == '[').is_some() =>
+            {
+                features.class_intersection = true;
+                scan_class_features(chars, start, features)?;
+            }
+            Some((_, ']')) => return Ok(()),
+            Some(_) => (),
+            None => return Err(FError::UnclosedClass(start)),
         }
+        empty = false;
+    }
+}
+
+/// Scan a glob pattern for the syntax features that it uses without
+/// parsing it, assuming that all of them are enabled and that the separator
+/// is a slash.
+#[cfg(feature = "regex")]
+pub(crate) fn scan_features(pattern: &str) -> Result<FeatureSet, FError> {
+    let mut features = FeatureSet {
+        inline_flags: ast::looks_like_flags(pattern),
+        ..FeatureSet::default()
+    };
+    let mut braces = Vec::new();
+    let mut prev = None;
+    let mut chars = pattern.chars().enumerate().peekable();
+    while let Some((pos, chr)) = chars.next() {
+        match chr {
+            '\\' => {
+                chars.next();
+            }
+            '*' if matches!(prev, None | Some('/' | '{' | ','))
+                && chars.next_if(|&(_, next)| next == '*').is_some() =>
+            {
+                features.globstar |= matches!(chars.peek(), None | Some(&(_, '/' | ',' | '}')));
+            }
+            '?' if chars.next_if(|&(_, next)| next == '{').is_some() => {
+                let mut count = String::new();
+                while let Some((_, digit)) = chars.next_if(|&(_, next)| next.is_ascii_digit()) {
+                    count.push(digit);
+                }
+                if !count.is_empty() && chars.next_if(|&(_, next)| next == '}').is_some() {
+                    features.counted_repetition = true;
+                } else {
+                    // Not a repetition count after all, but an alternation.
+                    features.nested_alternation |= !braces.is_empty();
+                    braces.push(pos + 1);
+                }
+            }
+            '[' => scan_class_features(&mut chars, pos, &mut features)?,
+            '{' => {
+                features.nested_alternation |= !braces.is_empty();
+                braces.push(pos);
+            }
+            '}' => {
+                braces.pop();
+            }
+            _ => (),
+        }
+        prev = Some(chr);
+    }
+    match braces.first() {
+        Some(&start) => Err(FError::UnclosedAlternation(start)),
+        None => Ok(features),
     }
 }
 
+/// Parse a shell glob-like pattern into its elements using the specified
+/// conversion options; the options are needed since e.g. the meaning of
+/// `**` depends on the `globstar` one.
+///
+/// # Errors
+/// [`crate::error::Error::ConflictingOptions`] if some of the options may not
+/// be used together; most of the other [`crate::error::Error`] values, mostly
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn parse(pattern: &str, options: &GlobOptions) -> Result<GlobAst, FError> {
+    options.validate()?;
+    validate_single_char_class(options)?;
+    let (flags, unflagged) = strip_flags(pattern, options)?;
+    let (body, anchored_root) = strip_root(unflagged, options);
+    let (nodes, spans) = parse_nodes(pattern, body, options)?;
+    let any_level = (options.match_at_any_level || options.root_relative)
+        && !anchored_root
+        && !separator_skeleton(&nodes, options.separator)
+            .trim_end_matches(options.separator)
+            .contains(options.separator);
+    Ok(GlobAst {
+        nodes,
+        spans,
+        anchored_root,
+        any_level,
+        case_insensitive: flags.case_insensitive,
+        dot_matches_newline: flags.dot_matches_newline,
+    })
+}
+
+/// Render the elements of a pattern as the separators they contain with
+/// a placeholder for anything else, e.g. `a*/b` as `../.` for a slash
+/// separator; the separators within alternation branches are included, too.
+fn separator_skeleton(nodes: &[Node], sep: char) -> String {
+    let other = if sep == '.' { '_' } else { '.' };
+    nodes
+        .iter()
+        .map(|node| match *node {
+            Node::Literal(ref text) => text
+                .chars()
+                .map(|chr| if chr == sep { sep } else { other })
+                .collect(),
+            Node::GlobstarDirs => format!("{}{}", other, sep),
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .map(|branch| separator_skeleton(branch, sep))
+                .collect(),
+            _ => other.to_string(),
+        })
+        .collect()
+}
+
+/// Build the regular expression pattern string for a parsed glob pattern,
+/// anchored as specified by the options; if `bytes` is set, the pattern is
+/// meant for matching byte strings that may not be valid UTF-8.
+pub(crate) fn ast_to_regex_string(ast: &GlobAst, options: &GlobOptions, bytes: bool) -> String {
+    let flags = Flags {
+        case_insensitive: ast.case_insensitive,
+        dot_matches_newline: ast.dot_matches_newline,
+    }
+    .to_regex();
+    let full_end = if options.optional_trailing_separator {
+        format!("{}?$", escape(options.separator))
+    } else {
+        "$".to_owned()
+    };
+    let (start, end) = match options.anchoring {
+        Anchoring::Full if ast.any_level => (
+            format!(
+                "^(?:{}*{})?",
+                wildcard_unit(".".to_owned(), options, bytes),
+                escape(options.separator)
+            ),
+            full_end,
+        ),
+        Anchoring::Full => ("^".to_owned(), full_end),
+        Anchoring::Start if ast.any_level => (
+            format!(
+                "^(?:{}*{})?",
+                wildcard_unit(".".to_owned(), options, bytes),
+                escape(options.separator)
+            ),
+            String::new(),
+        ),
+        Anchoring::Start => ("^".to_owned(), String::new()),
+        Anchoring::Unanchored => (String::new(), String::new()),
+    };
+    format!(
+        "{}{}{}{}",
+        if flags.is_empty() {
+            String::new()
+        } else {
+            format!("(?{})", flags)
+        },
+        start,
+        ast_body_regex(ast, options, bytes),
+        end
+    )
+}
+
+/// Build the regular expression pattern string for the elements of a parsed
+/// glob pattern, without any anchors or flags.
+fn ast_body_regex(ast: &GlobAst, options: &GlobOptions, bytes: bool) -> String {
+    match ast::globstar_parent(&ast.nodes, options.separator) {
+        Some(parent) if options.collapse_globstar_edges => format!(
+            "{}(?:{}{})?",
+            nodes_to_regex(&parent, options, bytes, true),
+            escape(options.separator),
+            globstar_tail(options, bytes)
+        ),
+        _ => nodes_to_regex(&ast.nodes, options, bytes, true),
+    }
+}
+
+/// Compile the regular expression for a parsed glob pattern; the original
+/// pattern is only used for reporting errors.
+#[cfg(feature = "regex")]
+pub(crate) fn ast_to_regex(
+    pattern: &str,
+    ast: &GlobAst,
+    options: &GlobOptions,
+) -> Result<Regex, FError> {
+    let re_pattern = ast_to_regex_string(ast, options, false);
+    RegexBuilder::new(&re_pattern)
+        .unicode(options.unicode)
+        .build()
+        .map_err(|err| FError::invalid_regex(pattern, &re_pattern, &err))
+}
+
+/// Compile the regular expression for the prefixes of the texts that a parsed
+/// glob pattern may match; return `None` if any text is such a prefix, e.g.
+/// if the pattern may match anywhere within the text, or if the regular
+/// expression could not be compiled.
+#[cfg(feature = "regex")]
+pub(crate) fn ast_to_prefix_regex(ast: &GlobAst, options: &GlobOptions) -> Option<Regex> {
+    if ast.any_level || !matches!(options.anchoring, Anchoring::Full) {
+        return None;
+    }
+    let flags = Flags {
+        case_insensitive: ast.case_insensitive,
+        dot_matches_newline: ast.dot_matches_newline,
+    }
+    .to_regex();
+    let body = match ast::globstar_parent(&ast.nodes, options.separator) {
+        Some(parent) if options.collapse_globstar_edges => format!(
+            "(?:{}|{}(?:{}{})?)",
+            nodes_to_prefix_regex(&parent, options, Lead::Start),
+            nodes_to_regex(&parent, options, false, true),
+            escape(options.separator),
+            globstar_tail_prefix(options)
+        ),
+        _ => nodes_to_prefix_regex(&ast.nodes, options, Lead::Start),
+    };
+    let full = if options.optional_trailing_separator {
+        format!(
+            "(?:{}|{}{})",
+            body,
+            ast_body_regex(ast, options, false),
+            escape(options.separator)
+        )
+    } else {
+        body
+    };
+    RegexBuilder::new(&format!(
+        "{}^{}$",
+        if flags.is_empty() {
+            String::new()
+        } else {
+            format!("(?{})", flags)
+        },
+        full
+    ))
+    .unicode(options.unicode)
+    .build()
+    .ok()
+}
+
+/// Compile the regular expression that reports which branch of the first
+/// top-level alternation of a parsed glob pattern was used for matching,
+/// in a group named `branchN` for the `N`-th branch; return `None` if there
+/// is no such alternation or if the regular expression could not be compiled.
+#[cfg(feature = "regex")]
+pub(crate) fn ast_to_branch_regex(ast: &GlobAst, options: &GlobOptions) -> Option<Regex> {
+    let (pos, branches) = ast
+        .nodes
+        .iter()
+        .enumerate()
+        .find_map(|(pos, node)| match *node {
+            Node::Alternation(ref branches) => Some((pos, branches)),
+            _ => None,
+        })?;
+    let alts: Vec<String> = branches
+        .iter()
+        .enumerate()
+        .map(|(idx, branch)| {
+            // The whole pattern with only this branch left in the alternation,
+            // so that the context of the branch is handled as usual.
+            let mut nodes = ast.nodes.clone();
+            if let Some(node) = nodes.get_mut(pos) {
+                *node = Node::Alternation(vec![branch.clone()]);
+            }
+            let single = GlobAst {
+                nodes,
+                spans: ast.spans.clone(),
+                ..*ast
+            };
+            format!(
+                "(?P<branch{}>{})",
+                idx,
+                ast_to_regex_string(&single, options, false)
+            )
+        })
+        .collect();
+    RegexBuilder::new(&alts.join("|"))
+        .unicode(options.unicode)
+        .build()
+        .ok()
+}
+
+/// The characters tried for a character class when generating sample
+/// matches, after the ones mentioned in the class itself.
+#[cfg(feature = "regex")]
+const SAMPLE_CHARS: &str = "aZ0_-.~ \u{e9}\u{3b1}";
+
+/// Pick up to two characters that a character class matches, trying
+/// the ones mentioned in the class first.
+#[cfg(feature = "regex")]
+fn sample_class_chars(class: &Class, options: &GlobOptions) -> Vec<String> {
+    let node = Node::Class(class.clone());
+    let Ok(re) = Regex::new(&format!(
+        "^(?:{})$",
+        node_to_regex(&node, options, false, false)
+    )) else {
+        return Vec::new();
+    };
+    class
+        .items
+        .iter()
+        .flat_map(|item| match *item {
+            ClassItem::Char(chr) => vec![chr],
+            ClassItem::Range(start, end) => vec![start, end],
+            ClassItem::Named { .. } => Vec::new(),
+        })
+        .chain(SAMPLE_CHARS.chars())
+        .map(String::from)
+        .filter(|text| re.is_match(text))
+        .unique()
+        .take(2)
+        .collect()
+}
+
+/// Generate up to `max` strings that a sequence of elements may match,
+/// trying a few fixed strings for the wildcards and the character classes
+/// and enumerating the alternation branches.
+#[cfg(feature = "regex")]
+pub(crate) fn sample_texts(nodes: &[Node], options: &GlobOptions, max: usize) -> Vec<String> {
+    let sep = options.separator;
+    nodes.iter().fold(vec![String::new()], |prefixes, node| {
+        let choices: Vec<String> = match *node {
+            Node::Literal(ref text) => vec![text.clone()],
+            Node::AnyChar => vec!["x".to_owned()],
+            Node::AnyChars(count) => vec!["x".repeat(count)],
+            Node::Star => vec![String::new(), "x".to_owned(), "xyz".to_owned()],
+            Node::GlobstarDirs => {
+                vec![String::new(), format!("d{}", sep), format!("d{0}e{0}", sep)]
+            }
+            Node::GlobstarTail | Node::StarTail => {
+                vec![String::new(), "x".to_owned(), format!("d{}x", sep)]
+            }
+            Node::Class(ref class) => sample_class_chars(class, options),
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .flat_map(|branch| sample_texts(branch, options, max))
+                .unique()
+                .take(max)
+                .collect(),
+        };
+        prefixes
+            .iter()
+            .cartesian_product(&choices)
+            .map(|(prefix, choice)| format!("{}{}", prefix, choice))
+            .take(max)
+            .collect()
+    })
+}
+
+/// Compile the regular expression for matching byte strings against a parsed
+/// glob pattern: the wildcards also match bytes that may never start a valid
+/// UTF-8 sequence, one at a time.
+#[cfg(feature = "regex")]
+pub(crate) fn ast_to_bytes_regex(
+    pattern: &str,
+    ast: &GlobAst,
+    options: &GlobOptions,
+) -> Result<BytesRegex, FError> {
+    let re_pattern = ast_to_regex_string(ast, options, true);
+    BytesRegexBuilder::new(&re_pattern)
+        .unicode(options.unicode)
+        .build()
+        .map_err(|err| FError::invalid_regex(pattern, &re_pattern, &err))
+}
+
 /// Parse a shell glob-like pattern into a regular expression pattern string.
 ///
 /// See the module-level documentation for a description of the pattern
@@ -597,12 +2675,188 @@ where
 /// # Errors
 /// Most of the [`crate::error::Error`] values, mostly syntax errors in
 /// the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
 pub fn glob_to_regex_string(pattern: &str) -> Result<String, FError> {
-    let parser = GlobIterator {
-        pattern: pattern.chars(),
-        state: State::Start,
+    glob_to_regex_string_with(pattern, &GlobOptions::default())
+}
+
+/// Parse a shell glob-like pattern into a regular expression pattern string
+/// using the specified conversion options.
+///
+/// # Errors
+/// [`crate::error::Error::ConflictingOptions`] if some of the options may not
+/// be used together; most of the other [`crate::error::Error`] values, mostly
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn glob_to_regex_string_with(pattern: &str, options: &GlobOptions) -> Result<String, FError> {
+    Ok(ast_to_regex_string(
+        &parse(pattern, options)?,
+        options,
+        false,
+    ))
+}
+
+/// Parse a shell glob-like pattern into a regular expression fragment that
+/// may be embedded into a larger regular expression.
+///
+/// The fragment is not anchored at the start or the end, and the options
+/// that depend on the pattern's position within the text, `match_at_any_level`
+/// and `root_relative`, are ignored, so e.g. a leading slash is matched
+/// literally. All the other transformations still apply: the characters
+/// special to regular expressions are escaped, the `*` and `?` wildcards and
+/// the character classes never match a slash, and a `**` component is handled
+/// according to the `globstar` option, a trailing one matching any text.
+///
+/// The fragment never contains a top-level `|`, so it may be concatenated
+/// with other regular expression pieces; it should be wrapped in `(?:...)` if
+/// a quantifier is to be applied to it. Note that each `{...}` alternation
+/// is a capturing group unless the `compact_alternations` option is enabled.
+/// If the `inline_flags` option is enabled and the pattern starts with
+/// a `(?flags)` group, the fragment is wrapped in a `(?flags:...)` one, so
+/// that the flags do not affect the rest of the larger regular expression.
+///
+/// # Errors
+/// [`crate::error::Error::ConflictingOptions`] if some of the options may not
+/// be used together; most of the other [`crate::error::Error`] values, mostly
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn glob_to_regex_fragment(pattern: &str, options: &GlobOptions) -> Result<String, FError> {
+    options.validate()?;
+    validate_single_char_class(options)?;
+    let (flags, body) = strip_flags(pattern, options)?;
+    let (nodes, _) = parse_nodes(pattern, body, options)?;
+    let fragment = nodes_to_regex(&nodes, options, false, true);
+    let spec = flags.to_regex();
+    Ok(if spec.is_empty() {
+        fragment
+    } else {
+        format!("(?{}:{})", spec, fragment)
+    })
+}
+
+/// Translate a `[...]` character class the way Python's `fnmatch` module
+/// does, starting right after the `[` character; return the regular
+/// expression and the position right after the closing `]`, or `None` if
+/// the class is not closed and the `[` should be matched literally.
+fn python_class(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut end = start;
+    if chars.get(end) == Some(&'!') {
+        end += 1;
+    }
+    if chars.get(end) == Some(&']') {
+        end += 1;
+    }
+    while chars.get(end).is_some_and(|chr| *chr != ']') {
+        end += 1;
+    }
+    let stuff = chars.get(start..end).filter(|_| end < chars.len())?;
+    let (negated, body) = match *stuff {
+        ['!', ref rest @ ..] => (true, rest),
+        _ => (false, stuff),
     };
-    Ok(parser.flatten_ok().collect::<Result<Vec<_>, _>>()?.join(""))
+
+    // Split the class at the dashes that denote ranges, skipping the first
+    // character and the one right after each range.
+    let mut chunks: Vec<Vec<char>> = Vec::new();
+    let mut chunk_start = 0;
+    let mut search = 1;
+    while let Some(dash) = body
+        .iter()
+        .skip(search)
+        .position(|chr| *chr == '-')
+        .map(|offset| search + offset)
+    {
+        chunks.push(body[chunk_start..dash].to_vec());
+        chunk_start = dash + 1;
+        search = dash + 3;
+    }
+    match (&body[chunk_start..], chunks.last_mut()) {
+        (&[], Some(last)) => last.push('-'),
+        (rest, _) => chunks.push(rest.to_vec()),
+    }
+
+    // Remove the reversed ranges, e.g. `z-a`, as Python does.
+    for idx in (1..chunks.len()).rev() {
+        if let (Some(&last), Some(&first)) = (
+            chunks.get(idx - 1).and_then(|prev| prev.last()),
+            chunks.get(idx).and_then(|next| next.first()),
+        ) {
+            if last > first {
+                let next = chunks.remove(idx);
+                if let Some(prev) = chunks.get_mut(idx - 1) {
+                    prev.pop();
+                    prev.extend(next.into_iter().skip(1));
+                }
+            }
+        }
+    }
+
+    let items = chunks
+        .iter()
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|chr| escape_in_class(*chr))
+                .collect::<String>()
+        })
+        .join("-");
+    Some((
+        match (negated, items.is_empty()) {
+            (false, true) => NEVER.to_owned(),
+            (true, true) => ".".to_owned(),
+            (true, false) => format!("[^{}]", items),
+            (false, false) => format!("[{}]", items),
+        },
+        end + 1,
+    ))
+}
+
+/// Translate a shell glob-like pattern into a regular expression the way
+/// Python's `fnmatch.translate()` function does, e.g. for code ported from
+/// Python.
+///
+/// Unlike the native conversion, there is no path awareness at all: `*`
+/// matches any sequence of characters, including slashes and newlines,
+/// `?` matches any single character, and a `[...]` class may also match
+/// a slash. A backslash is an ordinary character, there are no `{...}`
+/// alternations, a `[` without a closing `]` is matched literally, and
+/// a reversed range in a class, e.g. `z-a`, matches nothing. No options
+/// are honored.
+///
+/// The result differs from Python's in a few ways that do not change what
+/// it matches: it starts with a `^` anchor, since Python's `re.match()`
+/// anchors the start implicitly, it ends with `\z` instead of `\Z`, which
+/// the `regex` crate does not support, it does not use atomic groups for
+/// multiple `*` wildcards, and some characters are escaped differently,
+/// e.g. `*.txt` becomes `^(?s:.*\.txt)\z`.
+///
+/// # Errors
+/// None so far; any pattern may be translated.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn python_fnmatch_translate(pattern: &str) -> Result<String, FError> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut res = String::new();
+    let mut idx = 0;
+    let mut prev_star = false;
+    while let Some(&chr) = chars.get(idx) {
+        idx += 1;
+        match chr {
+            // Consecutive `*` wildcards are redundant.
+            '*' if prev_star => (),
+            '*' => res.push_str(".*"),
+            '?' => res.push('.'),
+            '[' => match python_class(&chars, idx) {
+                Some((class, end)) => {
+                    res.push_str(&class);
+                    idx = end;
+                }
+                None => res.push_str("\\["),
+            },
+            other => res.push_str(&escape(other)),
+        }
+        prev_star = chr == '*';
+    }
+    Ok(format!("^(?s:{})\\z", res))
 }
 
 /// Parse a shell glob-like pattern into a regular expression.
@@ -616,6 +2870,291 @@ pub fn glob_to_regex_string(pattern: &str) -> Result<String, FError> {
 #[allow(clippy::missing_inline_in_public_items)]
 #[cfg(feature = "regex")]
 pub fn glob_to_regex(pattern: &str) -> Result<Regex, FError> {
-    let re_pattern = glob_to_regex_string(pattern)?;
-    Regex::new(&re_pattern).map_err(|err| FError::InvalidRegex(re_pattern, err.to_string()))
+    glob_to_regex_with(pattern, &GlobOptions::default())
+}
+
+/// Parse a shell glob-like pattern, specified as a sequence of characters,
+/// into a regular expression, e.g. if it was built as a `Vec<char>`.
+///
+/// The characters are fed to the parser directly, without building a string
+/// first; thus only the default conversion options are supported, since
+/// e.g. recognizing a leading `(?flags)` group needs to look ahead.
+///
+/// # Errors
+/// Most of the [`crate::error::Error`] values, mostly syntax errors in
+/// the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+#[cfg(feature = "regex")]
+pub fn glob_to_regex_chars<I>(chars: I) -> Result<Regex, FError>
+where
+    I: IntoIterator<Item = char>,
+{
+    let options = GlobOptions::default();
+    // Only kept for reporting errors.
+    let mut pattern = String::new();
+    let (nodes, spans) = parse_spanned_chars(
+        chars.into_iter().inspect(|&chr| pattern.push(chr)),
+        (0, 0),
+        true,
+        &options,
+    )?
+    .into_iter()
+    .unzip();
+    let ast = GlobAst {
+        nodes,
+        spans,
+        anchored_root: false,
+        any_level: false,
+        case_insensitive: false,
+        dot_matches_newline: false,
+    };
+    ast_to_regex(&pattern, &ast, &options)
+}
+
+/// Parse a shell glob-like pattern into a regular expression using
+/// the specified conversion options.
+///
+/// # Errors
+/// [`crate::error::Error::ConflictingOptions`] if some of the options may not
+/// be used together; most of the other [`crate::error::Error`] values, mostly
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+#[cfg(feature = "regex")]
+pub fn glob_to_regex_with(pattern: &str, options: &GlobOptions) -> Result<Regex, FError> {
+    ast_to_regex(pattern, &parse(pattern, options)?, options)
+}
+
+/// Merge the characters and ranges of a character class into as few ranges
+/// as possible, e.g. `[abcx-z]` into `[a-cx-z]`.
+#[cfg(feature = "regex")]
+fn compact_class(class: Class) -> Class {
+    let (chars, named): (Vec<_>, Vec<_>) = class
+        .items
+        .into_iter()
+        .partition(|item| !matches!(*item, ClassItem::Named { .. }));
+    let ranges = ast::merge_ranges(
+        chars
+            .into_iter()
+            .filter_map(|item| match item {
+                ClassItem::Char(chr) => Some((u32::from(chr), u32::from(chr))),
+                ClassItem::Range(start, end) => Some((u32::from(start), u32::from(end))),
+                ClassItem::Named { .. } => None,
+            })
+            .collect(),
+    );
+    Class {
+        negated: class.negated,
+        items: ranges
+            .into_iter()
+            .filter_map(|(start, end)| {
+                Some(class_span(char::from_u32(start)?, char::from_u32(end)?))
+            })
+            .chain(named)
+            .collect(),
+        intersections: class.intersections.into_iter().map(compact_class).collect(),
+    }
+}
+
+/// Get the characters that an alternation branch may match if it consists of
+/// a single character or a simple character class that may be merged with
+/// the other branches into a single class; a separator or, if the leading
+/// dot is special, a dot is not, since a class would not match it.
+#[cfg(feature = "regex")]
+fn single_char_items(branch: &[Node], options: &GlobOptions) -> Option<Vec<ClassItem>> {
+    match *branch {
+        [Node::Literal(ref text)] => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(chr), None)
+                    if chr != options.separator && (chr != '.' || !options.literal_leading_dot) =>
+                {
+                    Some(vec![ClassItem::Char(chr)])
+                }
+                _ => None,
+            }
+        }
+        [Node::Class(ref class)] if !class.negated && class.intersections.is_empty() => {
+            Some(class.items.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Simplify a sequence of glob pattern elements without changing the texts
+/// that it matches: merge the ranges of the character classes, drop
+/// the duplicate alternation branches, replace an alternation of single
+/// characters with a character class, and inline an alternation with
+/// a single branch.
+#[cfg(feature = "regex")]
+fn compact_nodes(nodes: Vec<Node>, options: &GlobOptions) -> Vec<Node> {
+    merge_literals(
+        nodes
+            .into_iter()
+            .flat_map(|node| match node {
+                Node::Class(class) => vec![Node::Class(compact_class(class))],
+                Node::Alternation(branches) => {
+                    let mut unique: Vec<Vec<Node>> = Vec::with_capacity(branches.len());
+                    for branch in branches {
+                        let compacted = compact_nodes(branch, options);
+                        if !unique.contains(&compacted) {
+                            unique.push(compacted);
+                        }
+                    }
+                    if unique.len() == 1 {
+                        return unique.pop().unwrap_or_default();
+                    }
+                    match unique
+                        .iter()
+                        .map(|branch| single_char_items(branch, options))
+                        .collect::<Option<Vec<_>>>()
+                    {
+                        Some(items) => vec![Node::Class(compact_class(Class {
+                            negated: false,
+                            items: items.concat(),
+                            intersections: Vec::new(),
+                        }))],
+                        None => vec![Node::Alternation(unique)],
+                    }
+                }
+                other => vec![other],
+            })
+            .collect(),
+    )
+}
+
+/// Simplify a sequence of glob pattern elements for rendering it back as
+/// a shorter pattern: inline an alternation with a single distinct branch,
+/// replace a character class that only matches a single character with
+/// that character, and collapse the runs of `*` wildcards and of `**`
+/// globstar components.
+#[cfg(feature = "regex")]
+pub(crate) fn simplify_nodes(nodes: Vec<Node>, options: &GlobOptions) -> Vec<Node> {
+    let mut res: Vec<Node> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let simplified = match node {
+            Node::Class(class) => {
+                let compacted = compact_class(class);
+                match *compacted.items.as_slice() {
+                    [ClassItem::Char(chr)]
+                        if !compacted.negated
+                            && compacted.intersections.is_empty()
+                            && (chr != '.'
+                                || !(options.literal_leading_dot || options.dot_is_wildcard)) =>
+                    {
+                        vec![Node::Literal(chr.to_string())]
+                    }
+                    _ => vec![Node::Class(compacted)],
+                }
+            }
+            Node::Alternation(branches) => {
+                let mut unique: Vec<Vec<Node>> = Vec::with_capacity(branches.len());
+                for branch in branches {
+                    let simplified_branch = simplify_nodes(branch, options);
+                    if !unique.contains(&simplified_branch) {
+                        unique.push(simplified_branch);
+                    }
+                }
+                if unique.len() == 1 {
+                    unique.pop().unwrap_or_default()
+                } else {
+                    vec![Node::Alternation(unique)]
+                }
+            }
+            other => vec![other],
+        };
+        for item in simplified {
+            match (res.last_mut(), item) {
+                (Some(&mut Node::Literal(ref mut text)), Node::Literal(more)) => {
+                    text.push_str(&more);
+                }
+                (Some(&mut Node::Star), Node::Star)
+                | (Some(&mut Node::GlobstarDirs), Node::GlobstarDirs)
+                | (Some(&mut Node::StarTail), Node::Star | Node::StarTail) => {}
+                (Some(last @ &mut Node::Star), Node::StarTail) => *last = Node::StarTail,
+                (Some(last @ &mut Node::GlobstarDirs), Node::GlobstarTail)
+                    if !options.collapse_globstar_edges =>
+                {
+                    *last = Node::GlobstarTail;
+                }
+                (_, other) => res.push(other),
+            }
+        }
+    }
+    res
+}
+
+/// Render a parsed pattern back as a simpler equivalent glob pattern;
+/// return `None` if the rendered pattern would not be parsed back into
+/// the original pattern's regular expression using the same options,
+/// apart from the simplifications themselves, e.g. because of
+/// a `.` that the `dot_is_wildcard` option would turn into a wildcard, or
+/// because of a `*` that would become the last element of the pattern with
+/// the `trailing_star_is_recursive` option enabled. The implicit `*` added
+/// by the `treat_as_prefix` option is not rendered, since it will be added
+/// again when the pattern is parsed.
+#[cfg(feature = "regex")]
+pub(crate) fn ast_to_simplified(ast: &GlobAst, options: &GlobOptions) -> Option<String> {
+    let mut nodes = ast.nodes.clone();
+    let implicit_star = options.treat_as_prefix
+        && nodes.last() == Some(&Node::Star)
+        && ast.spans.last().is_some_and(|span| span.start == span.end);
+    if implicit_star {
+        nodes.pop();
+    }
+    let simplified = GlobAst {
+        nodes: simplify_nodes(nodes, options),
+        spans: Vec::new(),
+        ..*ast
+    };
+    let rendered = simplified.to_glob_string_with(options);
+
+    // The original pattern, including the implicit `*`, with the same
+    // simplifications applied, so that only its collapsed wildcards and
+    // inlined alternations may differ from the original regular expression.
+    let expected = GlobAst {
+        nodes: simplify_nodes(ast.nodes.clone(), options),
+        spans: Vec::new(),
+        ..*ast
+    };
+    let reparsed = parse(&rendered, options).ok()?;
+    let again = GlobAst {
+        nodes: simplify_nodes(reparsed.nodes, options),
+        ..reparsed
+    };
+    (ast_to_regex_string(&again, options, false) == ast_to_regex_string(&expected, options, false))
+        .then_some(rendered)
+}
+
+/// Parse a shell glob-like pattern into a regular expression using
+/// the specified conversion options, then make the regular expression
+/// more compact without changing the texts that it matches, e.g. by merging
+/// the ranges of character classes, replacing `{a,b,c}` with `[a-c]`, and,
+/// if that makes it shorter, merging the common prefixes of literal
+/// alternation branches as if the `compact_alternations` option were
+/// enabled. This may reduce
+/// the memory used by e.g. a large [`regex::RegexSet`] of the patterns;
+/// note that the capture groups of the alternations may not be preserved.
+///
+/// # Errors
+/// [`crate::error::Error::ConflictingOptions`] if some of the options may not
+/// be used together; most of the other [`crate::error::Error`] values, mostly
+/// syntax errors in the specified glob pattern.
+#[allow(clippy::missing_inline_in_public_items)]
+#[cfg(feature = "regex")]
+pub fn glob_to_regex_compact(pattern: &str, options: &GlobOptions) -> Result<Regex, FError> {
+    let ast = parse(pattern, options)?;
+    let compacted = GlobAst {
+        nodes: compact_nodes(ast.nodes, options),
+        spans: Vec::new(),
+        ..ast
+    };
+    let merged = options.compact_alternations(true);
+    let best = if ast_to_regex_string(&compacted, &merged, false).len()
+        < ast_to_regex_string(&compacted, options, false).len()
+    {
+        merged
+    } else {
+        *options
+    };
+    ast_to_regex(pattern, &compacted, &best)
 }
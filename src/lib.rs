@@ -57,12 +57,40 @@
 #![warn(clippy::branches_sharing_code)]
 #![warn(clippy::missing_const_for_fn)]
 
+pub mod ast;
+#[cfg(feature = "dfa")]
+pub mod dfa;
 pub mod error;
+pub mod expand;
 pub mod glob;
+#[cfg(feature = "regex")]
+pub mod map;
+#[cfg(feature = "regex")]
+pub mod matcher;
+pub mod options;
+#[cfg(feature = "regex")]
+pub mod set;
+#[cfg(feature = "regex")]
+mod walker;
 
+#[cfg(feature = "dfa")]
+pub use dfa::GlobDfa;
+pub use expand::expand_braces;
+pub use glob::{
+    escape_for_class, escape_literal, glob_to_regex_fragment, glob_to_regex_string,
+    glob_to_regex_string_with, has_glob_metachars, parse, python_fnmatch_translate, split_segments,
+};
+#[cfg(feature = "regex")]
+pub use glob::{glob_to_regex, glob_to_regex_chars, glob_to_regex_compact, glob_to_regex_with};
+#[cfg(feature = "regex")]
+pub use map::GlobMap;
+#[cfg(feature = "regex")]
+pub use matcher::{CaseMatch, Glob, MatchKind, SegmentKind};
+#[cfg(feature = "unicode-normalization")]
+pub use options::NormalizationForm;
+pub use options::{Anchoring, EmptyBrace, FeatureSet, GlobOptions, Preset, SingleCharClass};
 #[cfg(feature = "regex")]
-pub use glob::glob_to_regex;
-pub use glob::glob_to_regex_string;
+pub use set::{GlobSet, GlobSetBuilder};
 
 #[cfg(test)]
 pub mod tests;
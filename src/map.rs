@@ -0,0 +1,194 @@
+//! Map glob patterns to values, the first matching pattern winning.
+//!
+//! A [`GlobMap`] keeps a list of patterns along with the values associated
+//! with them, e.g. the MIME types of files with the specified extensions,
+//! and looks up the value of the first pattern that matches a text string:
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::GlobMap;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut types = GlobMap::new();
+//! types.insert("*.tar.gz", "application/x-compressed-tar")?;
+//! types.insert("*.gz", "application/gzip")?;
+//! assert_eq!(types.get("src.tar.gz"), Some(&"application/x-compressed-tar"));
+//! assert_eq!(types.get("log.gz"), Some(&"application/gzip"));
+//! assert_eq!(types.get("README"), None);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The [`GlobMap::get`] method makes no attempt to determine which pattern
+//! is more specific: if more than one of them matches, the one inserted first
+//! wins, so the more specific patterns should be inserted before the more
+//! general ones. The [`GlobMap::get_most_specific`] method picks the pattern
+//! with the most literal characters instead, similarly to the way
+//! `.gitattributes` or `.editorconfig` sections are usually laid out.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use std::cmp::Reverse;
+use std::sync::OnceLock;
+
+use regex::RegexSet;
+
+use crate::ast;
+use crate::error::Error as FError;
+use crate::matcher::Glob;
+use crate::set;
+
+/// A list of glob patterns and the values associated with them.
+#[derive(Debug, Clone)]
+pub struct GlobMap<V> {
+    /// The compiled patterns, in the order they were inserted.
+    globs: Vec<Glob>,
+    /// The values associated with the patterns, in the same order.
+    values: Vec<V>,
+    /// The set of the patterns' regular expressions, compiled on first use;
+    /// `None` if that failed or if any of the patterns normalizes the text.
+    set: OnceLock<Option<RegexSet>>,
+}
+
+impl<V> GlobMap<V> {
+    /// Create an empty map.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            globs: Vec::new(),
+            values: Vec::new(),
+            set: OnceLock::new(),
+        }
+    }
+
+    /// Compile a glob pattern using the default options and add it to
+    /// the end of the map, after all the previously inserted ones.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn insert(&mut self, pattern: &str, value: V) -> Result<(), FError> {
+        self.insert_glob(Glob::new(pattern)?, value);
+        Ok(())
+    }
+
+    /// Add an already compiled glob pattern to the end of the map, e.g. one
+    /// compiled using non-default options.
+    #[inline]
+    pub fn insert_glob(&mut self, glob: Glob, value: V) {
+        self.globs.push(glob);
+        self.values.push(value);
+        self.set = OnceLock::new();
+    }
+
+    /// Find the indices of all the patterns that match the specified text.
+    ///
+    /// The patterns' regular expressions are combined into a single set on
+    /// the first lookup after an insertion. In the unlikely case that this
+    /// fails, e.g. if the set would exceed the regular expression engine's
+    /// size limit, the patterns are tried one by one instead. The same goes
+    /// for the patterns that convert the text to a normalization form, since
+    /// each of them may use a different one, and for patterns compiled with
+    /// different values of the `unicode` option.
+    fn matching(&self, text: &str) -> Vec<usize> {
+        match *self
+            .set
+            .get_or_init(|| set::build_regex_set(&self.globs).ok())
+        {
+            Some(ref set) => set.matches(text).into_iter().collect(),
+            None => self
+                .globs
+                .iter()
+                .enumerate()
+                .filter(|&(_, glob)| glob.is_match(text))
+                .map(|(idx, _)| idx)
+                .collect(),
+        }
+    }
+
+    /// Return the value associated with the first inserted pattern that
+    /// matches the specified text, if any.
+    #[inline]
+    #[must_use]
+    pub fn get<T: AsRef<str>>(&self, text: T) -> Option<&V> {
+        self.matching(text.as_ref())
+            .first()
+            .and_then(|&idx| self.values.get(idx))
+    }
+
+    /// Return the value associated with the most specific pattern that
+    /// matches the specified text, if any, e.g. `src/*.rs` rather than `*.rs`
+    /// for `src/main.rs`.
+    ///
+    /// The pattern with the most literal characters wins; a tie is broken
+    /// by the fewest wildcards, i.e. `?`, `*`, `**`, and character classes,
+    /// and then by the insertion order. An alternation counts as a single
+    /// wildcard along with the branch with the fewest literal characters,
+    /// e.g. `*.{c,cc}` has two literal characters and two wildcards.
+    #[inline]
+    #[must_use]
+    pub fn get_most_specific<T: AsRef<str>>(&self, text: T) -> Option<&V> {
+        self.matching(text.as_ref())
+            .into_iter()
+            .filter_map(|idx| Some((idx, self.globs.get(idx)?)))
+            .min_by_key(|&(_, glob)| {
+                let (literal, wildcards) = ast::specificity(&glob.ast().nodes);
+                (Reverse(literal), wildcards)
+            })
+            .and_then(|(idx, _)| self.values.get(idx))
+    }
+
+    /// The number of patterns in the map.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.globs.len()
+    }
+
+    /// Check whether the map contains no patterns at all.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.globs.is_empty()
+    }
+
+    /// Iterate over the patterns and their values in the order they were
+    /// inserted.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&Glob, &V)> {
+        self.globs.iter().zip(&self.values)
+    }
+}
+
+impl<V> Default for GlobMap<V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,1217 @@
+//! A glob pattern compiled into a regular expression.
+//!
+//! The [`Glob`] type keeps the original pattern and the options used to
+//! compile it along with the resulting regular expression, so that it may
+//! be queried about the pattern's properties later.
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::{Glob, GlobOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let glob = Glob::with_options("/build", &GlobOptions::default().root_relative(true))?;
+//! assert!(glob.is_anchored_root());
+//! assert!(glob.is_match("build"));
+//! assert!(!glob.is_match("src/build"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A [`Glob`] is both [`Send`] and [`Sync`], so it may be shared among
+//! threads. Cloning it is cheap, since the clones share the same compiled
+//! regular expression; a handle to that may also be obtained using
+//! the [`Glob::regex_arc`] method.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use std::borrow::Cow;
+use std::ffi::{CStr, OsStr};
+#[cfg(feature = "walkdir")]
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
+#[cfg(feature = "walkdir")]
+use std::iter;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(feature = "walkdir")]
+use std::path::PathBuf;
+use std::path::{Component, Path};
+use std::sync::{Arc, OnceLock};
+
+use itertools::Itertools;
+use regex::bytes::Regex as BytesRegex;
+use regex::{Regex, RegexBuilder};
+#[cfg(feature = "walkdir")]
+use walkdir::WalkDir;
+
+#[cfg(feature = "walkdir")]
+use crate::ast::Node;
+use crate::ast::{self, GlobAst};
+#[cfg(feature = "dfa")]
+use crate::dfa::GlobDfa;
+use crate::error::Error as FError;
+use crate::expand::DEFAULT_MAX_BRACE_EXPANSIONS;
+use crate::glob as fglob;
+use crate::options::{Anchoring, FeatureSet, GlobOptions};
+use crate::set::GlobSet;
+use crate::walker;
+
+/// Convert a text string to the Unicode normalization form specified by
+/// the options, if any.
+#[cfg(feature = "unicode-normalization")]
+pub(crate) fn normalized<'text>(text: &'text str, options: &GlobOptions) -> Cow<'text, str> {
+    options
+        .normalize
+        .map_or(Cow::Borrowed(text), |form| form.normalize(text))
+}
+
+/// Leave a text string as it is, since Unicode normalization is not supported.
+#[cfg(not(feature = "unicode-normalization"))]
+pub(crate) const fn normalized<'text>(text: &'text str, _options: &GlobOptions) -> Cow<'text, str> {
+    Cow::Borrowed(text)
+}
+
+/// Check whether the options specify a Unicode normalization form that
+/// the text strings must be converted to before matching.
+#[cfg(feature = "unicode-normalization")]
+pub(crate) const fn normalizes(options: &GlobOptions) -> bool {
+    options.normalize.is_some()
+}
+
+/// Unicode normalization is not supported, so the text strings are never
+/// converted.
+#[cfg(not(feature = "unicode-normalization"))]
+pub(crate) const fn normalizes(_options: &GlobOptions) -> bool {
+    false
+}
+
+/// Convert a byte string to the Unicode normalization form specified by
+/// the options, if any, provided that it is valid UTF-8; any other byte
+/// string is left as it is.
+#[cfg(feature = "unicode-normalization")]
+fn normalized_bytes<'text>(bytes: &'text [u8], options: &GlobOptions) -> Cow<'text, [u8]> {
+    match (options.normalize, str::from_utf8(bytes)) {
+        (Some(form), Ok(text)) => match form.normalize(text) {
+            Cow::Borrowed(_) => Cow::Borrowed(bytes),
+            Cow::Owned(converted) => Cow::Owned(converted.into_bytes()),
+        },
+        _ => Cow::Borrowed(bytes),
+    }
+}
+
+/// Leave a byte string as it is, since Unicode normalization is not supported.
+#[cfg(not(feature = "unicode-normalization"))]
+const fn normalized_bytes<'text>(bytes: &'text [u8], _options: &GlobOptions) -> Cow<'text, [u8]> {
+    Cow::Borrowed(bytes)
+}
+
+/// Translate a pattern from the `glob` crate's syntax: there are no
+/// escape sequences and no alternations there.
+#[cfg(feature = "glob")]
+fn translate_glob_crate(pattern: &str) -> String {
+    let mut res = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '[' => {
+                res.push('[');
+                res.extend(chars.next_if_eq(&'!'));
+                res.extend(chars.next_if_eq(&']'));
+                for inner in chars.by_ref() {
+                    if inner == '\\' {
+                        res.push('\\');
+                    }
+                    res.push(inner);
+                    if inner == ']' {
+                        break;
+                    }
+                }
+            }
+            '\\' | '{' => {
+                res.push('\\');
+                res.push(chr);
+            }
+            other => res.push(other),
+        }
+    }
+    res
+}
+
+/// Make a few small changes to a text string that is matched by a pattern,
+/// hoping that the result will no longer be: swap or drop the last character,
+/// append a character, or insert a path separator at the start, in
+/// the middle, or at the end.
+fn near_misses(text: &str, separator: char) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let middle: String = chars.iter().take(chars.len() / 2).collect();
+    let rest: String = chars.iter().skip(chars.len() / 2).collect();
+    let mut res = Vec::new();
+    if let Some((&last, init)) = chars.split_last() {
+        let head: String = init.iter().collect();
+        res.push(format!("{}{}", head, if last == 'q' { 'z' } else { 'q' }));
+        res.push(head);
+    }
+    res.push(format!("{}~", text));
+    res.push(format!("{}{}", separator, text));
+    res.push(format!("{}{}{}", middle, separator, rest));
+    res.push(format!("{}{}x", text, separator));
+    res
+}
+
+/// Count the separators that a sequence of elements may match, if that is limited.
+#[cfg(feature = "walkdir")]
+fn max_slashes(nodes: &[Node], options: &GlobOptions) -> Option<usize> {
+    nodes
+        .iter()
+        .map(|node| match *node {
+            Node::Literal(ref text) => Some(text.matches(options.separator).count()),
+            Node::AnyChar | Node::AnyChars(_) | Node::Star => {
+                (!options.treat_slash_as_ordinary).then_some(0)
+            }
+            Node::Class(_) => {
+                (!options.treat_slash_as_ordinary && !options.classes_match_separator).then_some(0)
+            }
+            Node::GlobstarDirs | Node::GlobstarTail | Node::StarTail => None,
+            Node::Alternation(ref branches) => branches
+                .iter()
+                .map(|branch| max_slashes(branch, options))
+                .collect::<Option<Vec<_>>>()
+                .map(|counts| counts.into_iter().max().unwrap_or(0)),
+        })
+        .sum()
+}
+
+/// Find the literal leading directories of a pattern and the maximum number
+/// of path components that it may match, so that a directory walk may be pruned.
+#[cfg(feature = "walkdir")]
+fn walk_limits(ast: &GlobAst, options: &GlobOptions) -> (String, Option<usize>) {
+    if ast.any_level || ast.case_insensitive || !matches!(options.anchoring, Anchoring::Full) {
+        return (String::new(), None);
+    }
+    let leading: String = ast
+        .nodes
+        .iter()
+        .map_while(|node| match *node {
+            Node::Literal(ref text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let limit = max_slashes(&ast.nodes, options).map(|count| count + 1);
+    let prefix = leading
+        .rsplit_once(options.separator)
+        .map_or("", |(dirs, _)| dirs);
+    if prefix.split(options.separator).any(str::is_empty) {
+        // No leading directories, or empty components, e.g. `/abs` or `a//b`.
+        return (String::new(), limit);
+    }
+    (prefix.to_owned(), limit)
+}
+
+/// Compile a glob pattern once, on first use, and return a `&'static Glob`
+/// reference to it, e.g. `static_glob!("*.rs")` or
+/// `static_glob!("src/**/*.rs", GlobOptions::default().globstar(true))`.
+///
+/// The pattern is compiled lazily using a [`std::sync::LazyLock`], so
+/// an invalid pattern is NOT detected during the build; instead, the first
+/// evaluation of the macro will panic with a message naming the pattern
+/// and the error. Use [`Glob::new`] or [`Glob::with_options`] for patterns
+/// that may not be valid, e.g. ones specified by the user.
+///
+/// ```rust
+/// use fnmatch_regex::static_glob;
+///
+/// fn is_source(name: &str) -> bool {
+///     static_glob!("*.{rs,toml}").is_match(name)
+/// }
+///
+/// assert!(is_source("main.rs"));
+/// assert!(!is_source("README.md"));
+/// ```
+#[macro_export]
+macro_rules! static_glob {
+    ($pattern:expr) => {
+        $crate::static_glob!($pattern, $crate::GlobOptions::default())
+    };
+    ($pattern:expr, $options:expr) => {{
+        #[allow(clippy::panic)]
+        static GLOB: ::std::sync::LazyLock<$crate::Glob> = ::std::sync::LazyLock::new(|| {
+            $crate::Glob::with_options($pattern, &$options)
+                .unwrap_or_else(|err| panic!("Invalid glob pattern {:?}: {}", $pattern, err))
+        });
+        &*GLOB
+    }};
+}
+
+/// How specifically a pattern matched a text string, as reported by
+/// [`Glob::match_kind`]. The variants are ordered from the least to the most
+/// specific one, so that e.g. the best of several matching rules may be
+/// found using [`Iterator::max`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum MatchKind {
+    /// The pattern did not match the text.
+    None,
+    /// The pattern matched the text using wildcards, character classes,
+    /// alternations, or options that let it match more than one string.
+    Wildcard,
+    /// The pattern is a literal one and it is equal to the text.
+    Exact,
+}
+
+/// Whether a pattern matched a text string with the exact case of its letters,
+/// as reported by [`Glob::match_case_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CaseMatch {
+    /// The pattern did not match the text even if case is ignored.
+    None,
+    /// The pattern only matched the text if case is ignored.
+    CaseInsensitiveOnly,
+    /// The pattern matched the text with the exact case.
+    Exact,
+}
+
+/// The kind of a single path component of a pattern, as reported by
+/// [`Glob::segments`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SegmentKind {
+    /// A component that only contains literal text, with any escape
+    /// sequences resolved.
+    Literal(String),
+    /// A component that contains wildcards, character classes, or
+    /// alternations, e.g. `*.txt` or `{a,b}`.
+    Wildcard,
+    /// A `**` globstar component that may span any number of directory levels.
+    Globstar,
+}
+
+/// A glob pattern compiled into a regular expression.
+///
+/// Two globs are considered equal, and they hash the same, if they were
+/// compiled into the same regular expression, even if the patterns differ,
+/// e.g. `a.txt` and `a\.txt` are equal, while `a` and `{a}` are not.
+/// The `unicode` and `normalize` options must also be the same, since they
+/// change what the regular expression matches without changing its text.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    /// The original glob pattern.
+    pattern: String,
+    /// The options used to compile the pattern.
+    options: GlobOptions,
+    /// The parsed pattern.
+    ast: GlobAst,
+    /// The compiled regular expression, shared among the clones.
+    regex: Arc<Regex>,
+    /// The regular expression for byte strings, compiled on first use;
+    /// `None` if that failed.
+    bytes_regex: Arc<OnceLock<Option<BytesRegex>>>,
+    /// The case-insensitive form of the regular expression, compiled on
+    /// first use; `None` if that failed.
+    folded_regex: Arc<OnceLock<Option<Regex>>>,
+    /// The regular expression for the prefixes of the matching texts,
+    /// compiled on first use; `None` if any text is such a prefix.
+    prefix_regex: Arc<OnceLock<Option<Regex>>>,
+    /// The regular expression that reports the alternation branch used for
+    /// matching, compiled on first use; `None` if there is no alternation.
+    branch_regex: Arc<OnceLock<Option<Regex>>>,
+    /// The regular expression anchored only at the start of the text,
+    /// compiled on first use unless the pattern is already anchored so;
+    /// `None` if that failed.
+    start_regex: Arc<OnceLock<Option<Regex>>>,
+}
+
+impl Glob {
+    /// Compile a glob pattern using the default options.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn new(pattern: &str) -> Result<Self, FError> {
+        Self::with_options(pattern, &GlobOptions::default())
+    }
+
+    /// Compile a glob pattern using the specified options.
+    /// If the `normalize` option is set, the pattern is converted to that
+    /// Unicode normalization form first, so the positions of any errors and
+    /// the spans of the parsed elements refer to the converted pattern.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn with_options(pattern: &str, options: &GlobOptions) -> Result<Self, FError> {
+        let source = normalized(pattern, options);
+        let ast = fglob::parse(&source, options)?;
+        let regex = fglob::ast_to_regex(&source, &ast, options)?;
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            options: *options,
+            ast,
+            regex: Arc::new(regex),
+            bytes_regex: Arc::new(OnceLock::new()),
+            folded_regex: Arc::new(OnceLock::new()),
+            prefix_regex: Arc::new(OnceLock::new()),
+            branch_regex: Arc::new(OnceLock::new()),
+            start_regex: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// Compile a glob pattern using the default options, except for
+    /// the path separator character, e.g. `\\` for Windows paths. If that is
+    /// also the escape character, escaping is disabled, so that e.g.
+    /// `src\*.rs` would match `src\main.rs`; use [`Glob::with_options`]
+    /// with a different `escape_char` to match the special characters
+    /// literally.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn with_separator(pattern: &str, sep: char) -> Result<Self, FError> {
+        let options = GlobOptions::default().separator(sep);
+        let escaping = if options.escape_char == Some(sep) {
+            options.escape_char(None)
+        } else {
+            options
+        };
+        Self::with_options(pattern, &escaping)
+    }
+
+    /// Scan a glob pattern for the syntax features that it uses without
+    /// compiling it, e.g. `**` components or `?{N}` repetition counts, so
+    /// that a pattern that needs an option that is not enabled may be
+    /// rejected with a precise message, see [`FeatureSet::unsupported_by`].
+    /// Only the constructs that would need the corresponding option are
+    /// reported, e.g. `a**` is not reported as a globstar, since it is not
+    /// a whole path component, and `?{a,b}` is not reported as a repetition
+    /// count, since there is no number within the braces.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::UnclosedClass`] or
+    /// [`crate::error::Error::UnclosedAlternation`] if the pattern ends within
+    /// a character class or an alternation.
+    #[inline]
+    pub fn required_features(pattern: &str) -> Result<FeatureSet, FError> {
+        fglob::scan_features(pattern)
+    }
+
+    /// Compile a pattern that matches the specified text exactly, treating
+    /// all the glob special characters in it as literal ones.
+    /// The [`Glob::pattern`] method will return the text with the special
+    /// characters escaped.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::InvalidRegex`] if the text is too long for
+    /// the regular expression engine.
+    #[inline]
+    pub fn literal(text: &str) -> Result<Self, FError> {
+        Self::new(&fglob::escape_literal(text))
+    }
+
+    /// Compile a pattern that matches files within the specified directory.
+    /// The directory name is matched literally, even if it contains any glob
+    /// special characters, and the wildcards in the pattern will only match
+    /// the names within that directory, e.g. `Glob::under_dir("src*", "*.rs")`
+    /// would match `src*/main.rs`, but not `src1/main.rs` or `src*/a/main.rs`.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn under_dir(dir: &str, pattern: &str) -> Result<Self, FError> {
+        if dir.is_empty() {
+            return Self::new(pattern);
+        }
+        let prefix = fglob::escape_literal(dir.strip_suffix('/').unwrap_or(dir));
+        Self::new(&format!("{}/{}", prefix, pattern))
+    }
+
+    /// Compile a pattern from the `glob` crate, translating its syntax.
+    /// A `**` path component is handled as if the `globstar` option were
+    /// enabled, the backslash and the opening brace are matched literally,
+    /// and a `[^...]` character class is not negated.
+    ///
+    /// There are some semantic differences that are not translated:
+    /// the `glob` crate's `*` and `?` wildcards match a slash unless
+    /// the `require_literal_separator` option is passed, while ours never do;
+    /// the `require_literal_leading_dot` and `case_sensitive` options of
+    /// the `glob` crate have no counterparts here.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly unsupported
+    /// constructs in the specified glob pattern.
+    #[cfg(feature = "glob")]
+    #[inline]
+    pub fn from_glob_crate_pattern(pattern: &glob::Pattern) -> Result<Self, FError> {
+        Self::with_options(
+            &translate_glob_crate(pattern.as_str()),
+            &GlobOptions::default().globstar(true),
+        )
+    }
+
+    /// The original glob pattern.
+    #[inline]
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The options used to compile the pattern.
+    #[inline]
+    #[must_use]
+    pub const fn options(&self) -> &GlobOptions {
+        &self.options
+    }
+
+    /// The parsed pattern.
+    #[inline]
+    #[must_use]
+    pub const fn ast(&self) -> &GlobAst {
+        &self.ast
+    }
+
+    /// The compiled regular expression.
+    #[inline]
+    #[must_use]
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// A shared handle to the compiled regular expression.
+    #[inline]
+    #[must_use]
+    pub fn regex_arc(&self) -> Arc<Regex> {
+        Arc::clone(&self.regex)
+    }
+
+    /// The source of the compiled regular expression used for matching
+    /// strings, e.g. for debugging.
+    #[inline]
+    #[must_use]
+    pub fn as_regex_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// Build a dense deterministic automaton for the pattern, e.g. for
+    /// matching a lot of text strings in a hot loop; see the [`crate::dfa`]
+    /// module for the trade-offs involved.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::InvalidRegex`] if the automaton could not be
+    /// built, e.g. if it would exceed the configured size limits.
+    #[cfg(feature = "dfa")]
+    #[inline]
+    pub fn to_dfa(&self) -> Result<GlobDfa, FError> {
+        GlobDfa::new(self)
+    }
+
+    /// The source of the regular expression used by [`Glob::is_match_bytes`]
+    /// for matching byte strings, e.g. for debugging: it differs from
+    /// the one returned by [`Glob::as_regex_str`] in that the wildcards and
+    /// the negated character classes also match single non-ASCII bytes,
+    /// e.g. the Latin-1 `\xe9` in `caf\xe9.txt`.
+    /// The source is generated anew each time, even if the byte string
+    /// regular expression has not been compiled yet or could not be.
+    #[inline]
+    #[must_use]
+    pub fn as_regex_bytes_str(&self) -> String {
+        fglob::ast_to_regex_string(&self.ast, &self.options, true)
+    }
+
+    /// The names of the capturing groups in the compiled regular expression,
+    /// as returned by [`Regex::capture_names`]: the first one is always
+    /// `None` for the implicit group that matches the whole text. Each
+    /// `{...}` alternation is an unnamed group unless the `compact_alternations`
+    /// option is enabled.
+    #[inline]
+    #[must_use]
+    pub fn capture_names(&self) -> Vec<Option<&str>> {
+        self.regex.capture_names().collect()
+    }
+
+    /// Get the index of the branch of an alternation that the specified text
+    /// was matched by, e.g. 1 for `api/post/new` and the `api/{get,post}/*`
+    /// pattern. Only the first alternation among the top-level elements of
+    /// the pattern is considered, not any within a later one; note that
+    /// an optional character class, e.g. `[0-9]?`, is also an alternation.
+    /// If the text may be matched using several branches, the first one
+    /// listed in the pattern is reported.
+    ///
+    /// Return `None` if the text does not match the pattern or if
+    /// the pattern contains no alternation. The regular expression with
+    /// a capturing group for each branch is compiled on first use; in
+    /// the unlikely case that this fails, `None` is always returned.
+    #[inline]
+    #[must_use]
+    pub fn matched_branch(&self, text: &str) -> Option<usize> {
+        let regex = self
+            .branch_regex
+            .get_or_init(|| fglob::ast_to_branch_regex(&self.ast, &self.options))
+            .as_ref()?;
+        let source = normalized(text, &self.options);
+        let caps = regex.captures(&source)?;
+        regex
+            .capture_names()
+            .flatten()
+            .find(|name| caps.name(name).is_some())
+            .and_then(|name| name.strip_prefix("branch")?.parse().ok())
+    }
+
+    /// Check whether the specified text matches the pattern.
+    /// The text may be anything that may be viewed as a string slice,
+    /// e.g. a `&str`, a `String`, or a `Cow<str>`.
+    #[inline]
+    #[must_use]
+    pub fn is_match<T: AsRef<str>>(&self, text: T) -> bool {
+        self.regex
+            .is_match(&normalized(text.as_ref(), &self.options))
+    }
+
+    /// Check whether the specified text matches the pattern and report
+    /// how specifically it did, e.g. `a.txt` would match the `a.txt` pattern
+    /// exactly, while `*.txt` would match it using wildcards.
+    /// See [`Glob::unescape_literal`] for the patterns considered literal.
+    #[inline]
+    #[must_use]
+    pub fn match_kind<T: AsRef<str>>(&self, text: T) -> MatchKind {
+        if !self.is_match(text) {
+            MatchKind::None
+        } else if self.is_literal() {
+            MatchKind::Exact
+        } else {
+            MatchKind::Wildcard
+        }
+    }
+
+    /// Find the first of the specified texts that matches the pattern, e.g.
+    /// for checking the several aliases of a file against a rule; the rest
+    /// of the texts are not examined. Return `None` if none of them match.
+    #[inline]
+    #[must_use]
+    pub fn matches_any_of<'text, I>(&self, texts: I) -> Option<&'text str>
+    where
+        I: IntoIterator<Item = &'text str>,
+    {
+        texts.into_iter().find(|text| self.is_match(text))
+    }
+
+    /// Build a set that matches the texts that this pattern matches, but
+    /// the other one does not, e.g. all the `*.log` files except `debug.log`.
+    /// The regular expression engine does not support look-around assertions,
+    /// so the patterns may not be combined into a single one; instead,
+    /// the other pattern is added to the set as a negated rule, so that it
+    /// overrides this one. Thus the set may be combined with more patterns
+    /// later, but it may not be converted into a [`regex::RegexSet`].
+    #[inline]
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> GlobSet {
+        let mut res = GlobSet::new();
+        res.insert_glob(self.clone());
+        res.insert_negated_glob(other.clone());
+        res
+    }
+
+    /// Check whether the specified byte string, e.g. a Unix filename that
+    /// is not necessarily valid UTF-8, matches the pattern.
+    ///
+    /// The valid UTF-8 sequences are matched in the same way as
+    /// by [`Glob::is_match`], e.g. `?` matches a whole multibyte character.
+    /// In addition, the `*`, `?`, and `**` wildcards, as well as the negated
+    /// character classes, match any non-ASCII byte on its own, e.g. `a?b`
+    /// would match `b"a\xffb"` and `caf?.txt` would match the Latin-1
+    /// `b"caf\xe9.txt"`; thus `??` may also match a two-byte UTF-8 character.
+    /// If the separator is not an ASCII character, only the bytes that may
+    /// never start a valid UTF-8 sequence are matched this way. Literal
+    /// characters in the pattern never match invalid UTF-8 bytes.
+    ///
+    /// The regular expression for byte strings is compiled on first use.
+    /// In the unlikely case that this fails, e.g. if the pattern is close to
+    /// the regular expression engine's size limit, the bytes are converted
+    /// to a string lossily and matched using [`Glob::is_match`] instead.
+    #[inline]
+    #[must_use]
+    pub fn is_match_bytes(&self, bytes: &[u8]) -> bool {
+        let source = normalized_bytes(bytes, &self.options);
+        match *self
+            .bytes_regex
+            .get_or_init(|| fglob::ast_to_bytes_regex(&self.pattern, &self.ast, &self.options).ok())
+        {
+            Some(ref regex) => regex.is_match(&source),
+            None => self.is_match(String::from_utf8_lossy(&source)),
+        }
+    }
+
+    /// Check whether a path, specified as a sequence of its components,
+    /// matches the pattern, e.g. `["src", "main.rs"]` for `src/main.rs`.
+    /// The components are joined using the separator, so that the pattern is
+    /// matched in the same way as for [`Glob::is_match`]; the components
+    /// themselves should not contain any separators.
+    ///
+    /// Each component is converted to a string lossily: any parts of it that
+    /// are not valid Unicode, e.g. invalid UTF-8 sequences on Unix-like
+    /// systems or unpaired UTF-16 surrogates on Windows, are replaced by
+    /// the `U+FFFD REPLACEMENT CHARACTER`. Such a component may still be
+    /// matched by wildcards, but never by a literal character.
+    /// On Unix-like systems, [`Glob::is_match_path`] matches the raw bytes
+    /// of a whole path instead.
+    #[inline]
+    #[must_use]
+    pub fn matches_components<I, S>(&self, components: I) -> bool
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let path = components
+            .into_iter()
+            .map(|comp| comp.as_ref().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(&self.options.separator.to_string());
+        self.regex.is_match(&normalized(&path, &self.options))
+    }
+
+    /// Check whether a path matches the pattern, using its raw bytes as
+    /// [`Glob::is_match_bytes`] does, so that a file name that is not valid
+    /// UTF-8 is still matched exactly, e.g. `*.txt` would match a Latin-1
+    /// name that contains a `\xe9` byte before the extension. The path is matched as
+    /// it is, without splitting it into components or normalizing it, so
+    /// e.g. `./src/main.rs` would not match `src/*.rs`.
+    #[cfg(unix)]
+    #[inline]
+    #[must_use]
+    pub fn is_match_path<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.is_match_bytes(path.as_ref().as_os_str().as_bytes())
+    }
+
+    /// Check whether a C string, e.g. a file name received over an FFI
+    /// boundary, matches the pattern, using its bytes up to, but not
+    /// including, the terminating NUL as [`Glob::is_match_bytes`] does, so
+    /// that the string is neither copied nor required to be valid UTF-8.
+    /// Since a C string may not contain a NUL byte before its end, the whole
+    /// string is always matched.
+    #[inline]
+    #[must_use]
+    pub fn is_match_cstr(&self, text: &CStr) -> bool {
+        self.is_match_bytes(text.to_bytes())
+    }
+
+    /// Read lines of text and return the ones that match the pattern along with
+    /// their 1-based line numbers, e.g. for a grep-like tool. The line endings,
+    /// either `\n` or `\r\n`, are stripped before matching. A read error,
+    /// e.g. invalid UTF-8 text, is returned in place of the line.
+    #[inline]
+    pub fn match_lines_numbered<R: BufRead>(
+        &self,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<(usize, String)>> {
+        let regex = Arc::clone(&self.regex);
+        let options = self.options;
+        reader
+            .lines()
+            .enumerate()
+            .filter_map(move |(idx, line)| match line {
+                Ok(text) => regex
+                    .is_match(&normalized(&text, &options))
+                    .then(|| Ok((idx + 1, text))),
+                Err(err) => Some(Err(err)),
+            })
+    }
+
+    /// Check whether a path within the `base` directory matches the pattern
+    /// relative to it, e.g. `/project/src/main.rs` would match `src/*.rs` if
+    /// `base` is `/project`. Return false if `path` is not within `base`.
+    /// The rest of the path is matched as by [`Glob::matches_components`], so
+    /// its components are joined using the separator regardless of the one
+    /// used by the operating system.
+    #[inline]
+    #[must_use]
+    pub fn matches_relative(&self, base: &Path, path: &Path) -> bool {
+        path.strip_prefix(base)
+            .is_ok_and(|rest| self.matches_components(rest.components().map(Component::as_os_str)))
+    }
+
+    /// Walk the directory tree under `root` and return the paths that match
+    /// the pattern, relative to `root`, e.g. `src/*.rs` would return
+    /// `root/src/main.rs`. The returned paths include the `root` prefix;
+    /// a leading `./` in the pattern stands for `root` itself, e.g.
+    /// `./src/*.rs` would also return `root/src/main.rs`.
+    ///
+    /// The walk starts at the literal leading directories of the pattern, if
+    /// any, e.g. `root/src` for `src/*.rs`, and it does not descend deeper
+    /// than the pattern may match unless it contains a `**` component;
+    /// a missing starting directory is not an error, there are simply no
+    /// matches. Any other errors from the directory walk, e.g. permission
+    /// problems or a missing `root`, are returned as they are encountered.
+    #[cfg(feature = "walkdir")]
+    #[inline]
+    pub fn walk(&self, root: &Path) -> impl Iterator<Item = io::Result<PathBuf>> {
+        let (prefix, limit) = walk_limits(&self.ast, &self.options);
+        let skipped = if prefix.is_empty() {
+            0
+        } else {
+            prefix.split(self.options.separator).count()
+        };
+        // `Path::components()` drops the `.` ones, so the pattern's leading
+        // `./` components are not looked for in the walked paths, but put
+        // back before matching them.
+        let dots = prefix
+            .split(self.options.separator)
+            .take_while(|&comp| comp == ".")
+            .count();
+        let start = root.join(
+            prefix
+                .split(self.options.separator)
+                .skip(dots)
+                .collect::<PathBuf>(),
+        );
+        let missing = skipped > 0
+            && matches!(
+                fs::symlink_metadata(&start),
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound
+            );
+        let mut walker = WalkDir::new(start).min_depth(usize::from(skipped == 0));
+        if let Some(components) = limit {
+            walker = walker.max_depth(components.saturating_sub(skipped));
+        }
+
+        let glob = self.clone();
+        let root_path = root.to_path_buf();
+        (!missing)
+            .then(|| walker.into_iter())
+            .into_iter()
+            .flatten()
+            .filter_map(move |entry| match entry {
+                Err(err) => Some(Err(io::Error::from(err))),
+                Ok(found) => {
+                    let path = found.into_path();
+                    let matched = path.strip_prefix(&root_path).is_ok_and(|relative| {
+                        glob.matches_components(
+                            iter::repeat_n(OsStr::new("."), dots)
+                                .chain(relative.components().map(|comp| comp.as_os_str())),
+                        )
+                    });
+                    matched.then_some(Ok(path))
+                }
+            })
+    }
+
+    /// Check whether the pattern matches a leading portion of the specified
+    /// text, as if the `anchoring` option were set to
+    /// [`crate::options::Anchoring::Start`], e.g. `ERROR*` would match
+    /// `ERROR: boom`, but not `WARN: ERROR`, whatever the anchoring
+    /// the pattern was compiled with. Unlike [`Glob::matches_prefix_of`],
+    /// which checks whether the text may still be completed to a matching
+    /// one, this checks whether the text already starts with a matching one.
+    ///
+    /// Unless the pattern was already compiled with that anchoring,
+    /// the regular expression is compiled on first use; in the unlikely case
+    /// that this fails, `false` is always returned.
+    #[inline]
+    #[must_use]
+    pub fn matches_prefix(&self, text: &str) -> bool {
+        if matches!(self.options.anchoring, Anchoring::Start) {
+            return self.is_match(text);
+        }
+        let source = normalized(text, &self.options);
+        self.start_regex
+            .get_or_init(|| {
+                let options = self.options.anchoring(Anchoring::Start);
+                fglob::ast_to_regex(&self.pattern, &self.ast, &options).ok()
+            })
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(&source))
+    }
+
+    /// Find the end of the shortest match of the pattern within
+    /// the specified text, as a byte offset, or `None` if there is no match.
+    /// This is only meaningful if the `anchoring` option is set to
+    /// [`crate::options::Anchoring::Unanchored`], e.g. to find where
+    /// the first path matching the pattern ends in a stream of paths;
+    /// with the default full anchoring, the result may only be the length of
+    /// the whole text or `None`. If the `normalize` option is set, the offset
+    /// is within the normalized text.
+    #[inline]
+    #[must_use]
+    pub fn shortest_match(&self, text: &str) -> Option<usize> {
+        self.regex.shortest_match(&normalized(text, &self.options))
+    }
+
+    /// Explain why the specified text does not match the pattern.
+    /// Return `None` if it does match; otherwise, return a description of
+    /// the point where the text diverges from the pattern, e.g.
+    /// `expected literal '.txt' at position 3 but found '.log'`.
+    /// The positions are counted in characters from the start of the text,
+    /// after converting it to a normalization form if the `normalize` option
+    /// is set.
+    /// If the pattern could fail in several ways, e.g. because of
+    /// an alternation or a `*` wildcard, the failure that got the furthest
+    /// into the text is reported.
+    #[inline]
+    #[must_use]
+    pub fn explain_mismatch(&self, text: &str) -> Option<String> {
+        let source = normalized(text, &self.options);
+        if self.regex.is_match(&source) {
+            return None;
+        }
+        Some(
+            walker::explain(&self.ast, &self.options, &source)
+                .unwrap_or_else(|| "the text does not match the pattern".to_owned()),
+        )
+    }
+
+    /// Check whether the specified text matches the pattern, ignoring
+    /// the case of ASCII letters, without compiling another regular expression.
+    /// Only the ASCII letters are folded, e.g. `*.TXT` would match `a.txt`,
+    /// but `Ä*` would not match `ä`. This walks the parsed pattern instead of
+    /// using the compiled regular expression, so it is slower than
+    /// [`Glob::is_match`]; it is meant for occasional checks.
+    #[inline]
+    #[must_use]
+    pub fn matches_ignore_ascii_case(&self, text: &str) -> bool {
+        walker::matches_ignore_ascii_case(
+            &self.ast,
+            &self.options,
+            &normalized(text, &self.options),
+        )
+    }
+
+    /// Check whether the specified text matches the pattern with the exact
+    /// case of its letters, or only if case is ignored, e.g. `Foo.txt` would
+    /// match the `foo.*` pattern only case-insensitively. This may help
+    /// tooling warn about case mismatches on case-insensitive, but
+    /// case-preserving, filesystems.
+    ///
+    /// The case-insensitive form of the regular expression is compiled on
+    /// first use. In the unlikely case that this fails, the text is matched
+    /// using [`Glob::matches_ignore_ascii_case`] instead.
+    #[inline]
+    #[must_use]
+    pub fn match_case_report(&self, text: &str) -> CaseMatch {
+        let source = normalized(text, &self.options);
+        if self.regex.is_match(&source) {
+            return CaseMatch::Exact;
+        }
+        let folded = match *self.folded_regex.get_or_init(|| {
+            RegexBuilder::new(self.regex.as_str())
+                .case_insensitive(true)
+                .unicode(self.options.unicode)
+                .build()
+                .ok()
+        }) {
+            Some(ref regex) => regex.is_match(&source),
+            None => walker::matches_ignore_ascii_case(&self.ast, &self.options, &source),
+        };
+        if folded {
+            CaseMatch::CaseInsensitiveOnly
+        } else {
+            CaseMatch::None
+        }
+    }
+
+    /// Check whether the specified text is a prefix of a text that the pattern
+    /// may match, e.g. so that a filter-as-you-type interface may keep
+    /// a suggestion while the user is still typing: `ab` is a prefix of
+    /// a text matched by `abc*`, while `xy` is not. Every text is such
+    /// a prefix if the pattern may match anywhere within a text string or
+    /// at any directory level.
+    ///
+    /// The regular expression for the prefixes is compiled on first use.
+    /// In the unlikely case that this fails, e.g. if the pattern is close to
+    /// the regular expression engine's size limit, every text is considered
+    /// a prefix, so that no suggestion is dropped.
+    #[inline]
+    #[must_use]
+    pub fn matches_prefix_of(&self, partial: &str) -> bool {
+        self.prefix_regex
+            .get_or_init(|| fglob::ast_to_prefix_regex(&self.ast, &self.options))
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(&normalized(partial, &self.options)))
+    }
+
+    /// Get the only string that the pattern matches, with any escape
+    /// sequences resolved, e.g. `foo*bar` for the `foo\*bar` pattern.
+    /// Return `None` if the pattern contains any wildcards, character classes,
+    /// or alternations, or if it may match more than one string because of
+    /// the options used, e.g. `match_at_any_level`, `optional_trailing_separator`,
+    /// or a `(?i)` flag.
+    #[inline]
+    #[must_use]
+    pub fn unescape_literal(&self) -> Option<String> {
+        if self.ast.any_level
+            || self.ast.case_insensitive
+            || self.options.optional_trailing_separator
+            || !matches!(self.options.anchoring, Anchoring::Full)
+        {
+            return None;
+        }
+        ast::literal_text(&self.ast.nodes)
+    }
+
+    /// Get a simpler pattern that matches the same texts when compiled using
+    /// the same options, e.g. for displaying or storing it: an alternation
+    /// with a single branch is inlined, e.g. `{a}` becomes `a`, a character
+    /// class that only matches a single character is replaced with it, e.g.
+    /// `[a]` becomes `a`, and the runs of `*` wildcards and of `**/` globstar
+    /// components are collapsed, e.g. `a**b` becomes `a*b` and `**/**/b`
+    /// becomes `**/b`. The result is rendered in the canonical form of
+    /// [`GlobAst::to_glob_string_with`], so the alternation branches are also
+    /// sorted and deduplicated; note that the capture groups of
+    /// the alternations are not preserved, e.g. in [`Glob::matched_branch`].
+    ///
+    /// The simplified pattern is compiled into the original pattern's regular
+    /// expression using the same options, except for the simplifications
+    /// themselves: e.g. `a**b` is compiled into `^a[^/]*b$` rather than
+    /// `^a[^/]*[^/]*b$`, and `{a}` into `^a$` rather than `^(a)$`.
+    ///
+    /// The implicit `*` of the `treat_as_prefix` option is not included.
+    /// If the simplified pattern would not be compiled into that regular
+    /// expression using the same options, e.g. because of an escaped `\.`
+    /// with the `dot_is_wildcard` option enabled, or because inlining `{*}`
+    /// at the end of the pattern would make the `*` recursive with
+    /// the `trailing_star_is_recursive` option enabled, the original pattern
+    /// is returned.
+    #[inline]
+    #[must_use]
+    pub fn simplify(&self) -> String {
+        fglob::ast_to_simplified(&self.ast, &self.options).unwrap_or_else(|| self.pattern.clone())
+    }
+
+    /// The minimum number of path components that any text matched by
+    /// the pattern must have, e.g. 3 for `a/*/c`, 2 for `a/**/c`, since
+    /// a `**` component may match no directories at all, and 1 for `*.txt`,
+    /// so that e.g. a path router may skip the patterns that could never
+    /// match a path with fewer components without trying them. The components
+    /// are counted as the number of separators plus one, so e.g. `/etc` has
+    /// two of them, the first one empty.
+    #[inline]
+    #[must_use]
+    pub fn required_min_components(&self) -> usize {
+        let sep = self.options.separator;
+        let count = match ast::globstar_parent(&self.ast.nodes, sep) {
+            Some(parent) if self.options.collapse_globstar_edges => {
+                ast::min_separators(&parent, sep)
+            }
+            _ => ast::min_separators(&self.ast.nodes, sep),
+        };
+        count + 1
+    }
+
+    /// Check whether the pattern can never match anything at all, e.g.
+    /// a negated class that lists all the characters except the separator,
+    /// or `[a-f&&[x-z]]` with the `class_intersection` option enabled,
+    /// so that e.g. a configuration file linter may flag the rules that would
+    /// never apply.
+    ///
+    /// The check is done on the parsed pattern, and it is conservative:
+    /// if it returns true, the pattern will certainly never match, but
+    /// some patterns that never match are still not detected, e.g. the ones
+    /// containing named classes such as `[[:digit:]&&[[:alpha:]]]`.
+    /// Note that a class that only lists the separator character, e.g. `[/]`,
+    /// is already rejected as an [`crate::error::Error::EmptyClass`] error
+    /// when the pattern is parsed.
+    #[inline]
+    #[must_use]
+    pub fn is_unsatisfiable(&self) -> bool {
+        ast::unsatisfiable(&self.ast.nodes, &self.options, true)
+    }
+
+    /// Get all the strings that the pattern matches, with any alternations
+    /// expanded and any escape sequences resolved, e.g. `a.c`, `a.h`, `b.c`,
+    /// and `b.h` for the `{a,b}.{c,h}` pattern; duplicates are only
+    /// returned once. If the `optional_trailing_separator` option is enabled,
+    /// each string is followed by the same one with a separator appended.
+    /// Return `None` if the pattern contains any wildcards or character
+    /// classes, if it may match more strings because of the options used,
+    /// e.g. `match_at_any_level` or a `(?i)` flag, or if its alternations
+    /// would expand to more than [`DEFAULT_MAX_BRACE_EXPANSIONS`] strings.
+    #[inline]
+    #[must_use]
+    pub fn finite_matches(&self) -> Option<Vec<String>> {
+        if self.ast.any_level
+            || self.ast.case_insensitive
+            || !matches!(self.options.anchoring, Anchoring::Full)
+        {
+            return None;
+        }
+        let texts = ast::finite_texts(&self.ast.nodes, DEFAULT_MAX_BRACE_EXPANSIONS)?;
+        if self.options.optional_trailing_separator {
+            let sep = self.options.separator;
+            Some(
+                texts
+                    .into_iter()
+                    .flat_map(|text| {
+                        let with_sep = format!("{text}{sep}");
+                        [text, with_sep]
+                    })
+                    .unique()
+                    .collect(),
+            )
+        } else {
+            Some(texts.into_iter().unique().collect())
+        }
+    }
+
+    /// Generate up to `count` example strings that the pattern matches, e.g.
+    /// for documentation or for checking a pattern interactively.
+    ///
+    /// The strings are built from the parsed pattern: the literal text is
+    /// kept as it is, the alternation branches are enumerated, and a few
+    /// fixed strings are tried for each wildcard, e.g. the empty string, `x`,
+    /// and `xyz` for a `*`, so the generation is bounded and deterministic.
+    /// The characters tried for a character class are the ones mentioned in
+    /// it, then a few common ones. Only the strings that the compiled regular
+    /// expression actually matches are returned, so there may be fewer than
+    /// `count` of them, or even none at all if e.g. a class matches none of
+    /// the characters tried.
+    #[inline]
+    #[must_use]
+    pub fn sample_matches(&self, count: usize) -> Vec<String> {
+        fglob::sample_texts(
+            &self.ast.nodes,
+            &self.options,
+            count.saturating_mul(4).max(16),
+        )
+        .into_iter()
+        .filter(|text| self.is_match(text))
+        .unique()
+        .take(count)
+        .collect()
+    }
+
+    /// Generate up to `count` example strings that the pattern almost, but
+    /// not quite, matches, e.g. for testing or for explaining a pattern.
+    ///
+    /// The strings are obtained by making a few small changes to the ones
+    /// returned by [`Glob::sample_matches`]: swapping or dropping the last
+    /// character, e.g. `main.rq` for `*.rs`, appending a character, or
+    /// inserting a path separator at the start, in the middle, or at the end.
+    /// The generation is bounded and deterministic; only the strings that
+    /// the compiled regular expression does not match are returned, so there
+    /// may be fewer than `count` of them, or even none at all if e.g.
+    /// the pattern is `**` with the `globstar` option enabled.
+    #[inline]
+    #[must_use]
+    pub fn counter_examples(&self, count: usize) -> Vec<String> {
+        self.sample_matches(count.max(4))
+            .iter()
+            .flat_map(|text| near_misses(text, self.options.separator))
+            .filter(|text| !self.is_match(text))
+            .unique()
+            .take(count)
+            .collect()
+    }
+
+    /// Check whether the pattern matches only a single string.
+    /// See [`Glob::unescape_literal`] for the details.
+    #[inline]
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.unescape_literal().is_some()
+    }
+
+    /// Split the pattern into path components and report which of them are
+    /// literal ones, e.g. `Literal("a")`, `Wildcard`, `Literal("c")` for
+    /// `a/*/c`. Empty components, e.g. for a leading or a trailing slash, are
+    /// skipped. An alternation is always considered a single wildcard
+    /// component, even if some of its branches contain slashes.
+    #[inline]
+    #[must_use]
+    pub fn segments(&self) -> Vec<SegmentKind> {
+        ast::segments(&self.ast.nodes, self.options.separator)
+    }
+
+    /// The number of path components in the pattern,
+    /// see [`Glob::segments`] for the details.
+    #[inline]
+    #[must_use]
+    pub fn num_segments(&self) -> usize {
+        self.segments().len()
+    }
+
+    /// Check whether the pattern may match a text that contains a slash,
+    /// i.e. whether it contains a literal slash, possibly within
+    /// an alternation branch, e.g. `a/*.txt`, or a globstar, e.g. `**/x`,
+    /// so that e.g. a matcher that only looks at the file names knows
+    /// whether it needs the full paths. The options that let the wildcards
+    /// or the character classes match a slash are not taken into account,
+    /// and neither is `match_at_any_level`, since the pattern itself is
+    /// still only matched against the last path components.
+    #[inline]
+    #[must_use]
+    pub fn is_multi_segment(&self) -> bool {
+        ast::spans_separator(&self.ast.nodes, self.options.separator)
+    }
+
+    /// Check whether the pattern was anchored to the root by a leading slash.
+    /// This may only be true if the `root_relative` option was enabled.
+    #[inline]
+    #[must_use]
+    pub const fn is_anchored_root(&self) -> bool {
+        self.ast.anchored_root
+    }
+}
+
+impl AsRef<Regex> for Glob {
+    #[inline]
+    fn as_ref(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl From<Glob> for Regex {
+    /// Take the compiled regular expression out of the glob; it is only
+    /// cloned if it is still shared with other clones of the glob.
+    #[inline]
+    fn from(glob: Glob) -> Self {
+        Arc::try_unwrap(glob.regex).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl PartialEq for Glob {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        if self.options.normalize != other.options.normalize {
+            return false;
+        }
+        self.regex.as_str() == other.regex.as_str() && self.options.unicode == other.options.unicode
+    }
+}
+
+impl Eq for Glob {}
+
+impl Hash for Glob {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.regex.as_str().hash(state);
+        self.options.unicode.hash(state);
+        #[cfg(feature = "unicode-normalization")]
+        self.options.normalize.hash(state);
+    }
+}
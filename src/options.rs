@@ -0,0 +1,779 @@
+//! Options controlling the conversion of a glob pattern.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#[cfg(feature = "unicode-normalization")]
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::{
+    is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized, UnicodeNormalization,
+};
+
+use crate::error::Error as FError;
+
+/// Which ends of the text the pattern must match at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Anchoring {
+    /// The pattern must match the whole text string.
+    #[default]
+    Full,
+    /// The pattern may match anywhere within the text string, e.g. `b*`
+    /// would match `abc`.
+    Unanchored,
+    /// The pattern must match at the start of the text string, but anything
+    /// may follow, e.g. `ERROR*` would match `ERROR: boom`, but not
+    /// `WARN: ERROR`.
+    Start,
+}
+
+/// How to handle an empty `{}` brace pair in a glob pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum EmptyBrace {
+    /// Match the `{}` characters literally, e.g. `a{}b` would only match `a{}b`.
+    #[default]
+    Literal,
+    /// Treat `{}` as an empty expansion, e.g. `a{}b` would only match `ab`.
+    Empty,
+}
+
+/// A Unicode normalization form that the pattern and the text strings are
+/// converted to before matching, so that e.g. a composed `\u{e9}` and
+/// a decomposed `e\u{301}` are considered the same.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NormalizationForm {
+    /// Canonical decomposition followed by canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+    /// Compatibility decomposition followed by canonical composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl NormalizationForm {
+    /// Convert a text string to this normalization form; the string is only
+    /// copied if it is not known to be in that form already.
+    #[inline]
+    #[must_use]
+    pub fn normalize(self, text: &str) -> Cow<'_, str> {
+        let quick = match self {
+            Self::Nfc => is_nfc_quick(text.chars()),
+            Self::Nfd => is_nfd_quick(text.chars()),
+            Self::Nfkc => is_nfkc_quick(text.chars()),
+            Self::Nfkd => is_nfkd_quick(text.chars()),
+        };
+        if quick == IsNormalized::Yes {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(match self {
+            Self::Nfc => text.nfc().collect(),
+            Self::Nfd => text.nfd().collect(),
+            Self::Nfkc => text.nfkc().collect(),
+            Self::Nfkd => text.nfkd().collect(),
+        })
+    }
+}
+
+/// A set of options suited to a particular kind of text strings,
+/// so that they need not be combined one by one; see
+/// [`GlobOptions::from_preset`]. The options may still be changed afterwards,
+/// e.g. `GlobOptions::from_preset(Preset::Path).max_globstar_depth(Some(2))`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Preset {
+    /// The default options: the whole text must match, the `*` and `?`
+    /// wildcards and the character classes never match a slash, and `**`
+    /// behaves like `*`.
+    #[default]
+    Filename,
+    /// The [`Preset::Filename`] options with the `globstar` one enabled,
+    /// so that a `**` path component matches any number of directory levels.
+    Path,
+    /// For labels and other strings that are not paths: the slash is not
+    /// special at all, as with the `treat_slash_as_ordinary` option, and
+    /// the pattern may match anywhere within the text, as with
+    /// [`Anchoring::Unanchored`].
+    PlainText,
+}
+
+/// The syntax features that a glob pattern uses, as reported by
+/// `Glob::required_features`, e.g. so that a pattern that needs an option
+/// that a tool does not enable may be rejected with a precise message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct FeatureSet {
+    /// A `**` path component, which needs the `globstar` option.
+    pub globstar: bool,
+    /// A `?{N}` repetition count, which needs the `counted_repetition` option.
+    pub counted_repetition: bool,
+    /// A `[...&&[...]]` intersection within a character class, which needs
+    /// the `class_intersection` option.
+    pub class_intersection: bool,
+    /// A leading `(?flags)` group, which needs the `inline_flags` option.
+    pub inline_flags: bool,
+    /// A named class within a character class, e.g. `[[:alpha:]]`.
+    pub named_class: bool,
+    /// An alternation nested within another one, e.g. `{a,b{c,d}}`, which
+    /// is only supported by the `expand_braces` function.
+    pub nested_alternation: bool,
+}
+
+impl FeatureSet {
+    /// The names of the features that may not be used with the specified
+    /// options, e.g. `globstar` if the pattern contains a `**` component,
+    /// but the `globstar` option is not enabled; the names of the features
+    /// that need an option are the same as the names of the options.
+    /// A nested alternation is always reported as `nested_alternation`.
+    #[inline]
+    #[must_use]
+    pub fn unsupported_by(&self, options: &GlobOptions) -> Vec<&'static str> {
+        [
+            (self.globstar && !options.globstar, "globstar"),
+            (
+                self.counted_repetition && !options.counted_repetition,
+                "counted_repetition",
+            ),
+            (
+                self.class_intersection && !options.class_intersection,
+                "class_intersection",
+            ),
+            (self.inline_flags && !options.inline_flags, "inline_flags"),
+            (self.nested_alternation, "nested_alternation"),
+        ]
+        .into_iter()
+        .filter_map(|(missing, name)| missing.then_some(name))
+        .collect()
+    }
+}
+
+/// A regular expression character class for the `single_char_class` option,
+/// e.g. `[A-Za-z0-9_]` or `\w`. The class is stored inline, so that it may
+/// come from e.g. a configuration file while the options may still be
+/// copied; it may be at most [`SingleCharClass::MAX_LEN`] bytes long.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SingleCharClass {
+    /// The bytes of the class, padded with zeroes.
+    bytes: [u8; Self::MAX_LEN],
+    /// The length of the class in bytes.
+    len: usize,
+}
+
+impl SingleCharClass {
+    /// The maximum length of a character class in bytes.
+    pub const MAX_LEN: usize = 64;
+
+    /// Store a character class, e.g. one read from a configuration file.
+    /// Whether it actually looks like a single character class is only
+    /// checked when a pattern is converted using it.
+    ///
+    /// # Errors
+    ///
+    /// [`FError::InvalidSingleCharClass`] if the class is longer than
+    /// [`SingleCharClass::MAX_LEN`] bytes.
+    #[inline]
+    pub fn new(class: &str) -> Result<Self, FError> {
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes
+            .get_mut(..class.len())
+            .ok_or_else(|| FError::InvalidSingleCharClass(class.to_owned()))?
+            .copy_from_slice(class.as_bytes());
+        Ok(Self {
+            bytes,
+            len: class.len(),
+        })
+    }
+
+    /// The character class as a regular expression snippet.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.bytes
+            .get(..self.len)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for SingleCharClass {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SingleCharClass")
+            .field(&self.as_str())
+            .finish()
+    }
+}
+
+impl fmt::Display for SingleCharClass {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SingleCharClass {
+    type Err = FError;
+
+    #[inline]
+    fn from_str(class: &str) -> Result<Self, Self::Err> {
+        Self::new(class)
+    }
+}
+
+/// Options controlling the conversion of a glob pattern to a regular expression.
+///
+/// The default options produce the same regular expressions as
+/// the [`crate::glob::glob_to_regex_string`] function.
+/// The options may be set using the chained builder-like methods:
+///
+/// ```rust
+/// # use fnmatch_regex::GlobOptions;
+/// let options = GlobOptions::default().globstar(true).max_globstar_depth(Some(2));
+/// assert!(options.globstar);
+/// ```
+///
+/// The options are cheap to copy, and the conversion functions take them by
+/// reference, so that a single set may be shared while converting many
+/// patterns, e.g. `glob_to_regex_with(pattern, &options)` in a loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GlobOptions {
+    /// Let a `**` path component match any number of directory levels.
+    pub globstar: bool,
+    /// The maximum number of directory levels that a `**` component may span.
+    pub max_globstar_depth: Option<usize>,
+    /// Let a pattern that contains no slashes, except possibly a trailing one,
+    /// match at any directory level, e.g. `*.log` would match `sub/dir/app.log`.
+    pub match_at_any_level: bool,
+    /// Match paths relative to a root directory, similar to gitignore rules:
+    /// a leading slash anchors the pattern to the root and is stripped, and
+    /// a pattern that contains no other slashes may match at any directory level
+    /// as if `match_at_any_level` were enabled.
+    pub root_relative: bool,
+    /// Treat a `{N}` immediately following a `?` wildcard as a repetition count
+    /// instead of an alternation, e.g. `?{3}` would match exactly three characters.
+    pub counted_repetition: bool,
+    /// Merge the common prefixes of the alternatives in a `{...}` alternation,
+    /// e.g. `{alpha,alps,beta}` would produce `(?:alp(?:ha|s)|beta)`.
+    /// This may result in smaller and faster automata for large sets of
+    /// literal strings.
+    pub compact_alternations: bool,
+    /// How to handle an empty `{}` brace pair.
+    pub empty_brace: EmptyBrace,
+    /// Recognize a leading `(?i)`, `(?s)`, or `(?is)` group that sets
+    /// the case-insensitive and the dot-matches-newline flags of
+    /// the regular expression.
+    pub inline_flags: bool,
+    /// Which ends of the text the pattern must match at.
+    pub anchoring: Anchoring,
+    /// Do not treat the slash specially: let the `*` and `?` wildcards and
+    /// the character classes match it like any other character, e.g. when
+    /// matching version tags instead of paths. This may not be combined with
+    /// the `globstar` option.
+    pub treat_slash_as_ordinary: bool,
+    /// Let the character classes match a slash if it is included in them,
+    /// e.g. `[/a]`, or if they are negated and do not exclude it, e.g. `[!a]`.
+    /// The `*` and `?` wildcards still never match a slash.
+    pub classes_match_separator: bool,
+    /// The path separator character, a slash by default; all the rules that
+    /// mention the slash apply to it instead. Note that the backslash is
+    /// still the escape character by default, so a backslash separator must
+    /// be written as `\\` in the pattern, e.g. `dir\\*.txt`; the same goes
+    /// for any other separator that is also the `escape_char` one.
+    pub separator: char,
+    /// Only let a literal `.` in the pattern match a dot at the start of
+    /// a path component, similar to the `FNM_PERIOD` flag of `fnmatch(3)`:
+    /// the `*`, `?`, and `**` wildcards and the character classes do not
+    /// match it, e.g. `*.txt` would match `a.txt`, but not `.txt` or
+    /// `dir/.txt`; a trailing `**` then also matches newline characters, as
+    /// `*` does. The leading directories skipped by `match_at_any_level`
+    /// may still be hidden ones.
+    pub literal_leading_dot: bool,
+    /// Let a `/**` at the end of the pattern also match nothing at all, so
+    /// that e.g. `foo/**` matches `foo` itself as well as everything under it,
+    /// in the same way that a leading `**/` or a `/**/` in the middle may
+    /// match zero directory levels.
+    pub collapse_globstar_edges: bool,
+    /// Recognize `&&[...]` intersections at the end of a character class,
+    /// e.g. `[a-z&&[^aeiou]]` would only match lowercase consonants; if not
+    /// enabled, `&&` is matched literally.
+    pub class_intersection: bool,
+    /// Compile the regular expression in Unicode mode; if disabled, e.g. for
+    /// use with a `regex` crate built without its Unicode features, the case
+    /// insensitive matching and the POSIX named classes only consider ASCII
+    /// characters, while the wildcards and the negated character classes
+    /// still match any single character using Unicode-aware groups that need
+    /// no Unicode tables. A character class that contains non-ASCII
+    /// characters, a Unicode general category, or a negated named class
+    /// within a non-negated one, e.g. `[[:^alpha:]]`, is then reported as
+    /// an invalid regular expression.
+    pub unicode: bool,
+    /// Let a `*`, `?`, `[`, or `{` character be matched literally if it is
+    /// doubled, e.g. `a??` would only match `a?`, as an alternative to
+    /// a backslash escape, e.g. in configuration files where backslashes
+    /// are awkward to write; since `**` is then a literal `*`, this may not
+    /// be used together with the `globstar` option.
+    pub escape_by_doubling: bool,
+    /// Let a `*` wildcard at the very end of the pattern match the rest of
+    /// the text, slashes included, e.g. `logs/*` would match both `logs/a`
+    /// and `logs/a/b`, while a `*` anywhere else, even in the last branch of
+    /// a trailing alternation, still only matches within a path component.
+    pub trailing_star_is_recursive: bool,
+    /// Let a `?` right after the end of a character class make the class
+    /// optional, e.g. `[0-9]?` would match both an empty string and `5`,
+    /// instead of being a wildcard that matches any single character;
+    /// the wildcard may still be written as `{?}` after a class.
+    pub optional_class: bool,
+    /// Let a text string that ends with a slash also be matched as if it
+    /// did not, e.g. `foo/bar` would also match `foo/bar/`.
+    pub optional_trailing_separator: bool,
+    /// Match a `[` that does not start a properly closed character class
+    /// literally, along with what follows it, e.g. `file[1.txt` would only
+    /// match `file[1.txt`, instead of reporting an `UnclosedClass` error.
+    pub unclosed_class_literal: bool,
+    /// A regular expression character class, e.g. `[A-Za-z0-9_]` or `\w`,
+    /// that the `?` wildcard should match a single character of, instead of
+    /// any character except a slash; see [`SingleCharClass::new`].
+    pub single_char_class: Option<SingleCharClass>,
+    /// Let a `.` in the pattern match any single character, like the `?`
+    /// wildcard, e.g. for matching version strings; an escaped `\.` still
+    /// only matches a dot.
+    pub dot_is_wildcard: bool,
+    /// Let the pattern match any text that starts with a string it would
+    /// match, as if an implicit `*` were appended to it, e.g. `abc` would
+    /// also match `abcdef`; the implicit `*` does not match a slash.
+    pub treat_as_prefix: bool,
+    /// Report an unescaped `*` or `?` wildcard in the pattern as an error,
+    /// so that only literal text, character classes, and alternations may
+    /// be used, e.g. for rules that should not be able to match everything.
+    pub disallow_wildcards: bool,
+    /// The character that lets the next one be matched literally, a backslash
+    /// by default, e.g. `%` so that `%*` would only match `*` in places where
+    /// backslashes are awkward to write; `None` disables escaping altogether,
+    /// similar to the `FNM_NOESCAPE` flag of `fnmatch(3)`, so that
+    /// a backslash is matched literally. The `\n`-like letter escapes are
+    /// recognized after any escape character. This may not be a glob special
+    /// character, e.g. `*` or `{`, or one of `,`, `!`, or `-`.
+    pub escape_char: Option<char>,
+    /// Convert the pattern and the text strings to a Unicode normalization
+    /// form before matching, so that e.g. a composed `\u{e9}` in the pattern
+    /// would also match a decomposed `e\u{301}` in the text.
+    #[cfg(feature = "unicode-normalization")]
+    pub normalize: Option<NormalizationForm>,
+}
+
+impl Default for GlobOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::from_preset(Preset::Filename)
+    }
+}
+
+impl From<Preset> for GlobOptions {
+    #[inline]
+    fn from(preset: Preset) -> Self {
+        Self::from_preset(preset)
+    }
+}
+
+impl GlobOptions {
+    /// Build the set of options described by the specified preset.
+    #[inline]
+    #[must_use]
+    pub const fn from_preset(preset: Preset) -> Self {
+        let (globstar, anchoring, treat_slash_as_ordinary) = match preset {
+            Preset::Filename => (false, Anchoring::Full, false),
+            Preset::Path => (true, Anchoring::Full, false),
+            Preset::PlainText => (false, Anchoring::Unanchored, true),
+        };
+        Self {
+            globstar,
+            max_globstar_depth: None,
+            match_at_any_level: false,
+            root_relative: false,
+            counted_repetition: false,
+            compact_alternations: false,
+            empty_brace: EmptyBrace::Literal,
+            inline_flags: false,
+            anchoring,
+            treat_slash_as_ordinary,
+            classes_match_separator: false,
+            separator: '/',
+            literal_leading_dot: false,
+            collapse_globstar_edges: false,
+            class_intersection: false,
+            unicode: true,
+            escape_by_doubling: false,
+            trailing_star_is_recursive: false,
+            optional_class: false,
+            optional_trailing_separator: false,
+            unclosed_class_literal: false,
+            single_char_class: None,
+            dot_is_wildcard: false,
+            treat_as_prefix: false,
+            disallow_wildcards: false,
+            escape_char: Some('\\'),
+            #[cfg(feature = "unicode-normalization")]
+            normalize: None,
+        }
+    }
+
+    /// Let a `**` path component match any number of directory levels.
+    #[inline]
+    #[must_use]
+    pub const fn globstar(self, globstar: bool) -> Self {
+        Self { globstar, ..self }
+    }
+
+    /// Limit the number of directory levels that a `**` component may span.
+    #[inline]
+    #[must_use]
+    pub const fn max_globstar_depth(self, max_globstar_depth: Option<usize>) -> Self {
+        Self {
+            max_globstar_depth,
+            ..self
+        }
+    }
+
+    /// Let a pattern that contains no slashes match at any directory level.
+    #[inline]
+    #[must_use]
+    pub const fn match_at_any_level(self, match_at_any_level: bool) -> Self {
+        Self {
+            match_at_any_level,
+            ..self
+        }
+    }
+
+    /// Match paths relative to a root directory, similar to gitignore rules.
+    #[inline]
+    #[must_use]
+    pub const fn root_relative(self, root_relative: bool) -> Self {
+        Self {
+            root_relative,
+            ..self
+        }
+    }
+
+    /// Treat a `{N}` immediately following a `?` wildcard as a repetition count.
+    #[inline]
+    #[must_use]
+    pub const fn counted_repetition(self, counted_repetition: bool) -> Self {
+        Self {
+            counted_repetition,
+            ..self
+        }
+    }
+
+    /// Merge the common prefixes of the alternatives in a `{...}` alternation.
+    #[inline]
+    #[must_use]
+    pub const fn compact_alternations(self, compact_alternations: bool) -> Self {
+        Self {
+            compact_alternations,
+            ..self
+        }
+    }
+
+    /// Choose how to handle an empty `{}` brace pair.
+    #[inline]
+    #[must_use]
+    pub const fn empty_brace(self, empty_brace: EmptyBrace) -> Self {
+        Self {
+            empty_brace,
+            ..self
+        }
+    }
+
+    /// Recognize a leading `(?flags)` group.
+    #[inline]
+    #[must_use]
+    pub const fn inline_flags(self, inline_flags: bool) -> Self {
+        Self {
+            inline_flags,
+            ..self
+        }
+    }
+
+    /// Choose which ends of the text the pattern must match at.
+    #[inline]
+    #[must_use]
+    pub const fn anchoring(self, anchoring: Anchoring) -> Self {
+        Self { anchoring, ..self }
+    }
+
+    /// Do not treat the slash specially.
+    #[inline]
+    #[must_use]
+    pub const fn treat_slash_as_ordinary(self, treat_slash_as_ordinary: bool) -> Self {
+        Self {
+            treat_slash_as_ordinary,
+            ..self
+        }
+    }
+
+    /// Let the character classes match a slash.
+    #[inline]
+    #[must_use]
+    pub const fn classes_match_separator(self, classes_match_separator: bool) -> Self {
+        Self {
+            classes_match_separator,
+            ..self
+        }
+    }
+
+    /// Use a different path separator character instead of the slash.
+    #[inline]
+    #[must_use]
+    pub const fn separator(self, separator: char) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// Do not let the wildcards match a dot at the start of a path component.
+    #[inline]
+    #[must_use]
+    pub const fn literal_leading_dot(self, literal_leading_dot: bool) -> Self {
+        Self {
+            literal_leading_dot,
+            ..self
+        }
+    }
+
+    /// Let a trailing `/**` also match the directory itself.
+    #[inline]
+    #[must_use]
+    pub const fn collapse_globstar_edges(self, collapse_globstar_edges: bool) -> Self {
+        Self {
+            collapse_globstar_edges,
+            ..self
+        }
+    }
+
+    /// Recognize `&&[...]` intersections at the end of a character class.
+    #[inline]
+    #[must_use]
+    pub const fn class_intersection(self, class_intersection: bool) -> Self {
+        Self {
+            class_intersection,
+            ..self
+        }
+    }
+
+    /// Compile the regular expression in Unicode mode.
+    #[inline]
+    #[must_use]
+    pub const fn unicode(self, unicode: bool) -> Self {
+        Self { unicode, ..self }
+    }
+
+    /// Let a doubled special character be matched literally.
+    #[inline]
+    #[must_use]
+    pub const fn escape_by_doubling(self, escape_by_doubling: bool) -> Self {
+        Self {
+            escape_by_doubling,
+            ..self
+        }
+    }
+
+    /// Let a trailing `*` wildcard match across path separators.
+    #[inline]
+    #[must_use]
+    pub const fn trailing_star_is_recursive(self, trailing_star_is_recursive: bool) -> Self {
+        Self {
+            trailing_star_is_recursive,
+            ..self
+        }
+    }
+
+    /// Let a `?` after a character class make the class optional.
+    #[inline]
+    #[must_use]
+    pub const fn optional_class(self, optional_class: bool) -> Self {
+        Self {
+            optional_class,
+            ..self
+        }
+    }
+
+    /// Let a trailing slash in the text string be ignored.
+    #[inline]
+    #[must_use]
+    pub const fn optional_trailing_separator(self, optional_trailing_separator: bool) -> Self {
+        Self {
+            optional_trailing_separator,
+            ..self
+        }
+    }
+
+    /// Let an unclosed `[` be matched literally.
+    #[inline]
+    #[must_use]
+    pub const fn unclosed_class_literal(self, unclosed_class_literal: bool) -> Self {
+        Self {
+            unclosed_class_literal,
+            ..self
+        }
+    }
+
+    /// Specify the character class that the `?` wildcard should match.
+    #[inline]
+    #[must_use]
+    pub const fn single_char_class(self, single_char_class: Option<SingleCharClass>) -> Self {
+        Self {
+            single_char_class,
+            ..self
+        }
+    }
+
+    /// Let a `.` in the pattern match any single character.
+    #[inline]
+    #[must_use]
+    pub const fn dot_is_wildcard(self, dot_is_wildcard: bool) -> Self {
+        Self {
+            dot_is_wildcard,
+            ..self
+        }
+    }
+
+    /// Let the pattern match the texts that start with a matching string.
+    #[inline]
+    #[must_use]
+    pub const fn treat_as_prefix(self, treat_as_prefix: bool) -> Self {
+        Self {
+            treat_as_prefix,
+            ..self
+        }
+    }
+
+    /// Report the `*` and `?` wildcards in the pattern as errors.
+    #[inline]
+    #[must_use]
+    pub const fn disallow_wildcards(self, disallow_wildcards: bool) -> Self {
+        Self {
+            disallow_wildcards,
+            ..self
+        }
+    }
+
+    /// Set the escape character, or disable escaping altogether.
+    #[inline]
+    #[must_use]
+    pub const fn escape_char(self, escape_char: Option<char>) -> Self {
+        Self {
+            escape_char,
+            ..self
+        }
+    }
+
+    /// Convert the pattern and the text strings to a Unicode normalization form.
+    #[cfg(feature = "unicode-normalization")]
+    #[inline]
+    #[must_use]
+    pub const fn normalize(self, normalize: Option<NormalizationForm>) -> Self {
+        Self { normalize, ..self }
+    }
+
+    /// Check whether the options may be used together.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::ConflictingOptions`] describing the conflict.
+    #[inline]
+    pub const fn validate(&self) -> Result<(), FError> {
+        if self.max_globstar_depth.is_some() && !self.globstar {
+            return Err(FError::ConflictingOptions(
+                "max_globstar_depth requires globstar",
+            ));
+        }
+        if self.collapse_globstar_edges && !self.globstar {
+            return Err(FError::ConflictingOptions(
+                "collapse_globstar_edges requires globstar",
+            ));
+        }
+        if matches!(self.separator, '?' | '*' | '[' | ']' | '{' | '}') {
+            return Err(FError::ConflictingOptions(
+                "the separator may not be a glob special character",
+            ));
+        }
+        if matches!(
+            self.escape_char,
+            Some('?' | '*' | '[' | ']' | '{' | '}' | ',' | '!' | '-')
+        ) {
+            return Err(FError::ConflictingOptions(
+                "the escape character may not be a glob special character",
+            ));
+        }
+        if self.globstar && self.escape_by_doubling {
+            return Err(FError::ConflictingOptions(
+                "escape_by_doubling may not be used together with globstar",
+            ));
+        }
+        if self.globstar && self.treat_slash_as_ordinary {
+            return Err(FError::ConflictingOptions(
+                "globstar requires the slash to be treated specially",
+            ));
+        }
+        if self.literal_leading_dot
+            && (self.treat_slash_as_ordinary || self.classes_match_separator)
+        {
+            return Err(FError::ConflictingOptions(
+                "literal_leading_dot requires the slash to be treated specially",
+            ));
+        }
+        if self.literal_leading_dot && self.dot_is_wildcard {
+            return Err(FError::ConflictingOptions(
+                "dot_is_wildcard may not be used together with literal_leading_dot",
+            ));
+        }
+        if matches!(self.anchoring, Anchoring::Unanchored) {
+            if self.literal_leading_dot {
+                return Err(FError::ConflictingOptions(
+                    "literal_leading_dot requires full anchoring",
+                ));
+            }
+            if self.match_at_any_level {
+                return Err(FError::ConflictingOptions(
+                    "match_at_any_level requires full anchoring",
+                ));
+            }
+            if self.root_relative {
+                return Err(FError::ConflictingOptions(
+                    "root_relative requires full anchoring",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+//! Check whether any of several glob patterns matches a text string.
+//!
+//! A [`GlobSet`] keeps a list of patterns and combines their regular
+//! expressions into a single [`RegexSet`], so that it may be checked whether
+//! any of them matches a text string without trying them one by one:
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::GlobSet;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut ignored = GlobSet::new();
+//! ignored.insert("*.o")?;
+//! ignored.insert("target/**")?;
+//! assert!(ignored.is_match("main.o"));
+//! assert!(!ignored.is_match("main.c"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! If the set is only ever used for such membership tests, it may be
+//! converted into the underlying [`RegexSet`] using
+//! the [`GlobSet::into_regex_set`] method; this is faster than a [`GlobMap`]
+//! or a list of [`Glob`] values, since only a single pass over the text is
+//! needed and the set does not have to keep track of the matching patterns.
+//!
+//! A set may also be built from a list of rules in the format used by
+//! `.gitignore` files using the [`GlobSet::from_ignore_str`] method; a rule
+//! that starts with a `!` re-includes the texts matched by the earlier ones,
+//! and the last matching rule wins:
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use fnmatch_regex::GlobSet;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let ignored = GlobSet::from_ignore_str("# build artifacts\n*.o\n!keep.o\n")?;
+//! assert!(ignored.is_match("main.o"));
+//! assert!(!ignored.is_match("keep.o"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A [`GlobSetBuilder`] may be used to compile a list of patterns using
+//! the same options, e.g. the rules loaded from a user's configuration file;
+//! its [`GlobSetBuilder::build_lossy`] method skips the invalid patterns
+//! instead of failing, and reports their errors along with their indices.
+//!
+//! [`GlobMap`]: crate::map::GlobMap
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use std::sync::OnceLock;
+
+use regex::{RegexSet, RegexSetBuilder};
+
+use crate::error::Error as FError;
+use crate::matcher::{self, Glob};
+use crate::options::{GlobOptions, Preset};
+
+/// Combine the patterns' regular expressions into a single set, compiled
+/// using the same `unicode` option as the patterns themselves.
+///
+/// # Errors
+/// [`crate::error::Error::Unsupported`] if any of the patterns converts
+/// the text to a normalization form, or if the patterns were compiled with
+/// different values of the `unicode` option;
+/// [`crate::error::Error::Other`] if the regular expressions could not be
+/// combined, e.g. if the set would exceed the regular expression engine's
+/// size limit.
+pub(crate) fn build_regex_set(globs: &[Glob]) -> Result<RegexSet, FError> {
+    if globs.iter().any(|glob| matcher::normalizes(glob.options())) {
+        return Err(FError::Unsupported {
+            feature: "normalize",
+        });
+    }
+    let unicode = globs.first().is_none_or(|glob| glob.options().unicode);
+    if globs.iter().any(|glob| glob.options().unicode != unicode) {
+        return Err(FError::Unsupported {
+            feature: "mixed_unicode",
+        });
+    }
+    Ok(
+        RegexSetBuilder::new(globs.iter().map(|glob| glob.regex().as_str()))
+            .unicode(unicode)
+            .build()?,
+    )
+}
+
+/// Remove the trailing whitespace from a line of an ignore file, except for
+/// a whitespace character escaped with a backslash, e.g. in `foo\ `.
+fn trim_rule(line: &str) -> &str {
+    let trimmed = line.trim_end();
+    let escapes = trimmed.chars().rev().take_while(|&chr| chr == '\\').count();
+    if escapes % 2 == 0 {
+        return trimmed;
+    }
+    let escaped = line
+        .get(trimmed.len()..)
+        .and_then(|rest| rest.chars().next())
+        .map_or(0, char::len_utf8);
+    line.get(..trimmed.len() + escaped).unwrap_or(trimmed)
+}
+
+/// Compile a rule of an ignore file; a directory rule, i.e. one that ends
+/// with a slash, is extended so that it also matches everything within
+/// the directory, at any level if the rule itself would match at any level.
+fn compile_ignore_rule(pattern: &str, options: &GlobOptions) -> Result<Glob, FError> {
+    let glob = Glob::with_options(pattern, options)?;
+    if !pattern.ends_with('/') {
+        return Ok(glob);
+    }
+    let leading = if glob.ast().any_level { "**/" } else { "" };
+    Glob::with_options(&format!("{}{}**", leading, pattern), options)
+}
+
+/// A list of glob patterns checked together.
+#[derive(Debug, Clone, Default)]
+pub struct GlobSet {
+    /// The compiled patterns, in the order they were inserted.
+    globs: Vec<Glob>,
+    /// Whether each pattern is a negated rule that re-includes the texts
+    /// matched by the earlier ones, in the same order.
+    negated: Vec<bool>,
+    /// The set of the patterns' regular expressions, compiled on first use;
+    /// `None` if that failed or if any of the patterns normalizes the text.
+    set: OnceLock<Option<RegexSet>>,
+}
+
+impl GlobSet {
+    /// Create an empty set.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            globs: Vec::new(),
+            negated: Vec::new(),
+            set: OnceLock::new(),
+        }
+    }
+
+    /// Build a set from a list of rules in the format used by `.gitignore`
+    /// files, one per line: empty lines and lines that start with a `#` are
+    /// skipped, trailing whitespace is removed unless escaped, e.g. `foo\ `,
+    /// and a rule that starts with a `!` is negated, so that it re-includes
+    /// the texts matched by the earlier rules. A `\#` or `\!` at the start of
+    /// a rule matches the character literally. A directory rule, i.e. one
+    /// that ends with a slash, e.g. `build/`, matches the directory itself
+    /// as `build/` along with everything within it, e.g. `build/out/x`.
+    ///
+    /// The patterns are compiled using the [`Preset::Path`] options with
+    /// the `root_relative` one enabled, so that `**` matches any number of
+    /// directory levels, a pattern without slashes matches at any level, and
+    /// a leading slash anchors a pattern to the root.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the patterns.
+    #[inline]
+    pub fn from_ignore_str(rules: &str) -> Result<Self, FError> {
+        let options = GlobOptions::from_preset(Preset::Path).root_relative(true);
+        let mut res = Self::new();
+        for line in rules.lines().map(trim_rule) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (pattern, negated) = match line.strip_prefix('!') {
+                Some(rest) => (rest, true),
+                None => (line, false),
+            };
+            res.push(compile_ignore_rule(pattern, &options)?, negated);
+        }
+        Ok(res)
+    }
+
+    /// Compile a glob pattern using the default options and add it to the set.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the specified glob pattern.
+    #[inline]
+    pub fn insert(&mut self, pattern: &str) -> Result<(), FError> {
+        self.insert_glob(Glob::new(pattern)?);
+        Ok(())
+    }
+
+    /// Add an already compiled glob pattern to the set, e.g. one compiled
+    /// using non-default options.
+    #[inline]
+    pub fn insert_glob(&mut self, glob: Glob) {
+        self.push(glob, false);
+    }
+
+    /// Add an already compiled glob pattern to the set as a negated rule,
+    /// so that it re-includes the texts matched by the earlier patterns,
+    /// e.g. the `!keep.o` rule of an ignore file.
+    #[inline]
+    pub fn insert_negated_glob(&mut self, glob: Glob) {
+        self.push(glob, true);
+    }
+
+    /// Add a compiled pattern, possibly a negated rule, to the set.
+    fn push(&mut self, glob: Glob, negated: bool) {
+        self.globs.push(glob);
+        self.negated.push(negated);
+        self.set = OnceLock::new();
+    }
+
+    /// Check whether any of the patterns matches the specified text.
+    /// If the set contains any negated rules, the last matching rule wins:
+    /// the text is only matched if that one is not negated.
+    ///
+    /// The patterns' regular expressions are combined into a single set on
+    /// the first check after an insertion. In the unlikely case that this
+    /// fails, e.g. if the set would exceed the regular expression engine's
+    /// size limit, the patterns are tried one by one instead. The same goes
+    /// for the patterns that convert the text to a normalization form, since
+    /// each of them may use a different one, and for patterns compiled with
+    /// different values of the `unicode` option.
+    #[inline]
+    #[must_use]
+    pub fn is_match<T: AsRef<str>>(&self, text: T) -> bool {
+        let text_ref = text.as_ref();
+        let has_negated = self.negated.contains(&true);
+        match *self.set.get_or_init(|| build_regex_set(&self.globs).ok()) {
+            Some(ref set) if !has_negated => set.is_match(text_ref),
+            Some(ref set) => set
+                .matches(text_ref)
+                .iter()
+                .next_back()
+                .is_some_and(|idx| self.negated.get(idx) == Some(&false)),
+            None => self
+                .globs
+                .iter()
+                .zip(&self.negated)
+                .rev()
+                .find(|&(glob, _)| glob.is_match(text_ref))
+                .is_some_and(|(_, negated)| !negated),
+        }
+    }
+
+    /// Convert the set into a [`RegexSet`] of the patterns' regular
+    /// expressions, in the order they were inserted, for pure membership
+    /// tests using [`RegexSet::is_match`]; that is faster than trying
+    /// the patterns one by one.
+    ///
+    /// # Errors
+    /// [`crate::error::Error::Unsupported`] if the set contains any negated
+    /// rules, since a [`RegexSet`] may not express them, if any patterns
+    /// convert the text to a normalization form, since a [`RegexSet`] may not
+    /// do that, or if the patterns were compiled with different values of
+    /// the `unicode` option;
+    /// [`crate::error::Error::Other`] if the regular expressions could not
+    /// be combined, e.g. if the set would exceed the regular expression
+    /// engine's size limit.
+    #[inline]
+    pub fn into_regex_set(self) -> Result<RegexSet, FError> {
+        if self.negated.contains(&true) {
+            return Err(FError::Unsupported {
+                feature: "negated_rules",
+            });
+        }
+        match self.set.into_inner() {
+            Some(Some(set)) => Ok(set),
+            _ => build_regex_set(&self.globs),
+        }
+    }
+
+    /// The number of patterns in the set.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.globs.len()
+    }
+
+    /// Check whether the set contains no patterns at all.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.globs.is_empty()
+    }
+
+    /// Iterate over the patterns in the order they were inserted.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Glob> {
+        self.globs.iter()
+    }
+}
+
+/// A list of glob patterns to be compiled into a [`GlobSet`] using the same
+/// options, e.g. the rules loaded from a configuration file.
+/// The patterns may be added using the chained builder-like methods:
+///
+/// ```rust
+/// # use std::error::Error;
+/// use fnmatch_regex::{GlobOptions, GlobSetBuilder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let (set, errors) = GlobSetBuilder::new()
+///     .options(GlobOptions::default().globstar(true))
+///     .pattern("*.o")
+///     .pattern("*.[ch")
+///     .pattern("target/**")
+///     .build_lossy();
+/// assert_eq!(set.len(), 2);
+/// assert!(set.is_match("target/debug/main"));
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].0, 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GlobSetBuilder {
+    /// The patterns, in the order they were added.
+    patterns: Vec<String>,
+    /// The options used to compile all the patterns.
+    options: GlobOptions,
+}
+
+impl GlobSetBuilder {
+    /// Start with an empty list of patterns and the default options.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the options used to compile all the patterns.
+    #[inline]
+    #[must_use]
+    pub fn options(self, options: GlobOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Add a glob pattern to the list.
+    #[inline]
+    #[must_use]
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_owned());
+        self
+    }
+
+    /// Compile all the patterns into a set.
+    ///
+    /// # Errors
+    /// Most of the [`crate::error::Error`] values, mostly syntax errors in
+    /// the first pattern that could not be compiled.
+    #[inline]
+    pub fn build(self) -> Result<GlobSet, FError> {
+        let mut res = GlobSet::new();
+        for pattern in &self.patterns {
+            res.insert_glob(Glob::with_options(pattern, &self.options)?);
+        }
+        Ok(res)
+    }
+
+    /// Compile all the patterns that are valid into a set, skipping
+    /// the invalid ones instead of failing on the first one, e.g. so that
+    /// a tool may warn about the bad rules in a user's file and still use
+    /// the rest. The errors are returned along with the indices of
+    /// the patterns that caused them, in the order the patterns were added;
+    /// the set only contains the valid patterns, so its indices, e.g. in
+    /// a [`RegexSet`] built from it, may differ from the original ones.
+    #[inline]
+    #[must_use]
+    pub fn build_lossy(self) -> (GlobSet, Vec<(usize, FError)>) {
+        let mut res = GlobSet::new();
+        let mut errors = Vec::new();
+        for (idx, pattern) in self.patterns.iter().enumerate() {
+            match Glob::with_options(pattern, &self.options) {
+                Ok(glob) => res.insert_glob(glob),
+                Err(err) => errors.push((idx, err)),
+            }
+        }
+        (res, errors)
+    }
+}
@@ -0,0 +1,81 @@
+//! Test matching using a deterministic automaton.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use crate::error::Error as FError;
+use crate::matcher::Glob;
+use crate::options::{GlobOptions, Preset};
+
+#[rstest::rstest]
+#[case("*.txt", GlobOptions::default())]
+#[case("src/{main,lib}.rs", GlobOptions::default())]
+#[case("**/*.rs", GlobOptions::from(Preset::Path).literal_leading_dot(true))]
+#[case("[[:alpha:]_][!0-9]?", GlobOptions::default())]
+#[case("(?i)readme*", GlobOptions::default().inline_flags(true))]
+#[case("a*b", GlobOptions::from(Preset::PlainText))]
+#[case("*.log", GlobOptions::default().match_at_any_level(true))]
+fn test_dfa_consistent(#[case] pattern: &str, #[case] options: GlobOptions) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let dfa = glob.to_dfa()?;
+    assert_eq!(dfa.pattern(), pattern);
+    for text in [
+        "",
+        "a.txt",
+        "src/main.rs",
+        "src/lib.rs",
+        "src/.hidden/x.rs",
+        "a/b/c.rs",
+        "_xy",
+        "9xy",
+        "README.md",
+        "readme",
+        "xaxbx",
+        "d/app.log",
+        "\u{e9}\u{e9}\u{e9}",
+    ] {
+        assert_eq!(dfa.is_match(text), glob.is_match(text), "{:?}", text);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_dfa_normalize() -> Result<(), FError> {
+    use crate::options::NormalizationForm;
+
+    let glob = Glob::with_options(
+        "caf\u{e9}/*.txt",
+        &GlobOptions::default().normalize(Some(NormalizationForm::Nfc)),
+    )?;
+    let dfa = glob.to_dfa()?;
+    assert!(dfa.is_match("caf\u{e9}/a.txt"));
+    assert!(dfa.is_match("cafe\u{301}/a.txt"));
+    assert!(!dfa.is_match("cafe/a.txt"));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Test the brace expansion functionality.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use crate::error::Error as FError;
+use crate::expand as fexpand;
+
+#[rstest::rstest]
+#[case("test_plain", "img.png", &["img.png"])]
+#[case("test_plain", "", &[""])]
+#[case("test_simple", "img.{png,jpg}", &["img.png", "img.jpg"])]
+#[case("test_simple", "{a,b}{1,2}", &["a1", "a2", "b1", "b2"])]
+#[case("test_simple", "{a,,b}", &["a", "", "b"])]
+#[case("test_nested", "a{b,c{d,e}}f", &["abf", "acdf", "acef"])]
+#[case("test_nested", "{{a,b},{c,d}}", &["a", "b", "c", "d"])]
+#[case("test_wildcards", "*.{[ch],rs}", &["*.[ch]", "*.rs"])]
+#[case("test_wildcards", "?{a,[]{,]}", &["?a", "?[]{,]"])]
+#[case("test_wildcards", "[!{]{x,y}", &["[!{]x", "[!{]y"])]
+#[case("test_literal", r"\{a,b\}", &[r"\{a,b\}"])]
+#[case("test_literal", "{}", &["{}"])]
+#[case("test_literal", "a,b}", &["a,b}"])]
+#[case("test_literal", r"{a\,b,c}", &[r"a\,b", "c"])]
+fn test_expand(
+    #[case] test_name: &str,
+    #[case] pattern: &str,
+    #[case] expected: &[&str],
+) -> Result<(), FError> {
+    let expanded = fexpand::expand_braces(pattern)?;
+    println!("{}: {} -> {:?}", test_name, pattern, expanded);
+    assert_eq!(expanded, expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("{a,b", 10, "Unclosed alternation at position 0")]
+#[case("a{b,c{d}", 10, "Unclosed alternation at position 1")]
+#[case(r"{a,b\", 10, "Unclosed alternation at position 0")]
+#[case(r"a\", 10, "Bare escape character")]
+#[case("x[{a,b}", 10, "Unclosed character class at position 1")]
+#[case(
+    "{a,b}{c,d}{e,f}",
+    7,
+    "Brace expansion would produce more than 7 patterns"
+)]
+fn test_expand_fail(#[case] pattern: &str, #[case] max: usize, #[case] expected: &str) {
+    let err = fexpand::expand_braces_limit(pattern, max).unwrap_err();
+    println!("{} -> {}", pattern, err);
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn test_expand_limit() -> Result<(), FError> {
+    assert_eq!(fexpand::expand_braces_limit("{a,b}{c,d}{e,f}", 8)?.len(), 8);
+    assert!(matches!(
+        fexpand::expand_braces("{0,1,2,3,4,5,6,7,8,9}".repeat(5).as_str()),
+        Err(FError::TooManyExpansions(
+            fexpand::DEFAULT_MAX_BRACE_EXPANSIONS
+        ))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_expand_nesting() -> Result<(), FError> {
+    let nested = format!("{}{}", "{a,".repeat(64), "}".repeat(64));
+    assert_eq!(fexpand::expand_braces(&nested)?.len(), 65);
+    let deeper = format!("{}{}", "{a,".repeat(65), "}".repeat(65));
+    assert!(matches!(
+        fexpand::expand_braces(&deeper),
+        Err(FError::NestingTooDeep(192))
+    ));
+    assert!(matches!(
+        fexpand::expand_braces(&"{".repeat(30_000)),
+        Err(FError::NestingTooDeep(64))
+    ));
+    Ok(())
+}
@@ -26,8 +26,16 @@
  * SUCH DAMAGE.
  */
 
+#![allow(clippy::panic_in_result_fn)]
+
+use std::error::Error;
+
+use regex::Regex;
+
+use crate::ast::{Class, ClassItem, Node, Span};
 use crate::error::Error as FError;
 use crate::glob as fglob;
+use crate::options::{Anchoring, EmptyBrace, GlobOptions, Preset, SingleCharClass};
 
 #[rstest::rstest]
 #[case(
@@ -67,6 +75,24 @@ use crate::glob as fglob;
     &["aab", "a.b", "a?b", "a*b"],
     &["a/b"],
 )]
+#[case(
+    "test_wildcards",
+    "a*b",
+    &["ab", "aab", "a.b", "a?b", "a*b", "a.this.b"],
+    &["a/b", "a/x/b", "ab/", "/ab"],
+)]
+#[case(
+    "test_wildcards",
+    "a/**/b",
+    &["a/b/b", "a/x/b", "a/**/b"],
+    &["a/b", "a/x/y/b"],
+)]
+#[case(
+    "test_regex_special",
+    "(a|b)+^c$d}f]",
+    &["(a|b)+^c$d}f]"],
+    &["a", "b", "ab", "(a|b)+^c$d}f", "aab+^c$d}f]"],
+)]
 #[case(
     "test_class_simple",
     "[0-9]",
@@ -135,17 +161,48 @@ use crate::glob as fglob;
     &[" ", ".", "?", "+", "]"],
     &["--", "-a", "a-", "aa", "", "-", "a", "/"],
 )]
+#[case(
+    "test_class_special",
+    "[^a]",
+    &["^", "a"],
+    &["b", "", "/", "^a"],
+)]
+#[case(
+    "test_class_special",
+    "[[&~^]",
+    &["[", "&", "~", "^"],
+    &["a", "]", "", "/"],
+)]
+#[case(
+    "test_class_special",
+    "[!--/]",
+    &["a", "+", "0"],
+    &["-", ".", "/", ""],
+)]
+#[case(
+    "test_class_special",
+    "[a&&b]",
+    &["a", "&", "b"],
+    &["c", "&&", ""],
+)]
 #[case(
     "test_alternates",
     "look at {th?is,that,...*}",
-    &["look at th?is", "look at that", "look at ...*"],
+    &["look at th?is", "look at thxis", "look at that", "look at ...*", "look at ..."],
     &[
         "look at this",
         "look at ths",
         "look at ",
         "look at that and stuff",
+        "look at .../x",
     ],
 )]
+#[case(
+    "test_alternates",
+    r"{\*.txt,[!a-z]\?,\{[}]}",
+    &["*.txt", "1?", "{}"],
+    &["a.txt", "a?", "1x", "{"],
+)]
 #[case(
     "test_alternates",
     "whee{} whoo",
@@ -209,3 +266,2060 @@ fn test_pattern(
 
     Ok(())
 }
+
+#[rstest::rstest]
+#[case(
+    "test_globstar",
+    "a/**/b",
+    GlobOptions::default().globstar(true),
+    &["a/b", "a/x/b", "a/x/y/b", "a/x/y/z/b"],
+    &["a/xb", "ab", "b", "a/x/y/c", "x/a/b"],
+)]
+#[case(
+    "test_globstar",
+    "**/b.txt",
+    GlobOptions::default().globstar(true),
+    &["b.txt", "a/b.txt", "a/x/y/b.txt"],
+    &["ab.txt", "a/b.txt/c", "a/xb.txt"],
+)]
+#[case(
+    "test_globstar",
+    "a/**",
+    GlobOptions::default().globstar(true),
+    &["a/", "a/b", "a/x/y/b"],
+    &["a", "b/a/b"],
+)]
+#[case(
+    "test_globstar",
+    "a/x**/b",
+    GlobOptions::default().globstar(true),
+    &["a/x/b", "a/xyz/b"],
+    &["a/x/y/b", "a/b"],
+)]
+#[case(
+    "test_globstar_depth",
+    "a/**/b",
+    GlobOptions::default().globstar(true).max_globstar_depth(Some(2)),
+    &["a/b", "a/x/b", "a/x/y/b"],
+    &["a/x/y/z/b", "a/x/y/z/w/b"],
+)]
+#[case(
+    "test_globstar_depth",
+    "a/**/b",
+    GlobOptions::default().globstar(true).max_globstar_depth(Some(0)),
+    &["a/b"],
+    &["a/x/b", "a/x/y/b"],
+)]
+#[case(
+    "test_globstar_depth",
+    "a/**",
+    GlobOptions::default().globstar(true).max_globstar_depth(Some(2)),
+    &["a/", "a/x", "a/x/y"],
+    &["a/x/y/z"],
+)]
+#[case(
+    "test_any_level",
+    "*.log",
+    GlobOptions::default().match_at_any_level(true),
+    &["app.log", "sub/app.log", "sub/dir/app.log", ".log"],
+    &["app.log/x", "app.logs", "sub/app.logs"],
+)]
+#[case(
+    "test_any_level",
+    "a/*.log",
+    GlobOptions::default().match_at_any_level(true),
+    &["a/x.log", "a/.log"],
+    &["a/b/x.log", "b/a/x.log", "x.log"],
+)]
+#[case(
+    "test_any_level",
+    "/x.log",
+    GlobOptions::default().match_at_any_level(true),
+    &["/x.log"],
+    &["x.log", "a/x.log"],
+)]
+#[case(
+    "test_any_level",
+    "logs/",
+    GlobOptions::default().match_at_any_level(true),
+    &["logs/", "a/logs/", "a/b/logs/"],
+    &["logs", "a/logs", "alogs/"],
+)]
+#[case(
+    "test_counted_repetition",
+    "id-?{3}.txt",
+    GlobOptions::default().counted_repetition(true),
+    &["id-123.txt", "id-abc.txt", "id-???.txt"],
+    &["id-12.txt", "id-1234.txt", "id-1/3.txt", "id-1.txt"],
+)]
+#[case(
+    "test_counted_repetition",
+    "?{2}?{0}*",
+    GlobOptions::default().counted_repetition(true),
+    &["ab", "abc", "ab.txt"],
+    &["a", "", "a/b", "ab/c"],
+)]
+#[case(
+    "test_counted_repetition",
+    "file?{1,2}",
+    GlobOptions::default(),
+    &["filea1", "fileb2"],
+    &["file1", "filea", "filea12"],
+)]
+#[case(
+    "test_empty_brace",
+    "a{}b",
+    GlobOptions::default(),
+    &["a{}b"],
+    &["ab", "a{b", "a}b"],
+)]
+#[case(
+    "test_empty_brace",
+    "a{}b",
+    GlobOptions::default().empty_brace(EmptyBrace::Literal),
+    &["a{}b"],
+    &["ab"],
+)]
+#[case(
+    "test_empty_brace",
+    "a{}b",
+    GlobOptions::default().empty_brace(EmptyBrace::Empty),
+    &["ab"],
+    &["a{}b", "a{b", "a}b"],
+)]
+#[case(
+    "test_empty_brace",
+    "{}{x,y}{}",
+    GlobOptions::default().empty_brace(EmptyBrace::Empty),
+    &["x", "y"],
+    &["", "{}x{}", "xy"],
+)]
+#[case(
+    "test_inline_flags",
+    "(?i)*.txt",
+    GlobOptions::default().inline_flags(true),
+    &["X.TXT", "a.txt", "b.Txt"],
+    &["a.txt/b", "a.log", "a/b.txt"],
+)]
+#[case(
+    "test_inline_flags",
+    "(?i)*.txt",
+    GlobOptions::default(),
+    &["(xi)a.txt", "(?i)a.txt"],
+    &["X.TXT", "a.txt"],
+)]
+#[case(
+    "test_inline_flags",
+    "(?s)/a/**",
+    GlobOptions::default().inline_flags(true).globstar(true).root_relative(true),
+    &["a/b", "a/b\nc/d"],
+    &["/a/b", "b/a/c"],
+)]
+#[case(
+    "test_inline_flags",
+    "a/**",
+    GlobOptions::default().inline_flags(true).globstar(true),
+    &["a/b"],
+    &["a/b\nc/d"],
+)]
+#[case(
+    "test_inline_flags",
+    "(?)*(?i)",
+    GlobOptions::default().inline_flags(true),
+    &["(x)ab(xi)", "(?)(?i)"],
+    &["ab"],
+)]
+#[case(
+    "test_inline_flags",
+    "(?is)readme",
+    GlobOptions::default().inline_flags(true).match_at_any_level(true),
+    &["README", "a\nb/ReadMe", "a/readme"],
+    &["readme.md"],
+)]
+#[case(
+    "test_unanchored",
+    "b?d",
+    GlobOptions::default().anchoring(Anchoring::Unanchored),
+    &["bcd", "abcde", "x/bcd/y"],
+    &["bd", "b/d", "abd"],
+)]
+#[case(
+    "test_anchor_start",
+    "ERROR*",
+    GlobOptions::default().anchoring(Anchoring::Start),
+    &["ERROR", "ERROR: boom", "ERRORS/x"],
+    &["WARN: ERROR", " ERROR", "ERRO"],
+)]
+#[case(
+    "test_anchor_start",
+    "*.log",
+    GlobOptions::default().anchoring(Anchoring::Start).match_at_any_level(true),
+    &["a.log", "sub/a.log.1", "sub/a.log/b"],
+    &["a.txt", "sub/a.txt"],
+)]
+#[case(
+    "test_slash_ordinary",
+    "v*-rc?",
+    GlobOptions::default().treat_slash_as_ordinary(true),
+    &["v1.0-rc1", "v1/2/3-rc/", "v-rc\n"],
+    &["v1.0-rc", "x/v1.0-rc1"],
+)]
+#[case(
+    "test_slash_ordinary",
+    "a[!x]b[/-]?{2}",
+    GlobOptions::default().treat_slash_as_ordinary(true).counted_repetition(true),
+    &["a/b/cd", "a.b-//"],
+    &["axb/cd", "a/b.cd", "a/b/c"],
+)]
+#[case(
+    "test_slash_ordinary",
+    "a/**/b",
+    GlobOptions::default().treat_slash_as_ordinary(true),
+    &["a/x/b", "a/x/y/b", "a//b"],
+    &["a/b", "a/x/c"],
+)]
+#[case(
+    "test_classes_match_separator",
+    "x[/a]y",
+    GlobOptions::default(),
+    &["xay"],
+    &["x/y", "xby"],
+)]
+#[case(
+    "test_classes_match_separator",
+    "x[/a]y",
+    GlobOptions::default().classes_match_separator(true),
+    &["x/y", "xay"],
+    &["xby"],
+)]
+#[case(
+    "test_classes_match_separator",
+    "x[!a]?[.-0]*",
+    GlobOptions::default().classes_match_separator(true),
+    &["x/b/", "xbb.", "xbb/"],
+    &["xa/b/", "x//b/", "x/b/c/d"],
+)]
+#[case(
+    "test_classes_match_separator",
+    "x[!a]?[.-0]*",
+    GlobOptions::default(),
+    &["xbb.", "xbb0"],
+    &["x/b/", "xbb/"],
+)]
+#[case(
+    "test_negated_slash",
+    "x[!a-z]y",
+    GlobOptions::default(),
+    &["xAy", "x.y"],
+    &["x/y", "xay"],
+)]
+#[case(
+    "test_negated_slash",
+    "x[!a-z/]y",
+    GlobOptions::default(),
+    &["xAy", "x.y"],
+    &["x/y", "xay"],
+)]
+#[case(
+    "test_negated_slash",
+    "x[!a-z]y",
+    GlobOptions::default().classes_match_separator(true),
+    &["xAy", "x/y"],
+    &["xay"],
+)]
+#[case(
+    "test_negated_slash",
+    "x[!a-z/]y",
+    GlobOptions::default().classes_match_separator(true),
+    &["xAy", "x.y"],
+    &["x/y", "xay"],
+)]
+#[case(
+    "test_negated_slash",
+    "x[!.-0]y",
+    GlobOptions::default().treat_slash_as_ordinary(true),
+    &["xAy"],
+    &["x/y", "x.y"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "*.txt",
+    GlobOptions::default().literal_leading_dot(true),
+    &["a.txt", "a.b.txt"],
+    &[".txt", ".a.txt", "d/a.txt"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "dir/*",
+    GlobOptions::default().literal_leading_dot(true),
+    &["dir/a", "dir/a.b"],
+    &["dir/.a", "dir/."],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "?a*",
+    GlobOptions::default().literal_leading_dot(true),
+    &["xa", "xa."],
+    &[".a", ".a."],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "[.a]b",
+    GlobOptions::default().literal_leading_dot(true),
+    &["ab"],
+    &[".b"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "x/[!a]b",
+    GlobOptions::default().literal_leading_dot(true),
+    &["x/cb"],
+    &["x/.b", "x/ab"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "*?",
+    GlobOptions::default().literal_leading_dot(true),
+    &["a", "a.", "ab."],
+    &[".", ".a"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    ".*",
+    GlobOptions::default().literal_leading_dot(true),
+    &[".a", "."],
+    &["a", "..a/b"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "**/*.rs",
+    GlobOptions::default().literal_leading_dot(true).globstar(true),
+    &["a.rs", "src/a.rs", "src/x/a.rs"],
+    &[".git/a.rs", "src/.x/a.rs", ".a.rs"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "src/**",
+    GlobOptions::default().literal_leading_dot(true).globstar(true),
+    &["src/a", "src/a/b", "src/", "src/a\nb"],
+    &["src/.a", "src/a/.b"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "src/**",
+    GlobOptions::default().literal_leading_dot(true).globstar(true).max_globstar_depth(Some(2)),
+    &["src/a", "src/a/b"],
+    &["src/.a", "src/a/.b", "src/a/b/c"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "{a/,b}*",
+    GlobOptions::default().literal_leading_dot(true),
+    &["a/x", "bx", "b.x", "a/"],
+    &["a/.x", ".x"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "*.log",
+    GlobOptions::default().literal_leading_dot(true).match_at_any_level(true),
+    &["a.log", ".git/a.log"],
+    &["x/.log", ".log"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "{,x}.a",
+    GlobOptions::default().literal_leading_dot(true),
+    &[".a", "x.a"],
+    &["a"],
+)]
+#[case(
+    "test_literal_leading_dot",
+    "*{.a,b}",
+    GlobOptions::default().literal_leading_dot(true),
+    &["x.a", "b"],
+    &[".a", ".b"],
+)]
+#[case(
+    "test_collapse_globstar_edges",
+    "**/foo",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true),
+    &["foo", "a/foo", "a/b/foo"],
+    &["afoo", "/foo", "foo/a"],
+)]
+#[case(
+    "test_collapse_globstar_edges",
+    "foo/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true),
+    &["foo", "foo/", "foo/x", "foo/x/y"],
+    &["foox", "a/foo", "fo"],
+)]
+#[case(
+    "test_collapse_globstar_edges",
+    "a/**/b",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true),
+    &["a/b", "a/x/b", "a/x/y/b"],
+    &["ab", "a//b", "a/b/c"],
+)]
+#[case(
+    "test_collapse_globstar_edges",
+    "/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true),
+    &["/", "/x", "/x/y"],
+    &[""],
+)]
+#[case(
+    "test_collapse_globstar_edges",
+    "{a,b}/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true).max_globstar_depth(Some(1)),
+    &["a", "b/", "b/x"],
+    &["a/x/y", "c"],
+)]
+#[case(
+    "test_separator",
+    r"{a\\b,c}",
+    GlobOptions::default().separator('\\'),
+    &[r"a\b", "c"],
+    &["a/b", r"a\\b", "ab"],
+)]
+#[case(
+    "test_separator",
+    r"d\\*.txt",
+    GlobOptions::default().separator('\\'),
+    &[r"d\a.txt", r"d\a/b.txt"],
+    &[r"d\a\b.txt", "d/a.txt"],
+)]
+#[case(
+    "test_separator",
+    r"src\\**\\*.{rs,\\}",
+    GlobOptions::default().separator('\\').globstar(true),
+    &[r"src\a.rs", r"src\x\y\a.rs", r"src\a.\"],
+    &["src/a.rs", r"srcx\a.rs", r"src\a.c"],
+)]
+#[case(
+    "test_separator",
+    r"**\*[Z-b][!c]?",
+    GlobOptions::default().separator('\\').globstar(true),
+    &["a*]x/", r"a*Zd/"],
+    &[r"a*\xy", r"a*]\y", r"a*]c\", r"a*]xy\"],
+)]
+#[case(
+    "test_separator",
+    "*.log",
+    GlobOptions::default().separator(':').match_at_any_level(true),
+    &["a.log", "x:a.log", "x:y/a.log"],
+    &["x:a.log:", "a.log:b"],
+)]
+#[case(
+    "test_named_class",
+    "[[:digit:]_]*",
+    GlobOptions::default(),
+    &["1", "_x", "9.txt"],
+    &["a", "x1", "1/2"],
+)]
+#[case(
+    "test_named_class",
+    "[[:^digit:]]",
+    GlobOptions::default(),
+    &["a", "-"],
+    &["1", "/"],
+)]
+#[case(
+    "test_named_class",
+    "[[:Letter:]0-9]",
+    GlobOptions::default(),
+    &["a", "é", "Ж", "5"],
+    &["-", "/", "٣"],
+)]
+#[case(
+    "test_named_class",
+    "[![:Letter:]_]",
+    GlobOptions::default(),
+    &["5", "-"],
+    &["é", "_", "/"],
+)]
+#[case(
+    "test_named_class",
+    "[[:punct:][:Lu:]]",
+    GlobOptions::default(),
+    &[".", "Ж", "-"],
+    &["/", "ж"],
+)]
+#[case(
+    "test_named_class",
+    "[[:punct:]]",
+    GlobOptions::default().classes_match_separator(true),
+    &[".", "/"],
+    &["a"],
+)]
+#[case(
+    "test_named_class",
+    "[[:alpha:]-]",
+    GlobOptions::default(),
+    &["a", "-"],
+    &["1"],
+)]
+#[case(
+    "test_named_class",
+    "[[:]",
+    GlobOptions::default(),
+    &["[", ":"],
+    &["]"],
+)]
+#[case(
+    "test_named_class",
+    "[[:punct:]]*",
+    GlobOptions::default().literal_leading_dot(true),
+    &["-x", "-.x"],
+    &[".x", "/"],
+)]
+#[case(
+    "test_class_intersection",
+    "[a-z&&[^aeiou]]",
+    GlobOptions::default().class_intersection(true),
+    &["b", "z"],
+    &["a", "e", "B", "/", "&"],
+)]
+#[case(
+    "test_class_intersection",
+    "[!a-z&&[!0-9]]",
+    GlobOptions::default().class_intersection(true),
+    &["A", "-"],
+    &["a", "5", "/"],
+)]
+#[case(
+    "test_class_intersection",
+    "[a-z&&[a-m]&&[^c]]",
+    GlobOptions::default().class_intersection(true),
+    &["a", "m"],
+    &["c", "n"],
+)]
+#[case(
+    "test_class_intersection",
+    "[[:alpha:]/&&[!a-f]]",
+    GlobOptions::default().class_intersection(true),
+    &["g", "Z"],
+    &["a", "/", "1"],
+)]
+#[case(
+    "test_alternate_wildcards",
+    "{*.txt,README}",
+    GlobOptions::default(),
+    &["a.txt", ".txt", "README", "README.txt"],
+    &["a/b.txt", "README.md", "readme"],
+)]
+#[case(
+    "test_alternate_wildcards",
+    "src/{**/*.rs,[ch]?,[[:digit:]],}",
+    GlobOptions::from(Preset::Path),
+    &["src/main.rs", "src/a/b/lib.rs", "src/c1", "src/7", "src/"],
+    &["src/a/c1", "src/a1", "src/77", "main.rs"],
+)]
+#[case(
+    "test_alternate_wildcards",
+    "{*,.git}/x",
+    GlobOptions::default().literal_leading_dot(true),
+    &["a/x", ".git/x"],
+    &[".a/x", "a/b/x"],
+)]
+#[case(
+    "test_alternate_wildcards",
+    "{[a,b]-[}],[a-c&&[!b]]x,?}",
+    GlobOptions::default().class_intersection(true),
+    &["a-}", ",-}", "b-}", "ax", "cx", "z"],
+    &["a-,", "b-]", "bx", "zx", "zz"],
+)]
+#[case(
+    "test_alternate_branches",
+    "{a*,b?}",
+    GlobOptions::default(),
+    &["a", "abc", "bc", "b?"],
+    &["b", "bcd", "c", "a/b"],
+)]
+#[case(
+    "test_alternate_branches",
+    "x{?,??}y",
+    GlobOptions::default(),
+    &["xay", "xaby"],
+    &["xy", "xabcy", "x/y"],
+)]
+#[case(
+    "test_alternate_branches",
+    "{?{2},x?{3}}.c",
+    GlobOptions::default().counted_repetition(true),
+    &["ab.c", "xabc.c"],
+    &["a.c", "abc.c", "x.c", "xab.c"],
+)]
+#[case(
+    "test_alternate_branches",
+    r"{\??{2},[!/]{}}",
+    GlobOptions::default().counted_repetition(true),
+    &["?ab", "a{}"],
+    &["?a", "abc", "a"],
+)]
+#[case(
+    "test_alternate_branches",
+    "a{,{}}b",
+    GlobOptions::default().empty_brace(EmptyBrace::Empty),
+    &["ab"],
+    &["a{}b"],
+)]
+#[case(
+    "test_alternate_branches",
+    "{[]],[!]],[[:digit:]-z]}",
+    GlobOptions::default(),
+    &["]", "a", "-", "5", "z"],
+    &["", "]]", "/"],
+)]
+#[case(
+    "test_alternate_branches",
+    r"{[\,}],\,}",
+    GlobOptions::default(),
+    &[",", "}"],
+    &["\\", ",}", ""],
+)]
+#[case(
+    "test_alternate_branches",
+    "{a/*,*/b}/c",
+    GlobOptions::default(),
+    &["a/x/c", "x/b/c", "a/b/c"],
+    &["a/c", "a/x/y/c", "b/c"],
+)]
+#[case(
+    "test_alternate_branches",
+    "{**/,}*.rs",
+    GlobOptions::from(Preset::Path),
+    &["main.rs", "src/main.rs", "a/b/c.rs"],
+    &["main.c", "src/main.c"],
+)]
+#[case(
+    "test_escape_by_doubling",
+    "a??b",
+    GlobOptions::default().escape_by_doubling(true),
+    &["a?b"],
+    &["axyb", "ab", "a??b"],
+)]
+#[case(
+    "test_escape_by_doubling",
+    "???*[[**{{x}",
+    GlobOptions::default().escape_by_doubling(true),
+    &["??[*{x}", "?z[*{x}", "?zabc[*{x}"],
+    &["?z[*x", "?/[*{x}", "??[**{{x}"],
+)]
+#[case(
+    "test_escape_by_doubling",
+    "{a??,[[b*,{{x}",
+    GlobOptions::default().escape_by_doubling(true),
+    &["a?", "[b", "[bcd", "{x"],
+    &["ab", "a??", "b", "{{x", ""],
+)]
+#[case(
+    "test_escape_by_doubling",
+    r"[??]\?",
+    GlobOptions::default().escape_by_doubling(true),
+    &["??"],
+    &["?", "???"],
+)]
+#[case(
+    "test_escape_by_doubling",
+    "a??b",
+    GlobOptions::default(),
+    &["axyb", "a??b"],
+    &["a?b", "ab"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "logs/*",
+    GlobOptions::default().trailing_star_is_recursive(true),
+    &["logs/", "logs/a", "logs/a/b", "logs/a/b/"],
+    &["logs", "log/a", "a/logs/b"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "logs/*",
+    GlobOptions::default(),
+    &["logs/", "logs/a"],
+    &["logs/a/b", "logs/a/b/"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "*/*.log",
+    GlobOptions::default().trailing_star_is_recursive(true),
+    &["a/b.log", "a/.log"],
+    &["a/b/c.log", "a/b.log/c", "b.log"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "src/*/*",
+    GlobOptions::default().trailing_star_is_recursive(true),
+    &["src/a/b", "src/a/b/c.rs", "src/a/"],
+    &["src/a", "src/b.rs"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "{a,b*}",
+    GlobOptions::default().trailing_star_is_recursive(true),
+    &["a", "b", "bcd"],
+    &["b/c", "a/b"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "src/*",
+    GlobOptions::default()
+        .trailing_star_is_recursive(true)
+        .literal_leading_dot(true),
+    &["src/a", "src/a/b", "src/a/b.c"],
+    &["src/.git", "src/a/.git", "src/a/.git/config"],
+)]
+#[case(
+    "test_trailing_star_is_recursive",
+    "src/a*",
+    GlobOptions::default()
+        .trailing_star_is_recursive(true)
+        .literal_leading_dot(true),
+    &["src/a", "src/a.b", "src/a.b/c"],
+    &["src/a/.git", "src/b"],
+)]
+#[case(
+    "test_optional_class",
+    "[0-9]?",
+    GlobOptions::default().optional_class(true),
+    &["", "5"],
+    &["55", "5a", "a", "?"],
+)]
+#[case(
+    "test_optional_class",
+    "[0-9]?",
+    GlobOptions::default(),
+    &["55", "5a"],
+    &["", "5"],
+)]
+#[case(
+    "test_optional_class",
+    "v[0-9]?.[!/]?{txt,md}",
+    GlobOptions::default().optional_class(true),
+    &["v.txt", "v1.md", "v1.xtxt", "v.-md"],
+    &["v12.txt", "v1.x/txt", "v1.xxtxt"],
+)]
+#[case(
+    "test_optional_class",
+    "v[0-9]{?}",
+    GlobOptions::default().optional_class(true),
+    &["v1a", "v11"],
+    &["v1", "v"],
+)]
+#[case(
+    "test_optional_class",
+    "{a[bc]?,d}e",
+    GlobOptions::default().optional_class(true),
+    &["ae", "abe", "ace", "de"],
+    &["abce", "e", "axe"],
+)]
+#[case(
+    "test_optional_trailing_separator",
+    "foo/bar",
+    GlobOptions::default().optional_trailing_separator(true),
+    &["foo/bar", "foo/bar/"],
+    &["foo/bar//", "foo/ba", "foo/bar/x", "/foo/bar"],
+)]
+#[case(
+    "test_optional_trailing_separator",
+    "foo/bar",
+    GlobOptions::default(),
+    &["foo/bar"],
+    &["foo/bar/"],
+)]
+#[case(
+    "test_optional_trailing_separator",
+    "src/*",
+    GlobOptions::default().optional_trailing_separator(true),
+    &["src/a", "src/a/", "src/"],
+    &["src/a/b", "src/a/b/"],
+)]
+#[case(
+    "test_optional_trailing_separator",
+    "*.d",
+    GlobOptions::default()
+        .optional_trailing_separator(true)
+        .match_at_any_level(true),
+    &["a.d", "a.d/", "x/a.d/"],
+    &["a.d/b"],
+)]
+#[case(
+    "test_optional_trailing_separator",
+    "a:b",
+    GlobOptions::default()
+        .optional_trailing_separator(true)
+        .separator(':'),
+    &["a:b", "a:b:"],
+    &["a:b/"],
+)]
+#[case(
+    "test_unclosed_class_literal",
+    "file[1.txt",
+    GlobOptions::default().unclosed_class_literal(true),
+    &["file[1.txt"],
+    &["file1.txt", "file[.txt", "file[1xtxt"],
+)]
+#[case(
+    "test_unclosed_class_literal",
+    "[ab]*[c",
+    GlobOptions::default().unclosed_class_literal(true),
+    &["a[c", "bxy[c"],
+    &["ac", "[ab]x[c"],
+)]
+#[case(
+    "test_unclosed_class_literal",
+    "{a[b,c}",
+    GlobOptions::default().unclosed_class_literal(true),
+    &["a[b", "c"],
+    &["ab", "a[bx", "a[c"],
+)]
+#[case(
+    "test_unclosed_class_literal",
+    "x[[:alpha:]",
+    GlobOptions::default().unclosed_class_literal(true),
+    &["x[a", "x[:"],
+    &["xa", "x[[:alpha:]"],
+)]
+#[case(
+    "test_unclosed_class_literal",
+    r"a\[b[c",
+    GlobOptions::default().unclosed_class_literal(true),
+    &["a[b[c"],
+    &["a\\[b[c"],
+)]
+#[case(
+    "test_treat_as_prefix",
+    "abc",
+    GlobOptions::default().treat_as_prefix(true),
+    &["abc", "abcdef", "abc.txt"],
+    &["ab", "xabc", "abc/def"],
+)]
+#[case(
+    "test_treat_as_prefix",
+    "abc",
+    GlobOptions::default(),
+    &["abc"],
+    &["abcdef", "abc.txt"],
+)]
+#[case(
+    "test_treat_as_prefix",
+    "src/{a,b}",
+    GlobOptions::default().treat_as_prefix(true),
+    &["src/a", "src/alpha", "src/b.rs"],
+    &["src/c", "src/a/b"],
+)]
+#[case(
+    "test_treat_as_prefix",
+    "logs/",
+    GlobOptions::default().treat_as_prefix(true).trailing_star_is_recursive(true),
+    &["logs/", "logs/a"],
+    &["logs/a/b", "logs"],
+)]
+#[case(
+    "test_dot_is_wildcard",
+    "1.2.3",
+    GlobOptions::default(),
+    &["1.2.3"],
+    &["1x2y3", "1.2-3", "1/2/3"],
+)]
+#[case(
+    "test_dot_is_wildcard",
+    "1.2.3",
+    GlobOptions::default().dot_is_wildcard(true),
+    &["1.2.3", "1x2y3", "1-2.3"],
+    &["1.23", "1/2/3", "1..2.3"],
+)]
+#[case(
+    "test_dot_is_wildcard",
+    r"v{1.*,2\.0}",
+    GlobOptions::default().dot_is_wildcard(true),
+    &["v1.0", "v1_", "v2.0"],
+    &["v1", "v2x0"],
+)]
+#[case(
+    "test_single_char_class",
+    "id_??",
+    GlobOptions::default().single_char_class(SingleCharClass::new("[A-Za-z0-9_]").ok()),
+    &["id_a1", "id___", "id_Z9"],
+    &["id_a-", "id_a", "id_a/", "id_a\u{e9}"],
+)]
+#[case(
+    "test_single_char_class",
+    "v?{3}.*",
+    GlobOptions::default()
+        .counted_repetition(true)
+        .single_char_class(SingleCharClass::new(r"\d").ok()),
+    &["v123.txt", "v000."],
+    &["v12a.txt", "v12.txt", "v1234.txt"],
+)]
+#[case(
+    "test_single_char_class",
+    "{?,x?}*",
+    GlobOptions::default().single_char_class(SingleCharClass::new(r"\p{Lu}").ok()),
+    &["A", "xBc", "\u{c9}t"],
+    &["a", "xb", "-"],
+)]
+#[case(
+    "test_class_intersection",
+    "[a&&b]",
+    GlobOptions::default().class_intersection(true),
+    &["a", "&", "b"],
+    &["ab"],
+)]
+#[case(
+    "test_class_intersection",
+    "[a-z&&[^aeiou]]",
+    GlobOptions::default(),
+    &["a]", "&]", "[]", "b]"],
+    &["a", "b"],
+)]
+fn test_pattern_options(
+    #[case] test_name: &str,
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let re = fglob::glob_to_regex_with(pattern, &options)?;
+    println!("{}: {} {:?} -> {}", test_name, pattern, options, re);
+
+    for item in expect_ok {
+        println!("- {} should match", item);
+        assert!(re.is_match(item));
+    }
+
+    for item in expect_fail {
+        println!("- {} should not match", item);
+        assert!(!re.is_match(item));
+    }
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(
+    GlobOptions::default().max_globstar_depth(Some(2)),
+    "Conflicting options: max_globstar_depth requires globstar",
+    GlobOptions::default().max_globstar_depth(Some(2)).globstar(true)
+)]
+#[case(
+    GlobOptions::default().globstar(true).escape_by_doubling(true),
+    "Conflicting options: escape_by_doubling may not be used together with globstar",
+    GlobOptions::default().escape_by_doubling(true)
+)]
+#[case(
+    GlobOptions::default().collapse_globstar_edges(true),
+    "Conflicting options: collapse_globstar_edges requires globstar",
+    GlobOptions::default().collapse_globstar_edges(true).globstar(true)
+)]
+#[case(
+    GlobOptions::default().anchoring(Anchoring::Unanchored).match_at_any_level(true),
+    "Conflicting options: match_at_any_level requires full anchoring",
+    GlobOptions::default().match_at_any_level(true)
+)]
+#[case(
+    GlobOptions::default().anchoring(Anchoring::Unanchored).root_relative(true),
+    "Conflicting options: root_relative requires full anchoring",
+    GlobOptions::default().root_relative(true).anchoring(Anchoring::Full)
+)]
+#[case(
+    GlobOptions::default().globstar(true).treat_slash_as_ordinary(true),
+    "Conflicting options: globstar requires the slash to be treated specially",
+    GlobOptions::default().treat_slash_as_ordinary(true)
+)]
+#[case(
+    GlobOptions::default().separator('*'),
+    "Conflicting options: the separator may not be a glob special character",
+    GlobOptions::default().separator('\\')
+)]
+#[case(
+    GlobOptions::default().literal_leading_dot(true).classes_match_separator(true),
+    "Conflicting options: literal_leading_dot requires the slash to be treated specially",
+    GlobOptions::default().literal_leading_dot(true)
+)]
+#[case(
+    GlobOptions::default().literal_leading_dot(true).dot_is_wildcard(true),
+    "Conflicting options: dot_is_wildcard may not be used together with literal_leading_dot",
+    GlobOptions::default().dot_is_wildcard(true)
+)]
+#[case(
+    GlobOptions::default().literal_leading_dot(true).anchoring(Anchoring::Unanchored),
+    "Conflicting options: literal_leading_dot requires full anchoring",
+    GlobOptions::default().anchoring(Anchoring::Unanchored)
+)]
+#[case(
+    GlobOptions::default().escape_char(Some('*')),
+    "Conflicting options: the escape character may not be a glob special character",
+    GlobOptions::default().escape_char(Some('%'))
+)]
+fn test_conflicting_options(
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+    #[case] resolved: GlobOptions,
+) {
+    let err = fglob::glob_to_regex_with("a/**/b", &options).unwrap_err();
+    assert!(matches!(err, FError::ConflictingOptions(_)));
+    assert_eq!(err.to_string(), expected);
+    assert!(options.validate().is_err());
+    assert!(resolved.validate().is_ok());
+}
+
+#[rstest::rstest]
+#[case("{a,{b,c}}", "nested_alternation")]
+fn test_unsupported(#[case] pattern: &str, #[case] expected: &str) {
+    let res = fglob::glob_to_regex_string(pattern);
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::Unsupported { feature }) if feature == expected));
+}
+
+#[rstest::rstest]
+#[case("x[/]y", GlobOptions::default(), 1)]
+#[case("a/[//]", GlobOptions::default(), 2)]
+#[case("(?i)/a/[/]", GlobOptions::default().inline_flags(true).root_relative(true), 7)]
+#[case("ab[:]", GlobOptions::default().separator(':'), 2)]
+fn test_empty_class(#[case] pattern: &str, #[case] options: GlobOptions, #[case] expected: usize) {
+    let res = fglob::glob_to_regex_string_with(pattern, &options);
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::EmptyClass(position)) if position == expected));
+    assert!(fglob::glob_to_regex_with(pattern, &options.classes_match_separator(true)).is_ok());
+}
+
+#[rstest::rstest]
+#[case("[!]", 0, "[!]")]
+#[case("a[!]", 1, "a[!]")]
+#[case("a/[!]b", 2, "a/[!]b")]
+#[case("{b,[!]}", 3, "[!]")]
+fn test_negated_empty_class(#[case] pattern: &str, #[case] expected: usize, #[case] text: &str) {
+    let res = fglob::glob_to_regex_string(pattern);
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::UnclosedClass(position)) if position == expected));
+    assert!(matches!(
+        fglob::scan_features(pattern),
+        Err(FError::UnclosedClass(_))
+    ));
+
+    let options = GlobOptions::default().unclosed_class_literal(true);
+    let re = fglob::glob_to_regex_with(pattern, &options).unwrap();
+    assert!(re.is_match(text));
+    assert!(!re.is_match(&text.replace("[!]", "x")));
+}
+
+#[test]
+fn test_glob_to_regex_chars() -> Result<(), FError> {
+    let mut chars: Vec<char> = "src/".chars().collect();
+    chars.extend(['*', '.', '{', 'c', ',', 'h', '}']);
+    let re = fglob::glob_to_regex_chars(chars)?;
+    assert_eq!(re.as_str(), fglob::glob_to_regex("src/*.{c,h}")?.as_str());
+    assert!(re.is_match("src/main.c"));
+    assert!(!re.is_match("src/sub/main.h"));
+    assert!(matches!(
+        fglob::glob_to_regex_chars("a[b".chars()),
+        Err(FError::UnclosedClass(1))
+    ));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", &["a.txt", "\u{e9}.txt", ".txt"], &["a/b.txt", "a.TXT"])]
+#[case("(?i)[!a-z]?k", &["1\u{e9}k", "_xK"], &["Abk", "_x\u{212a}", "_/k"])]
+#[case("**/[[:alpha:]]*", &["a/b/c1", "z"], &["a/b/1c", "\u{e9}"])]
+#[case("{a,b\u{e9}}/**", &["a/x/y", "b\u{e9}/\u{e9}"], &["b/x"])]
+fn test_unicode_disabled(
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let options = GlobOptions::from(Preset::Path)
+        .inline_flags(true)
+        .unicode(false);
+    let glob = fglob::glob_to_regex_with(pattern, &options)?;
+    println!("{} -> {}", pattern, glob);
+    for item in expect_ok {
+        assert!(glob.is_match(item), "{:?}", item);
+    }
+    for item in expect_fail {
+        assert!(!glob.is_match(item), "{:?}", item);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unicode_disabled_errors() {
+    let options = GlobOptions::default().unicode(false);
+    for pattern in ["[\u{e9}]", "[[:Letter:]]", "[[:^alpha:]]"] {
+        assert!(matches!(
+            fglob::glob_to_regex_with(pattern, &options),
+            Err(FError::InvalidRegex(ref glob, _, _)) if glob == pattern
+        ));
+        let err = crate::Glob::with_options(pattern, &options).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(&format!("Glob `{}` produced invalid regex `", pattern)));
+        assert!(fglob::glob_to_regex(pattern).is_ok());
+    }
+}
+
+#[rstest::rstest]
+#[case("*.txt", '*', 0)]
+#[case("file?.txt", '?', 4)]
+#[case("src/**/a", '*', 4)]
+#[case("{a,b*}", '*', 4)]
+#[case("[a-z]?{2}", '?', 5)]
+fn test_disallow_wildcards(#[case] pattern: &str, #[case] wildcard: char, #[case] position: usize) {
+    let options = GlobOptions::default().disallow_wildcards(true);
+    let res = fglob::glob_to_regex_with(pattern, &options);
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(
+        res,
+        Err(FError::DisallowedWildcard(found, pos)) if found == wildcard && pos == position
+    ));
+    assert!(fglob::glob_to_regex(pattern).is_ok());
+}
+
+#[test]
+fn test_disallow_wildcards_allowed() -> Result<(), FError> {
+    let options = GlobOptions::default().disallow_wildcards(true);
+    let re = fglob::glob_to_regex_with("[a-z].txt", &options)?;
+    assert!(re.is_match("a.txt"));
+    assert!(!re.is_match("ab.txt"));
+
+    let re = fglob::glob_to_regex_with(r"{a,b}\*[!0-9]\?", &options)?;
+    assert!(re.is_match("a*x?"));
+    assert!(!re.is_match("a*1?"));
+
+    let doubled = options.escape_by_doubling(true);
+    assert!(fglob::glob_to_regex_with("a**b??", &doubled)?.is_match("a*b?"));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("100%%.txt", GlobOptions::default(), &["100%.txt"], &["100%%.txt", "100x.txt"])]
+#[case("a%*b", GlobOptions::default(), &["a*b"], &["ab", "axb", "a%*b"])]
+#[case("a\\*", GlobOptions::default(), &["a\\", "a\\b"], &["a\\*b/c", "a"])]
+#[case("[%]a]", GlobOptions::default(), &["]", "a"], &["%", "b"])]
+#[case("[!%!]", GlobOptions::default(), &["a", "%"], &["!", "/"])]
+#[case("{a%,b,c}", GlobOptions::default(), &["a,b", "c"], &["a", "b"])]
+#[case("{a%?,b?{2}}", GlobOptions::default().counted_repetition(true), &["a?", "bxy"], &["ab", "b?"])]
+#[case("{a%%?{2},b}", GlobOptions::default().counted_repetition(true), &["a%xy", "b"], &["a%?{2}"])]
+#[case("%n", GlobOptions::default(), &["\n"], &["n", "%n"])]
+#[case("x%%**%%y", GlobOptions::default().separator('%').globstar(true), &["x%y", "x%a%b%y"], &["xy"])]
+#[case("%%etc%%*", GlobOptions::default().separator('%').root_relative(true), &["etc%a"], &["a%etc%a"])]
+#[case("a[b%[c", GlobOptions::default().unclosed_class_literal(true), &["a[b[c"], &["abc"])]
+fn test_escape_char(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let re = fglob::glob_to_regex_with(pattern, &options.escape_char(Some('%')))?;
+    println!("{} -> {}", pattern, re);
+    for item in expect_ok {
+        assert!(re.is_match(item), "{:?}", item);
+    }
+    for item in expect_fail {
+        assert!(!re.is_match(item), "{:?}", item);
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a\\*", GlobOptions::default(), &["a\\", "a\\bc"], &["a*", "a\\b/c"])]
+#[case("[\\]", GlobOptions::default(), &["\\"], &["]"])]
+#[case("{a\\,b}", GlobOptions::default(), &["a\\", "b"], &["a,b"])]
+#[case("a[b\\[c", GlobOptions::default().unclosed_class_literal(true), &["a[b\\[c"], &["a[b[c"])]
+#[case(
+    "a[b[c",
+    GlobOptions::default().unclosed_class_literal(true).escape_by_doubling(true),
+    &["a[b[c"],
+    &["abc"]
+)]
+fn test_escape_char_none(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let re = fglob::glob_to_regex_with(pattern, &options.escape_char(None))?;
+    println!("{} -> {}", pattern, re);
+    for item in expect_ok {
+        assert!(re.is_match(item), "{:?}", item);
+    }
+    for item in expect_fail {
+        assert!(!re.is_match(item), "{:?}", item);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unclosed_class_literal_default() {
+    assert!(matches!(
+        fglob::glob_to_regex("file[1.txt"),
+        Err(FError::UnclosedClass(4))
+    ));
+    assert!(matches!(
+        fglob::glob_to_regex_with(
+            "file[1.txt{a",
+            &GlobOptions::default().unclosed_class_literal(true)
+        ),
+        Err(FError::UnclosedAlternation(_))
+    ));
+}
+
+#[rstest::rstest]
+#[case("ab[cd", GlobOptions::default(), Some(2), "Unclosed character class")]
+#[case(
+    "[a]x[!b-",
+    GlobOptions::default(),
+    Some(4),
+    "Unclosed character class"
+)]
+#[case("(?i)a[b", GlobOptions::default().inline_flags(true), Some(5), "Unclosed character class")]
+#[case("x*{a,b", GlobOptions::default(), Some(2), "Unclosed alternation")]
+#[case(
+    "x[/]y",
+    GlobOptions::default(),
+    Some(1),
+    "The character class would never match anything"
+)]
+#[case(r"ab\", GlobOptions::default(), None, "Bare escape character")]
+#[case(
+    "a/[z-a]",
+    GlobOptions::default(),
+    Some(2),
+    "Reversed range from 'z' to 'a'"
+)]
+#[case(
+    "a{b,c?}",
+    GlobOptions::default().disallow_wildcards(true),
+    Some(5),
+    "The '?' wildcard is not allowed"
+)]
+fn test_error_position(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] position: Option<usize>,
+    #[case] message: &str,
+) {
+    let err = fglob::glob_to_regex_string_with(pattern, &options).unwrap_err();
+    println!("{} -> {}", pattern, err);
+    assert_eq!(err.position(), position);
+    assert_eq!(err.message(), message);
+}
+
+#[test]
+fn test_error_display() {
+    assert_eq!(
+        fglob::glob_to_regex_string("abc[def")
+            .unwrap_err()
+            .to_string(),
+        "Unclosed character class at position 3"
+    );
+    assert_eq!(
+        fglob::glob_to_regex_string("a{b,c")
+            .unwrap_err()
+            .to_string(),
+        "Unclosed alternation at position 1"
+    );
+    assert_eq!(
+        fglob::glob_to_regex_string("x[/]y")
+            .unwrap_err()
+            .to_string(),
+        "The character class at position 1 would never match anything"
+    );
+}
+
+/// The expected results were produced by Python 3.11's `fnmatch.translate()`.
+#[rstest::rstest]
+#[case("*.txt", r"(?s:.*\.txt)\Z")]
+#[case("?[!abc]x", r"(?s:.[^abc]x)\Z")]
+#[case("[a-z]*", r"(?s:[a-z].*)\Z")]
+#[case("[!a-z0-9_]*.py", r"(?s:[^a-z0-9_].*\.py)\Z")]
+#[case("a/*/b", r"(?s:a/.*/b)\Z")]
+#[case("[a-c-e]", r"(?s:[a-c\-e])\Z")]
+#[case("[!]", r"(?s:\[!\])\Z")]
+#[case("**", r"(?s:.*)\Z")]
+#[case(r"[\]", r"(?s:[\\])\Z")]
+fn test_python_translate(#[case] pattern: &str, #[case] python: &str) -> Result<(), FError> {
+    assert_eq!(
+        fglob::python_fnmatch_translate(pattern)?,
+        format!("^{}", python.replace(r"\Z", r"\z"))
+    );
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a*b*c", &["abc", "a/b/c", "axbxbyc", "ab\nc"], &["ab", "xabc"])]
+#[case("a[]]b", &["a]b"], &["ab", "a]]b"])]
+#[case("data-?.csv", &["data-1.csv", "data-/.csv"], &["data-.csv"])]
+#[case("x[^y]", &["x^", "xy"], &["xz"])]
+#[case("[a&&b]", &["a", "&", "b"], &["ab"])]
+#[case("[z-a]x", &[], &["x", "zx", "ax"])]
+#[case("[!z-a]", &["z", "/"], &[""])]
+#[case("[b-a-z]", &["-", "z"], &["a", "b", "c"])]
+#[case("[ab", &["[ab"], &["a"])]
+#[case(r"a\*", &[r"a\", r"a\b"], &["a*"])]
+fn test_python_translate_match(
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let re = Regex::new(&fglob::python_fnmatch_translate(pattern)?)?;
+    println!("{} -> {}", pattern, re);
+    for item in expect_ok {
+        assert!(re.is_match(item));
+    }
+    for item in expect_fail {
+        assert!(!re.is_match(item));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_bare_escape() {
+    assert!(matches!(
+        fglob::glob_to_regex_string(r"[abc\"),
+        Err(FError::BareEscapeInClass)
+    ));
+    assert!(matches!(
+        fglob::glob_to_regex_string(r"x[!\"),
+        Err(FError::BareEscapeInClass)
+    ));
+    assert!(matches!(
+        fglob::glob_to_regex_string(r"abc\"),
+        Err(FError::BareEscape)
+    ));
+    assert!(matches!(
+        fglob::glob_to_regex_string(r"[abc\]"),
+        Err(FError::UnclosedClass(0))
+    ));
+}
+
+#[test]
+fn test_class_intersection_errors() {
+    let options = GlobOptions::default().class_intersection(true);
+    let parse = |pattern: &str| fglob::glob_to_regex_string_with(pattern, &options);
+    assert!(matches!(
+        parse("[a-z&&[^aeiou]x]"),
+        Err(FError::Unsupported {
+            feature: "class_items_after_intersection"
+        })
+    ));
+    assert!(matches!(parse("[a&&[b"), Err(FError::UnclosedClass(0))));
+    assert!(matches!(parse("x[a&&[b]"), Err(FError::UnclosedClass(1))));
+    assert!(matches!(
+        parse("[a&&[z-a]]"),
+        Err(FError::ReversedRange('z', 'a', 0))
+    ));
+    assert!(matches!(
+        parse(r"x[a&&[z-\a]]"),
+        Err(FError::ReversedRange('z', '\x07', 1))
+    ));
+    assert!(matches!(parse("[a&&[\\"), Err(FError::BareEscapeInClass)));
+}
+
+#[rstest::rstest]
+#[case("[[:nosuch:]]", "nosuch")]
+#[case("a[![:^Digit:]]", "Digit")]
+#[case("[x[:letter:]]", "letter")]
+fn test_unknown_class(#[case] pattern: &str, #[case] expected: &str) {
+    let res = fglob::glob_to_regex_string(pattern);
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::UnknownClass(name)) if name == expected));
+}
+
+#[rstest::rstest]
+#[case("[z-a]", 'z', 'a', 0)]
+#[case(r"[\z-a]", 'z', 'a', 0)]
+#[case(r"a[b-c]*[\~-b]", '~', 'b', 7)]
+#[case(r"[Z-\A]", 'Z', 'A', 0)]
+#[case(r"ab[\z-\t]", 'z', '\t', 2)]
+#[case(r"[xz-\a]", 'z', '\x07', 0)]
+fn test_reversed_range(
+    #[case] pattern: &str,
+    #[case] start: char,
+    #[case] end: char,
+    #[case] position: usize,
+) {
+    let res = fglob::glob_to_regex_string(pattern);
+    println!("{} -> {:?}", pattern, res);
+    assert!(
+        matches!(res, Err(FError::ReversedRange(s, e, pos)) if s == start && e == end && pos == position)
+    );
+}
+
+#[rstest::rstest]
+#[case(r"[a-\z]", "a", true)]
+#[case(r"[a-\z]", "z", true)]
+#[case(r"[a-\z]", "\\", false)]
+#[case(r"[\a-\r]", "\n", true)]
+#[case(r"[\a-\r]", "r", false)]
+#[case(r"[-\]]", "]", true)]
+fn test_class_range_end_escape(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] expected: bool,
+) -> Result<(), FError> {
+    let regex = fglob::glob_to_regex(pattern)?;
+    assert_eq!(regex.is_match(text), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("?{}", "")]
+#[case("?{a}", "a")]
+#[case("?{1,2}", "1,2")]
+#[case("?{-1}", "-1")]
+#[case("?{+1}", "+1")]
+#[case("?{3", "3")]
+fn test_invalid_repetition(#[case] pattern: &str, #[case] expected: &str) {
+    let res =
+        fglob::glob_to_regex_string_with(pattern, &GlobOptions::default().counted_repetition(true));
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::InvalidRepetition(spec)) if spec == expected));
+}
+
+#[rstest::rstest]
+#[case("")]
+#[case("a")]
+#[case(r"\w+")]
+#[case(r"[a-z]*")]
+#[case("[a-z][0-9]")]
+#[case("[a-z")]
+#[case(r"[a\]")]
+#[case(r"\p")]
+#[case(r"\p{}")]
+#[case("(a|b)")]
+fn test_invalid_single_char_class(#[case] class: &str) -> Result<(), FError> {
+    let res = fglob::glob_to_regex_string_with(
+        "a?",
+        &GlobOptions::default().single_char_class(Some(class.parse()?)),
+    );
+    println!("{} -> {:?}", class, res);
+    assert!(matches!(res, Err(FError::InvalidSingleCharClass(spec)) if spec == class));
+    Ok(())
+}
+
+#[test]
+fn test_single_char_class_owned() -> Result<(), FError> {
+    let config = format!("[{}]", "abc");
+    let options = GlobOptions::default().single_char_class(Some(SingleCharClass::new(&config)?));
+    drop(config);
+    assert_eq!(
+        fglob::glob_to_regex_string_with("x?", &options)?,
+        "^x(?:[abc])$"
+    );
+    assert_eq!(
+        options
+            .single_char_class
+            .map(|class| class.to_string())
+            .as_deref(),
+        Some("[abc]")
+    );
+
+    let longest = format!("[{}]", "a".repeat(SingleCharClass::MAX_LEN - 2));
+    assert_eq!(SingleCharClass::new(&longest)?.as_str(), longest);
+    let too_long = format!("[{}]", "a".repeat(SingleCharClass::MAX_LEN - 1));
+    assert!(matches!(
+        SingleCharClass::new(&too_long),
+        Err(FError::InvalidSingleCharClass(spec)) if spec == too_long
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_single_char_class_valid() -> Result<(), Box<dyn Error>> {
+    for class in [
+        ".",
+        r"\W",
+        r"\pL",
+        r"\P{Greek}",
+        "[]a]",
+        "[^]a]",
+        "[[:alpha:]_]",
+        r"[\]]",
+    ] {
+        let re = fglob::glob_to_regex_with(
+            "a?",
+            &GlobOptions::default().single_char_class(Some(class.parse()?)),
+        )?;
+        println!("{} -> {}", class, re);
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("(?x)*.txt", "x")]
+#[case("(?iX)*.txt", "iX")]
+#[case("(?m)", "m")]
+fn test_invalid_flags(#[case] pattern: &str, #[case] expected: &str) {
+    let res = fglob::glob_to_regex_string_with(pattern, &GlobOptions::default().inline_flags(true));
+    println!("{} -> {:?}", pattern, res);
+    assert!(matches!(res, Err(FError::InvalidFlags(flags)) if flags == expected));
+}
+
+#[test]
+fn test_long_alternation() -> Result<(), FError> {
+    let branches: Vec<_> = (0..10_000).map(|idx| format!("item{}.txt", idx)).collect();
+    let re = fglob::glob_to_regex(&format!("dir/{{{}}}", branches.join(",")))?;
+    for item in ["item0.txt", "item5000.txt", "item9999.txt"] {
+        assert!(re.is_match(&format!("dir/{}", item)));
+    }
+    for item in [
+        "item10000.txt",
+        "item0.txt",
+        "dir/item0.tx",
+        "dir/item0.txtt",
+    ] {
+        assert!(!re.is_match(item));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_long_class() -> Result<(), FError> {
+    let chars: String = ('\u{100}'..'\u{2800}').rev().collect();
+    let pattern = format!("[{}/a-z-]", chars);
+    let res = fglob::glob_to_regex_string(&pattern)?;
+    assert!(res.len() < pattern.len() + 10);
+
+    let re = fglob::glob_to_regex(&pattern)?;
+    for item in ["\u{100}", "\u{1000}", "\u{27ff}", "a", "-"] {
+        assert!(re.is_match(item));
+    }
+    for item in ["\u{ff}", "\u{2800}", "/", "A", ""] {
+        assert!(!re.is_match(item));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_long_literal() -> Result<(), FError> {
+    let text = "a.b(c)|d".repeat(5_000);
+    let re = fglob::glob_to_regex(&text)?;
+    assert!(re.is_match(&text));
+    assert!(!re.is_match(&text[1..]));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("{alpha,alps,beta}", "^(?:alp(?:ha|s)|beta)$")]
+#[case("{alpha,alps,al,beta,be}", "^(?:al(?:p(?:ha|s))?|be(?:ta)?)$")]
+#[case("x{b,a,a,b}y", "^x(?:a|b)y$")]
+#[case("x{,a}y", "^x(?:a)?y$")]
+#[case("{abc}", "^abc$")]
+#[case("{a.c,a+c}", r"^a(?:\+c|\.c)$")]
+#[case("{test,testing,tested}", "^test(?:ed|ing)?$")]
+fn test_compact_alternations(#[case] pattern: &str, #[case] expected: &str) -> Result<(), FError> {
+    let options = GlobOptions::default().compact_alternations(true);
+    assert_eq!(
+        fglob::glob_to_regex_string_with(pattern, &options)?,
+        expected
+    );
+
+    let compact = fglob::glob_to_regex_with(pattern, &options)?;
+    let plain = fglob::glob_to_regex(pattern)?;
+    for item in crate::expand::expand_braces(pattern)? {
+        assert!(compact.is_match(&item));
+        assert!(plain.is_match(&item));
+    }
+    for item in [
+        "", "a", "al", "alp", "b", "bet", "xy", "tes", "testin", "a.cc",
+    ] {
+        assert_eq!(compact.is_match(item), plain.is_match(item));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.rs", GlobOptions::default(), "[^/]*\\.rs")]
+#[case("/a{b,c}?", GlobOptions::default().root_relative(true), "/a(b|c)[^/]")]
+#[case("*.log", GlobOptions::default().match_at_any_level(true), "[^/]*\\.log")]
+#[case("**/x", GlobOptions::default().globstar(true), "(?:[^/]+/)*x")]
+#[case("(?i)a*", GlobOptions::default().inline_flags(true), "(?i:a[^/]*)")]
+#[case("(?i)a*", GlobOptions::default(), "\\([^/]i\\)a[^/]*")]
+fn test_fragment(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+) -> Result<(), FError> {
+    assert_eq!(fglob::glob_to_regex_fragment(pattern, &options)?, expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("[a-a]", "[a]")]
+#[case("[a-b]", "[ab]")]
+#[case("[a-c]", "[a-c]")]
+#[case("[b-ca]", "[abc]")]
+#[case("[x-z0-1]", "[01x-z]")]
+#[case("[!a-b]", "[^/ab]")]
+#[case("[.-0]", "[.0]")]
+#[case("[+--]", r"[+-\-]")]
+#[case("[+-,]", "[+,]")]
+fn test_class_normalization(#[case] pattern: &str, #[case] expected: &str) -> Result<(), FError> {
+    let fragment = fglob::glob_to_regex_fragment(pattern, &GlobOptions::default())?;
+    println!("{} -> {}", pattern, fragment);
+    assert_eq!(fragment, expected);
+    Ok(())
+}
+
+#[test]
+fn test_fragment_embed() -> Result<(), FError> {
+    let options = GlobOptions::default();
+    let source = fglob::glob_to_regex_fragment("*.rs", &options)?;
+    let dir = fglob::glob_to_regex_fragment("{src,tests}", &options)?;
+    let re = Regex::new(&format!("^{}/(?:{},)+{}\\.bak$", dir, source, source)).unwrap();
+    for item in ["src/a.rs,b.rs,.rs.bak", "tests/x.rs,x.rs.bak"] {
+        assert!(re.is_match(item));
+    }
+    for item in ["src/a.rs.bak", "docs/a.rs,a.rs.bak", "src/a/b.rs,a.rs.bak"] {
+        assert!(!re.is_match(item));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse() -> Result<(), FError> {
+    let ast = fglob::parse(
+        "/**/a?[!x-z]{b,}*",
+        &GlobOptions::default().globstar(true).root_relative(true),
+    )?;
+    assert!(ast.anchored_root);
+    assert!(!ast.any_level);
+    assert_eq!(
+        ast.nodes,
+        vec![
+            Node::GlobstarDirs,
+            Node::Literal("a".to_owned()),
+            Node::AnyChar,
+            Node::Class(Class {
+                negated: true,
+                items: vec![ClassItem::Range('x', 'z')],
+                intersections: Vec::new(),
+            }),
+            Node::Alternation(vec![vec![Node::Literal("b".to_owned())], vec![]]),
+            Node::Star,
+        ]
+    );
+
+    let ast = fglob::parse("**", &GlobOptions::default())?;
+    assert_eq!(ast.nodes, vec![Node::Star, Node::Star]);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("abc*def", &["abc", "def"])]
+#[case(r"a\*b?c\?d", &["a*b", "c?d"])]
+#[case("ab{cd,e}f/g", &["ab", "f/g"])]
+fn test_parse_merged_literals(
+    #[case] pattern: &str,
+    #[case] expected: &[&str],
+) -> Result<(), FError> {
+    let ast = fglob::parse(pattern, &GlobOptions::default())?;
+    let literals: Vec<&str> = ast
+        .nodes
+        .iter()
+        .filter_map(|node| match *node {
+            Node::Literal(ref text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(literals, expected);
+    assert_eq!(ast.nodes.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_parse_spans() -> Result<(), FError> {
+    let pattern = "/d\u{e9}j\\**[!x-z]{b,c\\}}?";
+    let ast = fglob::parse(pattern, &GlobOptions::default().root_relative(true))?;
+    let texts: Vec<&str> = ast
+        .spans
+        .iter()
+        .map(|span| &pattern[span.range()])
+        .collect();
+    assert_eq!(texts, ["d\u{e9}j\\*", "*", "[!x-z]", "{b,c\\}}", "?"]);
+    assert_eq!(ast.spans.len(), ast.nodes.len());
+    assert_eq!(ast.spans.first(), Some(&Span { start: 1, end: 7 }));
+
+    let ast = fglob::parse("ab", &GlobOptions::default().treat_as_prefix(true))?;
+    assert_eq!(
+        ast.spans,
+        [Span { start: 0, end: 2 }, Span { start: 2, end: 2 }]
+    );
+
+    let ast = fglob::parse("[[:alpha:]]x", &GlobOptions::default())?;
+    assert_eq!(
+        ast.spans,
+        [Span { start: 0, end: 11 }, Span { start: 11, end: 12 }]
+    );
+
+    let pattern = "\u{e9}[[:^Letter:]\u{e9}]*{[[:digit:]],b}?";
+    let ast = fglob::parse(pattern, &GlobOptions::default())?;
+    let texts: Vec<&str> = ast
+        .spans
+        .iter()
+        .map(|span| &pattern[span.range()])
+        .collect();
+    assert_eq!(
+        texts,
+        ["\u{e9}", "[[:^Letter:]\u{e9}]", "*", "{[[:digit:]],b}", "?"]
+    );
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(Preset::Filename, &[false, true, false, false, false])]
+#[case(Preset::Path, &[true, true, true, false, false])]
+#[case(Preset::PlainText, &[false, true, true, true, true])]
+fn test_preset(#[case] preset: Preset, #[case] expected: &[bool]) -> Result<(), FError> {
+    let options = GlobOptions::from(preset);
+    let re = fglob::glob_to_regex_with("a/**/b?", &options)?;
+    println!("{:?}: {:?} -> {}", preset, options, re);
+    let texts = ["a/b1", "a/x/b2", "a/x/y/b3", "za/x/bc/d", "a/x/b/"];
+    let actual: Vec<bool> = texts.iter().map(|text| re.is_match(text)).collect();
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", true)]
+#[case("file?.txt", true)]
+#[case("[abc]", true)]
+#[case("{a,b}", true)]
+#[case("[abc", true)]
+#[case(r"a\\*", true)]
+#[case(r"\*.txt", false)]
+#[case(r"file\?\[x\]\{a,b\}", false)]
+#[case("plain/path.txt", false)]
+#[case("a]b}c", false)]
+#[case("", false)]
+#[case(r"trailing\", false)]
+fn test_has_glob_metachars(#[case] pattern: &str, #[case] expected: bool) {
+    assert_eq!(fglob::has_glob_metachars(pattern), expected);
+}
+
+#[rstest::rstest]
+#[case("plain.txt", "plain.txt")]
+#[case("*", r"\*")]
+#[case("?", r"\?")]
+#[case("[", r"\[")]
+#[case("]", r"\]")]
+#[case("{", r"\{")]
+#[case("}", r"\}")]
+#[case(r"\", r"\\")]
+#[case("a*b?c[d]e{f,g}h\\i", r"a\*b\?c\[d\]e\{f,g\}h\\i")]
+#[case("!-^$.+()|,/", "!-^$.+()|,/")]
+fn test_escape_literal(#[case] text: &str, #[case] expected: &str) -> Result<(), FError> {
+    let escaped = fglob::escape_literal(text);
+    assert_eq!(escaped, expected);
+    assert!(!fglob::has_glob_metachars(&escaped));
+    let re = fglob::glob_to_regex(&escaped)?;
+    assert!(re.is_match(text));
+    assert!(!re.is_match(&format!("{}x", text)));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("abc", "abc", &["x", "[abc]"])]
+#[case("a-z", r"a\-z", &["b", "y"])]
+#[case("!a", r"\!a", &["b"])]
+#[case("]", r"\]", &["[", "]]"])]
+#[case("[:alpha:]", r"\[:alpha:\]", &["b", "[:"])]
+#[case(r"\n", r"\\n", &["\n"])]
+#[case("&&", r"\&\&", &["a"])]
+fn test_escape_for_class(
+    #[case] text: &str,
+    #[case] expected: &str,
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let escaped = fglob::escape_for_class(text);
+    assert_eq!(escaped, expected);
+    let options = GlobOptions::default().class_intersection(true);
+    let re = fglob::glob_to_regex_with(&format!("[{}]", escaped), &options)?;
+    for chr in text.chars() {
+        assert!(re.is_match(&chr.to_string()));
+    }
+    for item in expect_fail {
+        assert!(!re.is_match(item));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("src/*/{a,b}.rs", '/', &["src", "*", "{a,b}.rs"])]
+#[case("{a/b,c}/d", '/', &["{a/b,c}", "d"])]
+#[case("x{a,{b/c,d}/e}/f", '/', &["x{a,{b/c,d}/e}", "f"])]
+#[case("[a/b]/c", '/', &["[a/b]", "c"])]
+#[case("[]/]/[!]/]", '/', &["[]/]", "[!]/]"])]
+#[case("[[:alpha:]/]/x", '/', &["[[:alpha:]/]", "x"])]
+#[case(r"a\/b/c", '/', &[r"a\/b", "c"])]
+#[case(r"[\]/]/c", '/', &[r"[\]/]", "c"])]
+#[case("/etc/", '/', &["", "etc", ""])]
+#[case("", '/', &[""])]
+#[case("a}/b", '/', &["a}", "b"])]
+#[case(r"src\\*.{c,h}", '\\', &["src", "*.{c,h}"])]
+#[case(r"{a\\b,c}\\d\*", '\\', &[r"{a\\b,c}", r"d\*"])]
+#[case("a:{b:c}:[:]", ':', &["a", "{b:c}", "[:]"])]
+#[case(r"trailing\", '/', &[r"trailing\"])]
+fn test_split_segments(
+    #[case] pattern: &str,
+    #[case] sep: char,
+    #[case] expected: &[&str],
+) -> Result<(), FError> {
+    assert_eq!(fglob::split_segments(pattern, sep)?, expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a/[b/c", FError::UnclosedClass(2))]
+#[case("a/[[:alpha:]", FError::UnclosedClass(2))]
+#[case("a/{b,{c/d}", FError::UnclosedAlternation(2))]
+#[case("a/{b/c", FError::UnclosedAlternation(2))]
+fn test_split_segments_errors(#[case] pattern: &str, #[case] expected: FError) {
+    assert_eq!(
+        fglob::split_segments(pattern, '/').unwrap_err().to_string(),
+        expected.to_string()
+    );
+}
+
+#[rstest::rstest]
+#[case("{b,a}", GlobOptions::default(), "{a,b}")]
+#[case("{a,b,a}", GlobOptions::default(), "{a,b}")]
+#[case("x{b\\,c,a*}y", GlobOptions::default(), "x{a*,b\\,c}y")]
+#[case("[ca-b]", GlobOptions::default(), "[a-c]")]
+#[case("[!zxy0-45-9]", GlobOptions::default(), "[!0-9x-z]")]
+#[case("[ab]", GlobOptions::default(), "[ab]")]
+#[case("[]!-]", GlobOptions::default(), "[\\!\\-\\]]")]
+#[case(
+    "[[:digit:]a[:alpha:][:digit:]]",
+    GlobOptions::default(),
+    "[a[:alpha:][:digit:]]"
+)]
+#[case(
+    "[m-za-n&&[^x]&&[b]]",
+    GlobOptions::default().class_intersection(true),
+    "[a-z&&[!x]&&[b]]"
+)]
+#[case("\\q\\*\\\\,", GlobOptions::default(), "q\\*\\\\,")]
+#[case("a,b", GlobOptions::default(), "a,b")]
+#[case("**/src/**", GlobOptions::from(Preset::Path), "**/src/**")]
+#[case("?{3}x", GlobOptions::default().counted_repetition(true), "?{3}x")]
+#[case("(?si)a", GlobOptions::default().inline_flags(true), "(?is)a")]
+#[case("/etc/*", GlobOptions::default().root_relative(true), "/etc/*")]
+#[case("{}", GlobOptions::default(), "\\{\\}")]
+#[case("v[1-2]?", GlobOptions::default().optional_class(true), "v{,[12]}")]
+#[case("a**b", GlobOptions::default(), "a*b")]
+#[case("***", GlobOptions::from(Preset::Path), "*")]
+#[case("x/***/y", GlobOptions::from(Preset::Path), "x/*/y")]
+#[case("x/***", GlobOptions::from(Preset::Path), "x/*")]
+fn test_to_glob_string(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+) -> Result<(), FError> {
+    let ast = fglob::parse(pattern, &options)?;
+    let canonical = ast.to_glob_string();
+    assert_eq!(canonical, expected);
+    let reparsed = fglob::parse(&canonical, &options)?;
+    assert_eq!(reparsed.to_glob_string(), canonical);
+    let (original, normalized) = (
+        crate::Glob::with_options(pattern, &options)?,
+        crate::Glob::with_options(&canonical, &options)?,
+    );
+    for text in original
+        .sample_matches(20)
+        .iter()
+        .chain(&original.counter_examples(20))
+    {
+        assert_eq!(
+            normalized.is_match(text),
+            original.is_match(text),
+            "{:?}",
+            text
+        );
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(r"a\*b\?c\[d\]e\{f\}g\\h%i.j")]
+#[case(r"x{a\,b,c\}d,\*}y")]
+#[case(r"[\]\-a[:digit:]][\!\^b-d][!\]\-][a\!][\-\^][z\]\\%]")]
+#[case(r"[\[-\]][\!-#][\--/x][*?{}]")]
+#[case(r"??{a/b,*}?")]
+#[case(r"\(?i)a")]
+fn test_to_glob_string_escaping(#[case] pattern: &str) -> Result<(), FError> {
+    let ast = fglob::parse(pattern, &GlobOptions::default().inline_flags(true))?;
+    let original = crate::Glob::with_options(pattern, &GlobOptions::default().inline_flags(true))?;
+    let texts: Vec<String> = original
+        .sample_matches(20)
+        .into_iter()
+        .chain(original.counter_examples(20))
+        .collect();
+    for escaping in [
+        GlobOptions::default(),
+        GlobOptions::default().escape_char(Some('%')),
+        GlobOptions::default().escape_char(None),
+        GlobOptions::default().escape_by_doubling(true),
+        GlobOptions::default()
+            .escape_by_doubling(true)
+            .escape_char(None),
+        GlobOptions::default()
+            .escape_by_doubling(true)
+            .escape_char(None)
+            .counted_repetition(true),
+        GlobOptions::default()
+            .escape_char(None)
+            .dot_is_wildcard(true),
+    ] {
+        let options = escaping.inline_flags(true);
+        let rendered = ast.to_glob_string_with(&options);
+        println!("{} -> {}", pattern, rendered);
+        let reparsed = fglob::parse(&rendered, &options)?;
+        assert_eq!(reparsed.to_glob_string_with(&options), rendered);
+        let glob = crate::Glob::with_options(&rendered, &options)?;
+        for text in &texts {
+            assert_eq!(glob.is_match(text), original.is_match(text), "{:?}", text);
+        }
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("[[:alpha:]]%*", GlobOptions::default().escape_char(Some('%')), "[[:alpha:]]%*")]
+#[case(r"x%%y\[%]]", GlobOptions::default().escape_char(Some('%')), r"x%%y\[%]]")]
+#[case(
+    "??{a/b,*}?",
+    GlobOptions::default()
+        .escape_by_doubling(true)
+        .escape_char(None)
+        .counted_repetition(true),
+    "??{*,a/b}?"
+)]
+#[case("**[[]{{", GlobOptions::default().escape_by_doubling(true), r"\*\[\]\{")]
+#[case("a\\[]-]{\\}", GlobOptions::default().escape_char(None), "a\\[]-]{\\}")]
+fn test_to_glob_string_with_escaping(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+) -> Result<(), FError> {
+    let ast = fglob::parse(pattern, &options)?;
+    let rendered = ast.to_glob_string_with(&options);
+    assert_eq!(rendered, expected);
+    assert_eq!(
+        fglob::parse(&rendered, &options)?.to_glob_string(),
+        ast.to_glob_string()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_to_glob_string_separator() -> Result<(), FError> {
+    let options = GlobOptions::from(Preset::Path).separator('\\');
+    let ast = fglob::parse("a\\\\**\\\\{d,c}", &options)?;
+    assert_eq!(ast.to_glob_string_with(&options), "a\\\\**\\\\{c,d}");
+    let percent = options.escape_char(Some('%'));
+    let ast = fglob::parse("a\\**\\{d,c%,}", &percent)?;
+    assert_eq!(ast.to_glob_string_with(&percent), "a\\**\\{c%,,d}");
+    let percent_sep = percent.separator('%');
+    let ast = fglob::parse("a%%**%%{d,c%,}", &percent_sep)?;
+    assert_eq!(ast.to_glob_string_with(&percent_sep), "a%%**%%{c%,,d}");
+    Ok(())
+}
+
+#[test]
+fn test_many_empty_chunks() -> Result<(), FError> {
+    // Each character of a class and each excluded separator produces
+    // an empty chunk that is flattened away; make sure a long run of them
+    // does not exhaust the stack.
+    let count = 100_000;
+    let re = fglob::glob_to_regex(&format!("x[{}a]", "/".repeat(count)))?;
+    assert!(re.is_match("xa"));
+    assert!(!re.is_match("x/"));
+    let re = fglob::glob_to_regex(&format!("x[{}]", "a-c".repeat(count)))?;
+    assert!(re.is_match("xb"));
+    Ok(())
+}
+
+#[test]
+fn test_error_helpers() {
+    let err = FError::new("no patterns specified");
+    assert!(matches!(err, FError::Other(ref message) if message == "no patterns specified"));
+    assert_eq!(err.to_string(), "no patterns specified");
+
+    let unclosed = "(".repeat(2);
+    let re_err = Regex::new(&unclosed).unwrap_err();
+    let err = FError::invalid_regex("((", &unclosed, &re_err);
+    assert!(matches!(
+        err,
+        FError::InvalidRegex(ref glob, ref pattern, _) if glob == "((" && *pattern == unclosed
+    ));
+    assert!(err
+        .to_string()
+        .starts_with("Glob `((` produced invalid regex `((`: "));
+
+    let err = FError::from(re_err.clone());
+    assert!(matches!(err, FError::Other(ref message) if *message == re_err.to_string()));
+}
+
+#[rstest::rstest]
+#[case("{a,b,c}", GlobOptions::default(), "^[a-c]$")]
+#[case("[abcdx-z]", GlobOptions::default(), "^[a-dx-z]$")]
+#[case("{x,x}.txt", GlobOptions::default(), r"^x\.txt$")]
+#[case("{a,[b-d],e}", GlobOptions::default(), "^[a-e]$")]
+#[case("{ab,ac}", GlobOptions::default(), "^(ab|ac)$")]
+#[case(
+    "{alphabet,alphanumeric,alphabetical}",
+    GlobOptions::default(),
+    "^alpha(?:bet(?:ical)?|numeric)$"
+)]
+fn test_glob_to_regex_compact(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+) -> Result<(), FError> {
+    let compact = fglob::glob_to_regex_compact(pattern, &options)?;
+    println!("{} -> {}", pattern, compact);
+    assert_eq!(compact.as_str(), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("{a,b,c}*.{c,h,c}", GlobOptions::default())]
+#[case("[abc][def]{[g-i],j}", GlobOptions::default())]
+#[case("{a,/,.}x", GlobOptions::default())]
+#[case("{.,a,b}*", GlobOptions::default().literal_leading_dot(true))]
+#[case("src/{a,b}/**/{c,d}", GlobOptions::default().globstar(true))]
+#[case("{a,a/}**", GlobOptions::default().globstar(true).collapse_globstar_edges(true))]
+#[case("{[!a],b,[c-e&&[!d]]}", GlobOptions::default().class_intersection(true))]
+#[case("{A,b}[[:digit:]0-9x]", GlobOptions::default().inline_flags(true))]
+#[case("(?i){A,b}", GlobOptions::default().inline_flags(true))]
+#[case("{alpha,alps,beta,,x}", GlobOptions::default())]
+fn test_glob_to_regex_compact_equivalent(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+) -> Result<(), FError> {
+    let full = fglob::glob_to_regex_with(pattern, &options)?;
+    let compact = fglob::glob_to_regex_compact(pattern, &options)?;
+    println!("{} -> {} -> {}", pattern, full, compact);
+    assert!(compact.as_str().len() <= full.as_str().len());
+    let glob = crate::Glob::with_options(pattern, &options)?;
+    let texts: Vec<String> = glob
+        .sample_matches(50)
+        .into_iter()
+        .chain(glob.counter_examples(50))
+        .chain(
+            [
+                "", "a", "/", ".", ".x", "ax", "a/x", "B", "d", "5", "src/a/c",
+            ]
+            .iter()
+            .map(|text| (*text).to_owned()),
+        )
+        .collect();
+    for text in &texts {
+        assert_eq!(compact.is_match(text), full.is_match(text), "{:?}", text);
+    }
+    Ok(())
+}
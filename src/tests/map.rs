@@ -0,0 +1,165 @@
+//! Test the glob map functionality.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use crate::error::Error as FError;
+use crate::map::GlobMap;
+use crate::matcher::Glob;
+use crate::options::GlobOptions;
+
+/// Build a map of file types by extension, the more specific ones first.
+fn build_types() -> Result<GlobMap<&'static str>, FError> {
+    let mut types = GlobMap::new();
+    types.insert("*.tar.gz", "tarball")?;
+    types.insert("*.gz", "gzip")?;
+    types.insert("*.{c,h}", "C")?;
+    types.insert("*", "other")?;
+    Ok(types)
+}
+
+#[rstest::rstest]
+#[case("src.tar.gz", Some("tarball"))]
+#[case("log.1.gz", Some("gzip"))]
+#[case(".gz", Some("gzip"))]
+#[case("main.c", Some("C"))]
+#[case("README", Some("other"))]
+#[case("src/main.c", None)]
+fn test_get(#[case] text: &str, #[case] expected: Option<&str>) -> Result<(), FError> {
+    let types = build_types()?;
+    assert_eq!(types.get(text).copied(), expected);
+    Ok(())
+}
+
+#[test]
+fn test_insertion_order() -> Result<(), FError> {
+    let mut types = GlobMap::new();
+    assert!(types.is_empty());
+    assert_eq!(types.get("src.tar.gz"), None);
+
+    types.insert("*.gz", "gzip")?;
+    assert_eq!(types.get("src.tar.gz"), Some(&"gzip"));
+
+    types.insert("*.tar.gz", "tarball")?;
+    assert_eq!(types.get("src.tar.gz"), Some(&"gzip"));
+    assert_eq!(types.len(), 2);
+    assert_eq!(
+        types
+            .iter()
+            .map(|(glob, value)| (glob.pattern(), *value))
+            .collect::<Vec<_>>(),
+        [("*.gz", "gzip"), ("*.tar.gz", "tarball")]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_insert_glob() -> Result<(), FError> {
+    let mut types = build_types()?;
+    assert_eq!(types.get("lib/util.rs"), None);
+    types.insert_glob(
+        Glob::with_options("*.rs", &GlobOptions::default().match_at_any_level(true))?,
+        "Rust",
+    );
+    assert_eq!(types.get("lib/util.rs"), Some(&"Rust"));
+    assert_eq!(types.get("util.rs"), Some(&"other"));
+    assert!(matches!(
+        types.insert("[abc", "bad"),
+        Err(FError::UnclosedClass(0))
+    ));
+    assert_eq!(types.len(), 5);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("src/main.rs", Some("src rust"))]
+#[case("lib.rs", Some("rust"))]
+#[case("src/lib.rs", Some("src lib"))]
+#[case("src/bib.rs", Some("src ?ib"))]
+#[case("src/a.cc", Some("src C"))]
+#[case("src/README", Some("src"))]
+#[case("docs/a.md", None)]
+fn test_get_most_specific(
+    #[case] text: &str,
+    #[case] expected: Option<&str>,
+) -> Result<(), FError> {
+    let mut types = GlobMap::new();
+    types.insert("*.rs", "rust")?;
+    types.insert("src/*", "src")?;
+    types.insert("src/*.rs", "src rust")?;
+    types.insert("src/*.{c,cc}", "src C")?;
+    types.insert("src/?ib.rs", "src ?ib")?;
+    types.insert("src/lib.rs", "src lib")?;
+    types.insert("src/*.rs", "src rust again")?;
+    assert_eq!(types.get_most_specific(text).copied(), expected);
+    Ok(())
+}
+
+#[test]
+fn test_get_most_specific_first() -> Result<(), FError> {
+    let mut types = GlobMap::new();
+    types.insert("*/*", "nested")?;
+    types.insert("src/*.rs", "src rust")?;
+    types.insert("*.rs", "rust")?;
+    assert_eq!(types.get("src/main.rs"), Some(&"nested"));
+    assert_eq!(types.get_most_specific("src/main.rs"), Some(&"src rust"));
+    assert_eq!(types.get("main.rs"), Some(&"rust"));
+    assert_eq!(types.get_most_specific("main.rs"), Some(&"rust"));
+    assert_eq!(types.get_most_specific("README"), None);
+    Ok(())
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_normalize() -> Result<(), FError> {
+    use crate::options::NormalizationForm;
+
+    let options = GlobOptions::default().normalize(Some(NormalizationForm::Nfc));
+    let mut types = GlobMap::new();
+    types.insert("*.md", "markdown")?;
+    types.insert_glob(Glob::with_options("caf\u{e9}/*", &options)?, "cafe");
+    assert_eq!(types.get("caf\u{e9}/a.txt"), Some(&"cafe"));
+    assert_eq!(types.get("cafe\u{301}/a.txt"), Some(&"cafe"));
+    assert_eq!(types.get_most_specific("cafe\u{301}/a.md"), Some(&"cafe"));
+    assert_eq!(types.get("README.md"), Some(&"markdown"));
+    assert_eq!(types.get("cafe/a.txt"), None);
+    Ok(())
+}
+
+#[test]
+fn test_unicode() -> Result<(), FError> {
+    let options = GlobOptions::default().inline_flags(true).unicode(false);
+    let mut types = GlobMap::new();
+    types.insert_glob(Glob::with_options("(?i)k", &options)?, "k");
+    assert_eq!(types.get("K"), Some(&"k"));
+    assert_eq!(types.get("\u{212a}"), None);
+    types.insert("*", "any")?;
+    assert_eq!(types.get("K"), Some(&"k"));
+    assert_eq!(types.get("\u{212a}"), Some(&"any"));
+    Ok(())
+}
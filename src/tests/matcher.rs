@@ -0,0 +1,1748 @@
+//! Test the compiled glob functionality.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::error::Error;
+use std::ffi::{CStr, OsStr, OsString};
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use regex::Regex;
+
+use crate::ast::GlobAst;
+use crate::error::Error as FError;
+use crate::glob as fglob;
+use crate::matcher::{CaseMatch, Glob, MatchKind, SegmentKind};
+use crate::options::{Anchoring, EmptyBrace, FeatureSet, GlobOptions, Preset};
+
+#[rstest::rstest]
+#[case("/foo", true, &["foo"], &["/foo", "a/foo", "a/b/foo", "foo/a"])]
+#[case("/a/foo", true, &["a/foo"], &["/a/foo", "b/a/foo"])]
+#[case("foo", false, &["foo", "a/foo", "a/b/foo"], &["afoo", "a/afoo", "foo/a"])]
+#[case("*.log", false, &["x.log", "a/x.log", "a/b/x.log"], &["x.log/a", "xlog"])]
+#[case("foo/", false, &["foo/", "a/foo/"], &["foo", "a/foo"])]
+#[case("a/foo", false, &["a/foo"], &["b/a/foo", "foo", "/a/foo"])]
+fn test_root_relative(
+    #[case] pattern: &str,
+    #[case] anchored: bool,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &GlobOptions::default().root_relative(true))?;
+    println!("{} -> {}", pattern, glob.regex());
+    assert_eq!(glob.pattern(), pattern);
+    assert_eq!(glob.is_anchored_root(), anchored);
+
+    for item in expect_ok {
+        println!("- {} should match", item);
+        assert!(glob.is_match(item));
+    }
+
+    for item in expect_fail {
+        println!("- {} should not match", item);
+        assert!(!glob.is_match(item));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_full_path() -> Result<(), FError> {
+    let glob = Glob::new("/foo")?;
+    assert!(!glob.is_anchored_root());
+    assert!(glob.is_match("/foo"));
+    assert!(!glob.is_match("foo"));
+
+    let glob = Glob::new("foo")?;
+    assert!(!glob.is_anchored_root());
+    assert!(glob.is_match("foo"));
+    assert!(!glob.is_match("a/foo"));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", GlobOptions::default(), 1)]
+#[case("{a,b}/*.{c,h}", GlobOptions::default(), 3)]
+#[case("{a,b}/*.{c,h}", GlobOptions::default().compact_alternations(true), 1)]
+fn test_capture_names(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: usize,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.capture_names(), vec![None; expected]);
+    assert_eq!(glob.capture_names().len(), glob.regex().captures_len());
+    Ok(())
+}
+
+#[test]
+fn test_regex_sources() -> Result<(), FError> {
+    let glob = Glob::new("\u{1f600}?[!\u{10ffff}]")?;
+    let text = glob.as_regex_str();
+    let bytes = glob.as_regex_bytes_str();
+    println!("{} -> {} / {}", glob.pattern(), text, bytes);
+    assert_eq!(text, glob.regex().as_str());
+    assert_eq!(text, "^\u{1f600}[^/][^/\u{10ffff}]$");
+    assert_ne!(text, bytes);
+    assert!(bytes.starts_with("^\u{1f600}(?:[^/]|(?-u:"));
+    assert!(bytes.contains("(?:[^/\u{10ffff}]|(?-u:"));
+    assert!(glob.is_match_bytes(b"\xf0\x9f\x98\x80\xff\xfe"));
+    assert!(!glob.is_match_bytes("\u{1f600}a\u{10ffff}".as_bytes()));
+    Ok(())
+}
+
+#[test]
+fn test_shared() -> Result<(), FError> {
+    let glob = Glob::new("*.txt")?;
+    let regex = glob.regex_arc();
+    assert!(Arc::ptr_eq(&regex, &glob.clone().regex_arc()));
+
+    let handles: Vec<_> = ["a.txt", "b.txt", "c.log"]
+        .iter()
+        .map(|name| {
+            let (thr_glob, thr_regex) = (glob.clone(), Arc::clone(&regex));
+            thread::spawn(move || (thr_glob.is_match(name), thr_regex.is_match(name)))
+        })
+        .collect();
+    let results: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(results, [(true, true), (true, true), (false, false)]);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("plain.txt", "plain.txt")]
+#[case(r"a*b?c[d]e{f,g}h\i!j", r"a\*b\?c\[d\]e\{f,g\}h\\i!j")]
+#[case("(a|b)+^c$.d-e/f", "(a|b)+^c$.d-e/f")]
+#[case("[!]]{}", r"\[!\]\]\{\}")]
+fn test_literal(#[case] text: &str, #[case] pattern: &str) -> Result<(), FError> {
+    let glob = Glob::literal(text)?;
+    println!("{} -> {} -> {}", text, glob.pattern(), glob.regex());
+    assert_eq!(glob.pattern(), pattern);
+    assert!(glob.is_match(text));
+    assert!(!glob.is_match(format!("{}x", text)));
+    assert!(!glob.is_match(format!("x{}", text)));
+    assert!(!glob.is_match(&text[1..]));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("src*", "*.rs", &["src*/main.rs", "src*/.rs"], &["src1/main.rs", "src*/a/main.rs", "src*main.rs"])]
+#[case("src*/", "*.rs", &["src*/main.rs"], &["src*//main.rs", "srcx/main.rs"])]
+#[case("/a/[b]", "?", &["/a/[b]/x"], &["/a/b/x", "/a/[b]/xy", "/a/[b]//"])]
+#[case("/", "*.rs", &["/main.rs"], &["main.rs", "//main.rs"])]
+#[case("", "*.rs", &["main.rs"], &["/main.rs"])]
+fn test_under_dir(
+    #[case] dir: &str,
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::under_dir(dir, pattern)?;
+    println!(
+        "{} {} -> {} -> {}",
+        dir,
+        pattern,
+        glob.pattern(),
+        glob.regex()
+    );
+
+    for item in expect_ok {
+        println!("- {} should match", item);
+        assert!(glob.is_match(item));
+    }
+
+    for item in expect_fail {
+        println!("- {} should not match", item);
+        assert!(!glob.is_match(item));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "glob")]
+#[rstest::rstest]
+#[case("*.txt", "*.txt", &["a.txt", ".txt"], &["a/b.txt", "a.txt/b"])]
+#[case("a/**/b", "a/**/b", &["a/b", "a/x/b", "a/x/y/b"], &["b", "a/xb"])]
+#[case(r"a\b{c,d}", r"a\\b\{c,d}", &[r"a\b{c,d}"], &["abc", "a\x08c", r"a\bc"])]
+#[case("[^a]", "[^a]", &["^", "a"], &["b", "/"])]
+#[case("[!^a]", "[!^a]", &["b", "]"], &["^", "a", "/"])]
+#[case(r"[\]x", r"[\\]x", &[r"\x"], &["]x", "x"])]
+#[case("[]a]", "[]a]", &["]", "a"], &["b"])]
+#[case("[?][*]", "[?][*]", &["?*"], &["a*", "??"])]
+fn test_glob_crate(
+    #[case] source: &str,
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob_pattern = glob::Pattern::new(source).unwrap();
+    let glob = Glob::from_glob_crate_pattern(&glob_pattern)?;
+    println!("{} -> {} -> {}", source, glob.pattern(), glob.regex());
+    assert_eq!(glob.pattern(), pattern);
+
+    for item in expect_ok {
+        println!("- {} should match", item);
+        assert!(glob_pattern.matches(item));
+        assert!(glob.is_match(item));
+    }
+
+    for item in expect_fail {
+        println!("- {} should not match", item);
+        assert!(!glob.is_match(item));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_match_types() -> Result<(), FError> {
+    let glob = Glob::new("*.txt")?;
+    let owned = String::from("a.txt");
+    assert!(glob.is_match("a.txt"));
+    assert!(glob.is_match(&owned));
+    assert!(glob.is_match(owned.clone()));
+    assert!(glob.is_match(Cow::Borrowed("a.txt")));
+    assert!(glob.is_match(Cow::<str>::Owned(owned)));
+    assert!(!glob.is_match(String::from("a.log")));
+    assert!(!glob.is_match(Cow::Borrowed("a/b.txt")));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", "a.txt", None)]
+#[case(
+    "*.txt",
+    "a.log",
+    Some("expected literal '.txt' at position 1 but found '.log'")
+)]
+#[case(
+    "a.txt",
+    "a.txt.bak",
+    Some("expected the end of the text at position 5 but found '.bak'")
+)]
+#[case(
+    "abc",
+    "ab",
+    Some("expected literal 'abc' at position 0 but found 'ab'")
+)]
+#[case(
+    "a?c",
+    "a/c",
+    Some("expected any character except '/' at position 1 but found '/'")
+)]
+#[case(
+    "x[0-9]",
+    "xa",
+    Some("expected a character matching [0-9] at position 1 but found 'a'")
+)]
+#[case(
+    "x[!0-9]",
+    "x5",
+    Some("expected a character matching [!0-9] at position 1 but found '5'")
+)]
+#[case(
+    "linux-{generic,aws}",
+    "linux-gen",
+    Some("expected literal 'generic' at position 6 but found 'gen'")
+)]
+#[case(
+    "*/b",
+    "a/c/b",
+    Some("expected literal '/b' at position 1 but found '/c'")
+)]
+fn test_explain_mismatch(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] expected: Option<&str>,
+) -> Result<(), FError> {
+    let glob = Glob::new(pattern)?;
+    assert_eq!(glob.explain_mismatch(text).as_deref(), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.log", GlobOptions::default().match_at_any_level(true))]
+#[case("a/**/b/**", GlobOptions::default().globstar(true))]
+#[case(
+    "a/**/b/**",
+    GlobOptions::default().globstar(true).max_globstar_depth(Some(1))
+)]
+#[case("x?{2}[a-c-]*{foo,bar,}", GlobOptions::default().counted_repetition(true))]
+#[case(
+    "*.log",
+    GlobOptions::default().separator('.').match_at_any_level(true)
+)]
+#[case(
+    "a.**.b",
+    GlobOptions::default().separator('.').globstar(true)
+)]
+#[case("*?{,x/}[!b]*", GlobOptions::default().literal_leading_dot(true))]
+#[case(
+    "**/.*/**",
+    GlobOptions::default().literal_leading_dot(true).globstar(true)
+)]
+#[case("*{.a,b}*", GlobOptions::default().literal_leading_dot(true))]
+#[case("[[:Letter:]0-9]*", GlobOptions::default())]
+#[case("?[![:alpha:]]*", GlobOptions::default())]
+#[case(
+    "[a-z&&[^aeiou]]*[!b&&[!/-]]",
+    GlobOptions::default().class_intersection(true)
+)]
+#[case(
+    "a/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true)
+)]
+#[case(
+    "**/b/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true)
+)]
+#[case(
+    "{a/,x}*.log",
+    GlobOptions::default().literal_leading_dot(true).match_at_any_level(true)
+)]
+#[case("{*.txt,READ[A-Z]?}", GlobOptions::default())]
+#[case("{**/*.rs,[ch]?}", GlobOptions::default().globstar(true))]
+#[case("x{a*,b?,?{2}c}y", GlobOptions::default().counted_repetition(true))]
+#[case("logs/*", GlobOptions::default().trailing_star_is_recursive(true))]
+#[case("a/*", GlobOptions::default().optional_trailing_separator(true))]
+#[case("v[0-9]?.[!.]?x", GlobOptions::default().optional_class(true))]
+#[case(
+    "*/a*",
+    GlobOptions::default()
+        .trailing_star_is_recursive(true)
+        .literal_leading_dot(true)
+)]
+#[case(
+    "*",
+    GlobOptions::default()
+        .literal_leading_dot(true)
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "*.b",
+    GlobOptions::default()
+        .literal_leading_dot(true)
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "a/*{,.b}",
+    GlobOptions::default()
+        .literal_leading_dot(true)
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "a/**",
+    GlobOptions::default()
+        .globstar(true)
+        .literal_leading_dot(true)
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "a/**",
+    GlobOptions::default()
+        .globstar(true)
+        .max_globstar_depth(Some(1))
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "a/*",
+    GlobOptions::default()
+        .trailing_star_is_recursive(true)
+        .literal_leading_dot(true)
+        .anchoring(Anchoring::Start)
+)]
+#[case(
+    "a/**",
+    GlobOptions::default()
+        .globstar(true)
+        .anchoring(Anchoring::Unanchored)
+)]
+fn test_explain_consistent(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    for text in &[
+        "",
+        "a",
+        "a.log",
+        "d/a.log",
+        "d/a.log/x",
+        "a/b",
+        "a/b/c",
+        "a/x/b/c",
+        "a/x/y/b/c/d",
+        "a/b/",
+        "xab",
+        "xabc",
+        "xab-foo",
+        "xab-bar",
+        "xabbar",
+        "xa/b",
+        "xabcfoox",
+        ".a",
+        "a/.b",
+        "a/.b/c",
+        ".a.log",
+        "d/.a.log",
+        "a/.log",
+        "x.b",
+        "xab.c",
+        "x.",
+    ] {
+        let explanation = glob.explain_mismatch(text);
+        println!("{} / {}: {:?}", pattern, text, explanation);
+        assert_eq!(explanation.is_none(), glob.is_match(text));
+        assert!(!explanation.is_some_and(|msg| msg.contains("does not match the pattern")));
+        assert_eq!(glob.matches_ignore_ascii_case(text), glob.is_match(text));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", &["a.txt", "A.TXT", "b.Txt"], &["a.log", "A.TXT/x", "a/b.txt"])]
+#[case("README.*", &["readme.md", "ReadMe.MD", "README.md"], &["readme", "READ.md"])]
+#[case("[A-C]?{x,Y}", &["a.x", "Cby", "bbX"], &["d.x", "a/x", "abz", "ab.x"])]
+#[case("[!a]x", &["bx", "BX", "1x"], &["ax", "Ax", "AX", "/x"])]
+#[case("Ä*", &["Äb", "ÄB"], &["äb"])]
+fn test_ignore_ascii_case(
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::new(pattern)?;
+    for item in expect_ok {
+        println!("- {} should match {}", item, pattern);
+        assert!(glob.matches_ignore_ascii_case(item));
+    }
+    for item in expect_fail {
+        println!("- {} should not match {}", item, pattern);
+        assert!(!glob.matches_ignore_ascii_case(item));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_explain_inline_flags() -> Result<(), FError> {
+    let glob = Glob::with_options("(?i)*.txt", &GlobOptions::default().inline_flags(true))?;
+    assert!(glob.ast().case_insensitive);
+    assert_eq!(glob.explain_mismatch("A.TXT"), None);
+    assert_eq!(
+        glob.explain_mismatch("A.LOG").as_deref(),
+        Some("expected literal '.txt' at position 1 but found '.LOG'")
+    );
+
+    let glob = Glob::with_options("a/**", &GlobOptions::default().globstar(true))?;
+    assert_eq!(
+        glob.explain_mismatch("a/b\nc").as_deref(),
+        Some("expected any character except a newline at position 3 but found '\n'")
+    );
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.rs", "a.rs b.rs", Some(4))]
+#[case("b*.rs", "a.rs b.rs", Some(9))]
+#[case("[0-9]", "v1.20", Some(2))]
+#[case("*.txt", "a.rs b.rs", None)]
+fn test_shortest_match(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] expected: Option<usize>,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(
+        pattern,
+        &GlobOptions::default().anchoring(Anchoring::Unanchored),
+    )?;
+    assert_eq!(glob.shortest_match(text), expected);
+    assert_eq!(glob.explain_mismatch(text).is_none(), expected.is_some());
+
+    let full = Glob::new(pattern)?;
+    assert_eq!(
+        full.shortest_match(text),
+        full.is_match(text).then_some(text.len())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_slash_ordinary_walk() -> Result<(), FError> {
+    let glob = Glob::with_options(
+        "release/*/v[!.]?",
+        &GlobOptions::default().treat_slash_as_ordinary(true),
+    )?;
+    for text in [
+        "release/a/b/v/1",
+        "release//v12",
+        "release/x/v.1",
+        "release/x/v1",
+    ] {
+        assert_eq!(glob.explain_mismatch(text).is_none(), glob.is_match(text));
+        assert_eq!(glob.matches_ignore_ascii_case(text), glob.is_match(text));
+    }
+    assert!(glob.is_match("release/a/b/v/1"));
+    assert_eq!(
+        glob.explain_mismatch("release/x/v1").as_deref(),
+        Some("expected any character at position 12 but found the end of the text")
+    );
+    Ok(())
+}
+
+/// A pattern compiled once by the `static_glob!` macro.
+fn source_glob() -> &'static Glob {
+    crate::static_glob!("src/**/*.rs", GlobOptions::default().globstar(true))
+}
+
+#[test]
+fn test_static_glob_macro() {
+    let glob = source_glob();
+    assert!(glob.is_match("src/main.rs"));
+    assert!(glob.is_match("src/a/b/lib.rs"));
+    assert!(!glob.is_match("tests/main.rs"));
+    assert!(std::ptr::eq(glob, source_glob()));
+    assert!(crate::static_glob!("*.txt").is_match("a.txt"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid glob pattern \"[a\"")]
+fn test_static_glob_macro_invalid() {
+    let _ = crate::static_glob!("[a").is_match("a");
+}
+
+#[rstest::rstest]
+#[case("a.txt", "a.txt", GlobOptions::default(), MatchKind::Exact)]
+#[case("*.txt", "a.txt", GlobOptions::default(), MatchKind::Wildcard)]
+#[case(r"a\?.txt", "a?.txt", GlobOptions::default(), MatchKind::Exact)]
+#[case("a.txt", "b.txt", GlobOptions::default(), MatchKind::None)]
+#[case("*.txt", "a.log", GlobOptions::default(), MatchKind::None)]
+#[case(
+    "a.txt",
+    "d/a.txt",
+    GlobOptions::default().match_at_any_level(true),
+    MatchKind::Wildcard
+)]
+#[case(
+    "a.txt",
+    "a.txt",
+    GlobOptions::default().match_at_any_level(true),
+    MatchKind::Wildcard
+)]
+#[case(
+    "foo/bar",
+    "foo/bar/",
+    GlobOptions::default().optional_trailing_separator(true),
+    MatchKind::Wildcard
+)]
+fn test_match_kind(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: MatchKind,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.match_kind(text), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("Foo.txt", "Foo.txt", GlobOptions::default(), CaseMatch::Exact)]
+#[case(
+    "Foo.txt",
+    "foo.txt",
+    GlobOptions::default(),
+    CaseMatch::CaseInsensitiveOnly
+)]
+#[case(
+    "foo.*",
+    "FOO.TXT",
+    GlobOptions::default(),
+    CaseMatch::CaseInsensitiveOnly
+)]
+#[case("Foo.txt", "foo.log", GlobOptions::default(), CaseMatch::None)]
+#[case(
+    "[a-c]*",
+    "Bar",
+    GlobOptions::default(),
+    CaseMatch::CaseInsensitiveOnly
+)]
+#[case(
+    "\u{c4}*",
+    "\u{e4}b",
+    GlobOptions::default(),
+    CaseMatch::CaseInsensitiveOnly
+)]
+#[case(
+    "(?i)Foo.txt",
+    "foo.txt",
+    GlobOptions::default().inline_flags(true),
+    CaseMatch::Exact
+)]
+#[case(
+    "k",
+    "\u{212a}",
+    GlobOptions::default(),
+    CaseMatch::CaseInsensitiveOnly
+)]
+#[case("k", "\u{212a}", GlobOptions::default().unicode(false), CaseMatch::None)]
+#[case(
+    "k",
+    "K",
+    GlobOptions::default().unicode(false),
+    CaseMatch::CaseInsensitiveOnly
+)]
+fn test_match_case_report(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: CaseMatch,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.match_case_report(text), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("abc*", GlobOptions::default(), &["", "a", "ab", "abc", "abcdef"], &["xy", "b", "abX/"])]
+#[case("src/*.rs", GlobOptions::default(), &["sr", "src/", "src/main.r", "src/a.rx"], &["lib/", "src/a/"])]
+#[case("{a,bc}d", GlobOptions::default(), &["b", "bc", "ad", "bcd"], &["bd", "ade", "c"])]
+#[case(
+    "?{2}x",
+    GlobOptions::default().counted_repetition(true),
+    &["a", "ab", "abx"],
+    &["abc", "a/"]
+)]
+#[case(
+    "**/foo",
+    GlobOptions::default().globstar(true),
+    &["di", "dir/", "dir/fo", "a/b/foo", "a/foox/"],
+    &["/", "a//foo"]
+)]
+#[case(
+    "a/**",
+    GlobOptions::default().globstar(true).max_globstar_depth(Some(2)),
+    &["a", "a/", "a/b", "a/b/", "a/b/c"],
+    &["b", "a/b/c/"]
+)]
+#[case(
+    "*.txt",
+    GlobOptions::default().literal_leading_dot(true),
+    &["a", "a.t", "a.txt"],
+    &[".", ".t"]
+)]
+#[case(
+    "{*,.a}/*.txt",
+    GlobOptions::default().literal_leading_dot(true),
+    &["b/", ".a", ".a/x.t", "b/c.txt"],
+    &[".b", "b/.", "b/.txt"]
+)]
+#[case(
+    "*.txt",
+    GlobOptions::default().match_at_any_level(true),
+    &["a", "x/y/", ".anything"],
+    &[]
+)]
+#[case(
+    "a",
+    GlobOptions::default().optional_trailing_separator(true),
+    &["", "a", "a/"],
+    &["b", "a//", "a/b"]
+)]
+#[case(
+    "*",
+    GlobOptions::default().optional_trailing_separator(true),
+    &["", "/", "abc", "abc/"],
+    &["//", "a/b"]
+)]
+#[case(
+    "a/**",
+    GlobOptions::default()
+        .globstar(true)
+        .collapse_globstar_edges(true)
+        .optional_trailing_separator(true),
+    &["a", "a/", "a/b/c", "a/b/c/"],
+    &["b", "ab"]
+)]
+fn test_matches_prefix_of(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    for partial in expect_ok {
+        assert!(glob.matches_prefix_of(partial), "{:?}", partial);
+    }
+    for partial in expect_fail {
+        assert!(!glob.matches_prefix_of(partial), "{:?}", partial);
+    }
+    for text in glob.sample_matches(20) {
+        for (idx, _) in text.char_indices() {
+            assert!(glob.matches_prefix_of(&text[..idx]), "{:?}", &text[..idx]);
+        }
+        assert!(glob.matches_prefix_of(&text));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_with_separator() -> Result<(), FError> {
+    let glob = Glob::with_separator(r"src\*.rs", '\\')?;
+    assert_eq!(
+        glob,
+        Glob::with_options(
+            r"src\*.rs",
+            &GlobOptions::default().separator('\\').escape_char(None)
+        )?
+    );
+    assert!(glob.is_match(r"src\main.rs"));
+    assert!(glob.is_match(r"src\a/b.rs"));
+    assert!(!glob.is_match(r"src\a\b.rs"));
+    assert!(!glob.is_match("src/main.rs"));
+    let percent = Glob::with_separator(r"a\*%b", '%')?;
+    assert!(percent.is_match("a*%b"));
+    assert!(!percent.is_match("ax%b"));
+    assert!(matches!(
+        Glob::with_separator("a", '*'),
+        Err(FError::ConflictingOptions(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_match_kind_best() -> Result<(), FError> {
+    let rules = [Glob::new("*")?, Glob::new("a.txt")?, Glob::new("*.txt")?];
+    let best = |text: &str| rules.iter().map(|glob| glob.match_kind(text)).max();
+    assert_eq!(best("a.txt"), Some(MatchKind::Exact));
+    assert_eq!(best("b.txt"), Some(MatchKind::Wildcard));
+    assert_eq!(best("b/c"), Some(MatchKind::None));
+    Ok(())
+}
+
+#[test]
+fn test_eq_hash() -> Result<(), FError> {
+    let hash = |glob: &Glob| {
+        let mut hasher = DefaultHasher::new();
+        glob.hash(&mut hasher);
+        hasher.finish()
+    };
+    let (first, second) = (Glob::new("*.txt")?, Glob::new("*.txt")?);
+    assert_eq!(first, second);
+    assert_eq!(hash(&first), hash(&second));
+    assert_eq!(Glob::new("a.txt")?, Glob::new(r"a\.txt")?);
+    assert_ne!(Glob::new("a")?, Glob::new("{a}")?);
+    assert_ne!(
+        first,
+        Glob::with_options("*.txt", &GlobOptions::default().match_at_any_level(true))?
+    );
+    let flagged = GlobOptions::default().inline_flags(true);
+    let (folded, ascii) = (
+        Glob::with_options("(?i)k", &flagged)?,
+        Glob::with_options("(?i)k", &flagged.unicode(false))?,
+    );
+    assert_eq!(ascii.as_regex_str(), folded.as_regex_str());
+    assert_ne!(folded, ascii);
+    assert_ne!(hash(&folded), hash(&ascii));
+    #[cfg(feature = "unicode-normalization")]
+    {
+        use crate::options::NormalizationForm;
+
+        let nfc = Glob::with_options(
+            "*.txt",
+            &GlobOptions::default().normalize(Some(NormalizationForm::Nfc)),
+        )?;
+        assert_eq!(nfc.as_regex_str(), first.as_regex_str());
+        assert_ne!(first, nfc);
+        assert_ne!(hash(&first), hash(&nfc));
+    }
+
+    // The regular expression's caches do not affect the hash.
+    #[allow(clippy::mutable_key_type)]
+    let set: HashSet<Glob> = ["*.txt", "c", "*.txt", r"\c"]
+        .iter()
+        .map(|pattern| Glob::new(pattern))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(set.len(), 2);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(
+    "a?b",
+    &[&b"a\xffb"[..], &b"a\x80b"[..], "aäb".as_bytes(), &b"a\xc3b"[..]],
+    &[&b"a\xff\xffb"[..], &b"a/b"[..]]
+)]
+#[case(
+    "*.txt",
+    &[&b"\xfe\xff.txt"[..], &b".txt"[..], &b"a\xf5\x80.txt"[..], &b"\xc3.txt"[..]],
+    &[&b"a/\xff.txt"[..], &b"caf\xe9.log"[..]]
+)]
+#[case(
+    "caf?.txt",
+    &[&b"caf\xe9.txt"[..], "café.txt".as_bytes()],
+    &[&b"caf\xe9\xe9.txt"[..], &b"cafe\xe9.txt"[..]]
+)]
+#[case("*.txt", &[&b"caf\xe9.txt"[..]], &[&b"caf\xe9/x.txt"[..]])]
+#[case(
+    "[!a]?{2}",
+    &[&b"\xff\xfe\xfd"[..], "äöü".as_bytes()],
+    &[&b"a\xff\xfe"[..], &b"\xff/\xfe"[..]]
+)]
+#[case("[a-z]b", &[&b"ab"[..]], &[&b"\xffb"[..]])]
+#[case(r"a/**/b\\xff", &[&b"a/\xff/b\\xff"[..]], &[&b"a/\xff/b\xff"[..]])]
+fn test_match_bytes(
+    #[case] pattern: &str,
+    #[case] expect_ok: &[&[u8]],
+    #[case] expect_fail: &[&[u8]],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(
+        pattern,
+        &GlobOptions::default()
+            .globstar(true)
+            .counted_repetition(true),
+    )?;
+    for bytes in expect_ok {
+        assert!(glob.is_match_bytes(bytes));
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            assert!(glob.is_match(text));
+        }
+    }
+    for bytes in expect_fail {
+        assert!(!glob.is_match_bytes(bytes));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("{a,b}.{c,h}", GlobOptions::default(), Some(&["a.c", "a.h", "b.c", "b.h"][..]))]
+#[case(r"x{1,2}\*{y,z}", GlobOptions::default(), Some(&["x1*y", "x1*z", "x2*y", "x2*z"][..]))]
+#[case("{a,b,a}", GlobOptions::default(), Some(&["a", "b"][..]))]
+#[case("{,s}", GlobOptions::default(), Some(&["", "s"][..]))]
+#[case("plain", GlobOptions::default(), Some(&["plain"][..]))]
+#[case("{a,b}*", GlobOptions::default(), None)]
+#[case("{a,b}[cd]", GlobOptions::default(), None)]
+#[case("{a,b}", GlobOptions::default().match_at_any_level(true), None)]
+#[case(
+    "foo/{bar,baz}",
+    GlobOptions::default().optional_trailing_separator(true),
+    Some(&["foo/bar", "foo/bar/", "foo/baz", "foo/baz/"][..])
+)]
+fn test_finite_matches(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: Option<&[&str]>,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let matches = glob.finite_matches();
+    assert_eq!(
+        matches
+            .as_ref()
+            .map(|texts| texts.iter().map(String::as_str).collect::<Vec<_>>())
+            .as_deref(),
+        expected
+    );
+    for text in matches.unwrap_or_default() {
+        assert!(glob.is_match(&text));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a/*/c", GlobOptions::default(), 3, &["a/b/c"])]
+#[case("a/**/c", GlobOptions::from(Preset::Path), 2, &["a/c", "a/b/c", "a/b/d/c"])]
+#[case("*.txt", GlobOptions::default(), 1, &["a.txt"])]
+#[case("*.txt", GlobOptions::default().match_at_any_level(true), 1, &["a.txt", "b/a.txt"])]
+#[case("/etc/*", GlobOptions::default(), 3, &["/etc/passwd"])]
+#[case("/etc/*", GlobOptions::default().root_relative(true), 2, &["etc/passwd"])]
+#[case("{a/b/c,d/e}/f", GlobOptions::default(), 3, &["d/e/f", "a/b/c/f"])]
+#[case("{a,b/}", GlobOptions::default(), 1, &["a", "b/"])]
+#[case("foo/**", GlobOptions::from(Preset::Path), 2, &["foo/a", "foo/a/b"])]
+#[case(
+    "foo/**",
+    GlobOptions::from(Preset::Path).collapse_globstar_edges(true),
+    1,
+    &["foo", "foo/a"]
+)]
+#[case("a:*", GlobOptions::default().separator(':'), 2, &["a:b"])]
+fn test_required_min_components(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: usize,
+    #[case] matching: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.required_min_components(), expected);
+    for text in matching {
+        assert!(glob.is_match(text), "{:?}", text);
+        assert!(
+            text.split(options.separator).count() >= expected,
+            "{:?}",
+            text
+        );
+    }
+    assert!(matching
+        .iter()
+        .any(|text| text.split(options.separator).count() == expected));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.{c,h}", FeatureSet::default(), &[])]
+#[case("a**b/**", FeatureSet { globstar: true, ..FeatureSet::default() }, &["globstar"])]
+#[case("**/a{**,b}", FeatureSet { globstar: true, ..FeatureSet::default() }, &["globstar"])]
+#[case("a**/b", FeatureSet::default(), &[])]
+#[case(r"\**/b", FeatureSet::default(), &[])]
+#[case(
+    "x?{3}y",
+    FeatureSet { counted_repetition: true, ..FeatureSet::default() },
+    &["counted_repetition"]
+)]
+#[case("x?{a,b}", FeatureSet::default(), &[])]
+#[case(
+    "{a?{2},?{b,c}}",
+    FeatureSet { counted_repetition: true, nested_alternation: true, ..FeatureSet::default() },
+    &["counted_repetition", "nested_alternation"]
+)]
+#[case(
+    "[a-z&&[^aeiou]]",
+    FeatureSet { class_intersection: true, ..FeatureSet::default() },
+    &["class_intersection"]
+)]
+#[case("[&&[a]]", FeatureSet::default(), &[])]
+#[case("[a&&b]", FeatureSet::default(), &[])]
+#[case("[[:alpha:]_]", FeatureSet { named_class: true, ..FeatureSet::default() }, &[])]
+#[case("[[:]:]]", FeatureSet { named_class: true, ..FeatureSet::default() }, &[])]
+#[case(
+    "(?i)readme",
+    FeatureSet { inline_flags: true, ..FeatureSet::default() },
+    &["inline_flags"]
+)]
+#[case("a(?i)", FeatureSet::default(), &[])]
+#[case(
+    "img.{png,jp{e,}g}",
+    FeatureSet { nested_alternation: true, ..FeatureSet::default() },
+    &["nested_alternation"]
+)]
+#[case(r"{a,\{b}", FeatureSet::default(), &[])]
+#[case("{a,[{]b}", FeatureSet::default(), &[])]
+fn test_required_features(
+    #[case] pattern: &str,
+    #[case] expected: FeatureSet,
+    #[case] unsupported: &[&str],
+) -> Result<(), FError> {
+    let features = Glob::required_features(pattern)?;
+    assert_eq!(features, expected);
+    assert_eq!(
+        features.unsupported_by(&GlobOptions::default()),
+        unsupported
+    );
+    let needed = GlobOptions::default()
+        .globstar(features.globstar)
+        .counted_repetition(features.counted_repetition)
+        .class_intersection(features.class_intersection)
+        .inline_flags(features.inline_flags);
+    if !features.nested_alternation {
+        assert_eq!(features.unsupported_by(&needed), Vec::<&str>::new());
+        Glob::with_options(pattern, &needed)?;
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a/[b", FError::UnclosedClass(2))]
+#[case("[[:alpha]", FError::UnclosedClass(0))]
+#[case("a{b,{c}", FError::UnclosedAlternation(1))]
+fn test_required_features_errors(#[case] pattern: &str, #[case] expected: FError) {
+    assert_eq!(
+        Glob::required_features(pattern).unwrap_err().to_string(),
+        expected.to_string()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_is_match_path_non_utf8() -> Result<(), FError> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    let name = Path::new(OsStr::from_bytes(b"src/caf\xe9.txt"));
+    assert!(Glob::new("src/*.txt")?.is_match_path(name));
+    assert!(Glob::new("src/caf?.txt")?.is_match_path(name));
+    assert!(!Glob::new("src/caf\u{fffd}.txt")?.is_match_path(name));
+    assert!(Glob::new("src/caf\u{fffd}.txt")?.is_match(name.to_string_lossy()));
+    assert!(!Glob::new("src/*.rs")?.is_match_path(name));
+    assert!(!Glob::new("*.txt")?.is_match_path(name));
+    assert!(Glob::new("src/main.rs")?.is_match_path(Path::new("src/main.rs")));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("[!\u{0}-.0-\u{10ffff}]", GlobOptions::default(), true)]
+#[case("[!\u{0}-.0-\u{10ffff}]", GlobOptions::default().classes_match_separator(true), false)]
+#[case("[!\u{0}-.0-\u{10ffff}]", GlobOptions::from(Preset::PlainText), false)]
+#[case("a/[.-/]", GlobOptions::default().literal_leading_dot(true), true)]
+#[case("a/[.-/]", GlobOptions::default(), false)]
+#[case("a/x[.-/]", GlobOptions::default().literal_leading_dot(true), false)]
+#[case("a/*[.]", GlobOptions::default().literal_leading_dot(true), false)]
+#[case("[a-f&&[x-z]]", GlobOptions::default().class_intersection(true), true)]
+#[case("[a-f&&[!a-f]]", GlobOptions::default().class_intersection(true), true)]
+#[case("[a-f&&[c-z]]", GlobOptions::default().class_intersection(true), false)]
+#[case("[!\u{0}-\u{10ffff}]", GlobOptions::default(), true)]
+#[case("{a,[!\u{0}-.0-\u{10ffff}]}", GlobOptions::default(), false)]
+#[case(
+    "x{[!\u{0}-.0-\u{10ffff}],b[!\u{0}-.0-\u{10ffff}]}y",
+    GlobOptions::default(),
+    true
+)]
+#[case("[[:digit:]&&[[:alpha:]]]", GlobOptions::default().class_intersection(true), false)]
+#[case("*.{c,h}", GlobOptions::default(), false)]
+fn test_is_unsatisfiable(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: bool,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.is_unsatisfiable(), expected);
+    if expected {
+        assert_eq!(glob.sample_matches(10), Vec::<String>::new());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_is_unsatisfiable_empty_class() {
+    assert!(matches!(Glob::new("[/]"), Err(FError::EmptyClass(0))));
+    assert!(!Glob::new("[!/]").unwrap().is_unsatisfiable());
+}
+
+#[rstest::rstest]
+#[case("a*b", GlobOptions::default(), 5, &["ab", "axb", "axyzb"])]
+#[case("{x,y}?[0-9]", GlobOptions::default(), 3, &["xx0", "xx9", "yx0"])]
+#[case("**/*.rs", GlobOptions::from(Preset::Path), 4, &[".rs", "x.rs", "xyz.rs", "d/.rs"])]
+#[case(
+    "**/*.rs",
+    GlobOptions::from(Preset::Path).literal_leading_dot(true),
+    4,
+    &["x.rs", "xyz.rs", "d/x.rs", "d/xyz.rs"]
+)]
+#[case("[!a-z]?{2}", GlobOptions::default().counted_repetition(true), 9, &["Zxx", "0xx"])]
+#[case("[[:Lu:][:Nd:]]", GlobOptions::default(), 3, &["Z", "0"])]
+#[case("[[:Lm:]]", GlobOptions::default(), 2, &[])]
+#[case("/etc/{passwd,group}", GlobOptions::default().root_relative(true), 9, &["etc/passwd", "etc/group"])]
+fn test_sample_matches(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] count: usize,
+    #[case] expected: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let samples = glob.sample_matches(count);
+    println!("{} -> {:?}", pattern, samples);
+    assert_eq!(samples, expected);
+    assert_eq!(glob.sample_matches(count), samples);
+    for text in &samples {
+        assert!(glob.is_match(text));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.rs", GlobOptions::default(), 4)]
+#[case("src/*.{c,h}", GlobOptions::default(), 6)]
+#[case("?[0-9]", GlobOptions::default(), 5)]
+#[case("**/*.rs", GlobOptions::from(Preset::Path), 5)]
+#[case("logs/**", GlobOptions::from(Preset::Path), 3)]
+#[case("README", GlobOptions::default().match_at_any_level(true), 4)]
+#[case("*", GlobOptions::default().anchoring(Anchoring::Unanchored), 0)]
+fn test_counter_examples(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] count: usize,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let misses = glob.counter_examples(count);
+    println!("{} -> {:?}", pattern, misses);
+    assert_eq!(misses.len(), count);
+    assert_eq!(glob.counter_examples(count), misses);
+    for text in &misses {
+        assert!(!glob.is_match(text));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_counter_examples_extension() -> Result<(), FError> {
+    let glob = Glob::new("*.rs")?;
+    assert!(glob.counter_examples(2).contains(&".rq".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn test_sample_matches_many() -> Result<(), FError> {
+    let glob = Glob::new("{a,b,c}{d,e,f}*[gh]")?;
+    let samples = glob.sample_matches(100);
+    assert_eq!(samples.len(), 54);
+    assert!(samples.iter().all(|text| glob.is_match(text)));
+    assert_eq!(glob.sample_matches(5).len(), 5);
+    assert_eq!(glob.sample_matches(0), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_finite_matches_limit() -> Result<(), FError> {
+    let digits = "{0,1,2,3,4,5,6,7,8,9}";
+    let glob = Glob::new(&digits.repeat(4))?;
+    assert_eq!(glob.finite_matches().map(|texts| texts.len()), Some(10_000));
+    let glob = Glob::new(&digits.repeat(5))?;
+    assert_eq!(glob.finite_matches(), None);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("a/*/c", GlobOptions::default(), &[
+    SegmentKind::Literal("a".to_owned()),
+    SegmentKind::Wildcard,
+    SegmentKind::Literal("c".to_owned()),
+][..])]
+#[case("a/**/c.txt", GlobOptions::default().globstar(true), &[
+    SegmentKind::Literal("a".to_owned()),
+    SegmentKind::Globstar,
+    SegmentKind::Literal("c.txt".to_owned()),
+][..])]
+#[case("/src/{a,b}/x\\*/", GlobOptions::default(), &[
+    SegmentKind::Literal("src".to_owned()),
+    SegmentKind::Wildcard,
+    SegmentKind::Literal("x*".to_owned()),
+][..])]
+#[case("docs/**", GlobOptions::default().globstar(true), &[
+    SegmentKind::Literal("docs".to_owned()),
+    SegmentKind::Globstar,
+][..])]
+#[case("a:b*", GlobOptions::default().separator(':'), &[
+    SegmentKind::Literal("a".to_owned()),
+    SegmentKind::Wildcard,
+][..])]
+fn test_segments(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &[SegmentKind],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.segments(), expected);
+    assert_eq!(glob.num_segments(), expected.len());
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*.txt", GlobOptions::default(), false)]
+#[case("a/*.txt", GlobOptions::default(), true)]
+#[case("**/x", GlobOptions::default().globstar(true), true)]
+#[case("src/**", GlobOptions::default().globstar(true), true)]
+#[case("{a,b/c}", GlobOptions::default(), true)]
+#[case(r"a\*", GlobOptions::default(), false)]
+#[case("a:b", GlobOptions::default().separator(':'), true)]
+#[case("a/b", GlobOptions::default().separator(':'), false)]
+#[case("*.txt", GlobOptions::default().match_at_any_level(true), false)]
+#[case("logs*", GlobOptions::default().trailing_star_is_recursive(true), true)]
+fn test_is_multi_segment(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: bool,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.is_multi_segment(), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(r"foo\*bar", GlobOptions::default(), Some("foo*bar"))]
+#[case(r"a\[b\]\{c,d\}\?\\", GlobOptions::default(), Some(r"a[b]{c,d}?\"))]
+#[case(r"tab\there", GlobOptions::default(), Some("tab\there"))]
+#[case("plain/path.txt", GlobOptions::default(), Some("plain/path.txt"))]
+#[case("", GlobOptions::default(), Some(""))]
+#[case("a{}b", GlobOptions::default(), Some("a{}b"))]
+#[case("/foo", GlobOptions::default().root_relative(true), Some("foo"))]
+#[case("foo*bar", GlobOptions::default(), None)]
+#[case("foo?", GlobOptions::default(), None)]
+#[case("foo[b]ar", GlobOptions::default(), None)]
+#[case("foo{bar}", GlobOptions::default(), None)]
+#[case("foo", GlobOptions::default().match_at_any_level(true), None)]
+#[case("foo", GlobOptions::default().root_relative(true), None)]
+#[case("foo", GlobOptions::default().anchoring(Anchoring::Unanchored), None)]
+#[case("(?i)foo", GlobOptions::default().inline_flags(true), None)]
+#[case(
+    "foo/bar",
+    GlobOptions::default().optional_trailing_separator(true),
+    None
+)]
+fn test_unescape_literal(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: Option<&str>,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.unescape_literal().as_deref(), expected);
+    assert_eq!(glob.is_literal(), expected.is_some());
+    if let Some(text) = expected {
+        assert!(glob.is_match(text));
+        assert_eq!(
+            Glob::literal(text)?.unescape_literal().as_deref(),
+            Some(text)
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_components() -> Result<(), FError> {
+    let glob = Glob::with_options(
+        "(?i)Program Files/*/bin/*.EXE",
+        &GlobOptions::default().inline_flags(true),
+    )?;
+    let path: Vec<OsString> = ["program files", "Vendor", "BIN", "Tool.exe"]
+        .iter()
+        .map(OsString::from)
+        .collect();
+    assert!(glob.matches_components(&path));
+    assert!(glob.matches_components(path.iter().map(OsString::as_os_str)));
+    assert!(glob.matches_components(["PROGRAM FILES", "x", "Bin", "a.Exe"]));
+    assert!(!glob.matches_components(["Program Files", "bin", "a.exe"]));
+    assert!(!glob.matches_components(["Program Files", "x", "y", "bin", "a.exe"]));
+    assert!(!glob.matches_components(Vec::<&OsStr>::new()));
+
+    let glob = Glob::new("src/**")?;
+    assert!(glob.matches_components(["src", ""]));
+    assert!(!glob.matches_components(["src"]));
+    Ok(())
+}
+
+#[test]
+fn test_match_lines_numbered() -> Result<(), Box<dyn Error>> {
+    let glob = Glob::new("*error*")?;
+    let input = "starting\nan error here\r\nall fine\n\nanother error\nerror";
+    let found = glob
+        .match_lines_numbered(Cursor::new(input))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        found,
+        [
+            (2, "an error here".to_owned()),
+            (5, "another error".to_owned()),
+            (6, "error".to_owned()),
+        ]
+    );
+
+    let mut lines = glob.match_lines_numbered(Cursor::new(&b"error\n\xff error\nerror"[..]));
+    assert_eq!(lines.next().transpose()?, Some((1, "error".to_owned())));
+    assert!(matches!(lines.next(), Some(Err(_))));
+    assert_eq!(lines.next().transpose()?, Some((3, "error".to_owned())));
+    assert!(lines.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_matches_relative() -> Result<(), FError> {
+    let base = Path::new("/project");
+    let glob = Glob::new("src/*.rs")?;
+    assert!(glob.matches_relative(base, Path::new("/project/src/main.rs")));
+    assert!(glob.matches_relative(Path::new("/project/"), Path::new("/project/src/lib.rs")));
+    assert!(glob.matches_relative(base, Path::new("/project/./src/lib.rs")));
+    assert!(!glob.matches_relative(base, Path::new("/project/src/main.c")));
+    assert!(!glob.matches_relative(base, Path::new("/project/lib/src/main.rs")));
+    assert!(!glob.matches_relative(base, Path::new("/other/src/main.rs")));
+    assert!(!glob.matches_relative(base, Path::new("/project2/src/main.rs")));
+    assert!(!glob.matches_relative(base, Path::new("src/main.rs")));
+
+    let glob = Glob::with_options("*.log", &GlobOptions::default().separator(':'))?;
+    assert!(glob.matches_relative(base, Path::new("/project/a.log")));
+    assert!(!glob.matches_relative(base, Path::new("/project/logs/a.log")));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_components_lossy() -> Result<(), FError> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let bad = OsStr::from_bytes(b"a\xffb");
+    assert!(Glob::new("dir/*")?.matches_components([OsStr::new("dir"), bad]));
+    assert!(Glob::new("dir/a?b")?.matches_components([OsStr::new("dir"), bad]));
+    assert!(Glob::new("dir/a\u{fffd}b")?.matches_components([OsStr::new("dir"), bad]));
+    assert!(!Glob::new("dir/a??b")?.matches_components([OsStr::new("dir"), bad]));
+    Ok(())
+}
+
+#[test]
+fn test_classes_match_separator_walk() -> Result<(), FError> {
+    let glob = Glob::with_options(
+        "a[/_]b[!x]c",
+        &GlobOptions::default().classes_match_separator(true),
+    )?;
+    for text in ["a/b/c", "a_b/c", "a/bxc", "a-b/c", "a/b/c/"] {
+        assert_eq!(glob.explain_mismatch(text).is_none(), glob.is_match(text));
+        assert_eq!(glob.matches_ignore_ascii_case(text), glob.is_match(text));
+    }
+    assert!(glob.is_match("a/b/c"));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(
+    "api/{get,post,put}/*",
+    GlobOptions::default(),
+    "api/get/users",
+    Some(0)
+)]
+#[case(
+    "api/{get,post,put}/*",
+    GlobOptions::default(),
+    "api/post/users",
+    Some(1)
+)]
+#[case(
+    "api/{get,post,put}/*",
+    GlobOptions::default(),
+    "api/put/users",
+    Some(2)
+)]
+#[case("api/{get,post,put}/*", GlobOptions::default(), "api/head/users", None)]
+#[case("{*.tar,*.tar.gz}", GlobOptions::default(), "a.tar.gz", Some(1))]
+#[case("{*,a*}", GlobOptions::default(), "abc", Some(0))]
+#[case("{a,b}/{c,d}", GlobOptions::default(), "b/c", Some(1))]
+#[case("src/*.rs", GlobOptions::default(), "src/main.rs", None)]
+#[case(
+    "{src,.config}/{*.rs,a}",
+    GlobOptions::default().literal_leading_dot(true),
+    ".config/a",
+    Some(1)
+)]
+#[case(
+    "{x,b}*",
+    GlobOptions::default().anchoring(Anchoring::Unanchored),
+    "abc",
+    Some(1)
+)]
+#[case(
+    "{alpha,beta}",
+    GlobOptions::default().compact_alternations(true),
+    "beta",
+    Some(1)
+)]
+fn test_matched_branch(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] text: &str,
+    #[case] expected: Option<usize>,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    assert_eq!(glob.matched_branch(text), expected);
+    if expected.is_some() {
+        assert!(glob.is_match(text));
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("ERROR*", GlobOptions::default(), &["ERROR: boom", "ERROR"], &["WARN: ERROR", "ERR"])]
+#[case(
+    "git {push,pull}",
+    GlobOptions::default(),
+    &["git push origin", "git pull"],
+    &["git status", "sudo git push"]
+)]
+#[case(
+    "b?d",
+    GlobOptions::default().anchoring(Anchoring::Unanchored),
+    &["bcd", "bcde"],
+    &["abcd", "bd"]
+)]
+#[case(
+    "ERROR*",
+    GlobOptions::default().anchoring(Anchoring::Start),
+    &["ERROR: boom"],
+    &["WARN: ERROR"]
+)]
+fn test_matches_prefix(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] matching: &[&str],
+    #[case] mismatching: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let start = Glob::with_options(pattern, &options.anchoring(Anchoring::Start))?;
+    for text in matching {
+        assert!(glob.matches_prefix(text));
+        assert!(start.is_match(text));
+        assert_eq!(start.explain_mismatch(text), None);
+    }
+    for text in mismatching {
+        assert!(!glob.matches_prefix(text));
+        assert!(!start.is_match(text));
+        assert!(start.explain_mismatch(text).is_some());
+    }
+    Ok(())
+}
+
+/// A function that only knows about regular expressions.
+fn count_matches<R: AsRef<Regex>>(re: R, texts: &[&str]) -> usize {
+    texts
+        .iter()
+        .filter(|text| re.as_ref().is_match(text))
+        .count()
+}
+
+#[test]
+fn test_regex_conversions() -> Result<(), FError> {
+    let glob = Glob::new("*.{c,h}")?;
+    let texts = ["a.c", "b.h", "c.rs", "d/e.c"];
+    assert_eq!(count_matches(&glob, &texts), 2);
+    assert_eq!(count_matches(glob.clone(), &texts), 2);
+
+    let shared = glob.clone();
+    let re = Regex::from(glob);
+    assert_eq!(re.as_str(), shared.as_regex_str());
+    let unshared: Regex = shared.into();
+    assert_eq!(unshared.as_str(), re.as_str());
+    assert!(unshared.is_match("main.c"));
+    Ok(())
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[rstest::rstest]
+#[case("caf\u{e9}/*.txt", "caf\u{e9}/a.txt", "cafe\u{301}/a.txt")]
+#[case("cafe\u{301}/*.txt", "caf\u{e9}/a.txt", "cafe\u{301}/a.txt")]
+#[case("r\u{e9}sum\u{e9}?", "r\u{e9}sum\u{e9}s", "re\u{301}sume\u{301}s")]
+fn test_normalize(
+    #[case] pattern: &str,
+    #[case] composed: &str,
+    #[case] decomposed: &str,
+) -> Result<(), FError> {
+    use crate::options::NormalizationForm;
+
+    let plain = Glob::new(pattern)?;
+    assert_ne!(plain.is_match(composed), plain.is_match(decomposed));
+
+    for form in [
+        NormalizationForm::Nfc,
+        NormalizationForm::Nfd,
+        NormalizationForm::Nfkc,
+        NormalizationForm::Nfkd,
+    ] {
+        let glob = Glob::with_options(pattern, &GlobOptions::default().normalize(Some(form)))?;
+        assert_eq!(glob.pattern(), pattern);
+        assert!(glob.is_match(composed));
+        assert!(glob.is_match(decomposed));
+        assert!(glob.matches_prefix(decomposed));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[rstest::rstest]
+#[case("caf\u{e9}", "cafe\u{301}", true)]
+#[case("caf\u{e9}", "cafe", false)]
+#[case("*\u{e9}.txt", "e\u{301}e\u{301}.txt", true)]
+#[case("a/e\u{301}*", "a/\u{e9}t\u{e9}", true)]
+fn test_normalize_consistent(
+    #[case] pattern: &str,
+    #[case] text: &str,
+    #[case] expected: bool,
+) -> Result<(), FError> {
+    use crate::options::NormalizationForm;
+
+    let glob = Glob::with_options(
+        pattern,
+        &GlobOptions::default().normalize(Some(NormalizationForm::Nfc)),
+    )?;
+    assert_eq!(glob.is_match(text), expected);
+    assert_eq!(glob.explain_mismatch(text).is_none(), expected);
+    assert_eq!(glob.matches_components(text.split('/')), expected);
+    assert_eq!(glob.is_match_bytes(text.as_bytes()), expected);
+    assert_eq!(glob.matches_ignore_ascii_case(text), expected);
+    assert_eq!(glob.shortest_match(text).is_some(), expected);
+    assert_eq!(
+        glob.match_case_report(text),
+        if expected {
+            CaseMatch::Exact
+        } else {
+            CaseMatch::None
+        }
+    );
+    assert!(glob.matches_prefix_of(text) || !expected);
+    assert_eq!(
+        glob.match_lines_numbered(Cursor::new(format!("x\n{}\n", text)))
+            .map(|line| line.map(|(idx, _)| idx))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        if expected { vec![2] } else { Vec::new() }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_is_match_cstr() -> Result<(), FError> {
+    let glob = Glob::new("*.txt")?;
+    assert!(glob.is_match_cstr(c"notes.txt"));
+    assert!(!glob.is_match_cstr(c"dir/notes.txt"));
+    assert!(!glob.is_match_cstr(c""));
+
+    let bad = CStr::from_bytes_until_nul(b"a\xffb.txt\0b.rs\0").unwrap();
+    assert!(glob.is_match_cstr(bad));
+    assert!(!Glob::new("a?b.rs")?.is_match_cstr(bad));
+    assert!(Glob::new("a?b.*")?.is_match_cstr(bad));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("*", GlobOptions::default(), &["", "a", ".a", "a b", "a\nb"], &["/", "a/", "/a", "a/b"])]
+#[case("?", GlobOptions::default(), &["a", ".", "\u{e9}", "\n"], &["", "/", "ab", "a/"])]
+#[case("??", GlobOptions::default(), &["ab", ".a"], &["a", "abc", "a/", "/a"])]
+#[case("*?", GlobOptions::default(), &["a", "ab", "a\nb"], &["", "/", "a/", "a/b"])]
+#[case("*/*", GlobOptions::default(), &["a/b", "/", "a/", "/a"], &["", "a", "a/b/c", "//"])]
+#[case("**", GlobOptions::default(), &["", "a", "ab"], &["/", "a/b"])]
+#[case(
+    "**",
+    GlobOptions::default().globstar(true),
+    &["", "a", "a/b", "a/b/c", "/", "a/", "//"],
+    &["a\nb", "a/b\nc"]
+)]
+#[case(
+    "(?s)**",
+    GlobOptions::default().globstar(true).inline_flags(true),
+    &["", "a/b", "a\nb", "a/b\nc"],
+    &[]
+)]
+#[case(
+    "**/*",
+    GlobOptions::default().globstar(true),
+    &["", "a", "a/b", "a/b/c", "a/", "a\nb"],
+    &["/", "/a", "//"]
+)]
+#[case(
+    "*/**",
+    GlobOptions::default().globstar(true),
+    &["a/", "a/b", "a/b/c", "/", "/a"],
+    &["", "a", "a\nb"]
+)]
+fn test_wildcards_only(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expect_ok: &[&str],
+    #[case] expect_fail: &[&str],
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    println!("{} -> {}", pattern, glob.regex());
+    for (items, expected) in [(expect_ok, true), (expect_fail, false)] {
+        for item in items {
+            println!("- {:?} should match: {}", item, expected);
+            assert_eq!(glob.is_match(item), expected);
+            assert_eq!(glob.is_match_bytes(item.as_bytes()), expected);
+            assert_eq!(glob.explain_mismatch(item).is_none(), expected);
+            assert_eq!(glob.matches_ignore_ascii_case(item), expected);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_matches_any_of() -> Result<(), FError> {
+    let glob = Glob::new("*.{jpg,jpeg}")?;
+    assert_eq!(
+        glob.matches_any_of(["photo.png", "photo.gif", "photo.jpeg", "photo.jpg"]),
+        Some("photo.jpeg")
+    );
+    assert_eq!(glob.matches_any_of(["photo.png", "dir/photo.jpg"]), None);
+    assert_eq!(glob.matches_any_of([]), None);
+
+    let names = ["a.txt".to_owned(), "b.jpg".to_owned()];
+    assert_eq!(
+        glob.matches_any_of(names.iter().map(String::as_str)),
+        Some("b.jpg")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_simplify_prefix() -> Result<(), FError> {
+    let options = GlobOptions::default()
+        .treat_as_prefix(true)
+        .trailing_star_is_recursive(true);
+    let glob = Glob::with_options("[ab]", &options)?;
+    let simple_glob = Glob::with_options(&glob.simplify(), &options)?;
+    for text in ["a", "bx", "abx", "abx/", "ab/x", "c"] {
+        assert_eq!(
+            simple_glob.is_match(text),
+            glob.is_match(text),
+            "{:?}",
+            text
+        );
+    }
+    assert!(!simple_glob.is_match("abx/"));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("{a}", GlobOptions::default(), "a")]
+#[case("[a]", GlobOptions::default(), "a")]
+#[case("x{a}y[b]z", GlobOptions::default(), "xaybz")]
+#[case("[aa-a].txt", GlobOptions::default(), "a.txt")]
+#[case("{[a],b}", GlobOptions::default(), "{a,b}")]
+#[case("{b,a,b}", GlobOptions::default(), "{a,b}")]
+#[case("{a,a}.txt", GlobOptions::default(), "a.txt")]
+#[case("a**b", GlobOptions::default(), "a*b")]
+#[case("a***b", GlobOptions::default().globstar(true), "a*b")]
+#[case("*{*}.c", GlobOptions::default(), "*.c")]
+#[case("**/**/x", GlobOptions::default().globstar(true), "**/x")]
+#[case("x/**/**", GlobOptions::default().globstar(true), "x/**")]
+#[case(
+    "x/**/**",
+    GlobOptions::default().globstar(true).collapse_globstar_edges(true),
+    "x/**/**"
+)]
+#[case("a**", GlobOptions::default().trailing_star_is_recursive(true), "a*")]
+#[case("a{}b", GlobOptions::default().empty_brace(EmptyBrace::Empty), "ab")]
+#[case("[!a]", GlobOptions::default(), "[!a]")]
+#[case("[.]*", GlobOptions::default().literal_leading_dot(true), "[.]*")]
+#[case(
+    ".**[.a]",
+    GlobOptions::default().globstar(true).literal_leading_dot(true),
+    ".*[.a]"
+)]
+#[case("\\.[.]", GlobOptions::default().dot_is_wildcard(true), "\\.[.]")]
+#[case("(?i)/{a}", GlobOptions::default().inline_flags(true).root_relative(true), "(?i)/a")]
+#[case(
+    "[ab]",
+    GlobOptions::default().treat_as_prefix(true).trailing_star_is_recursive(true),
+    "[ab]"
+)]
+#[case("a{b}*", GlobOptions::default().treat_as_prefix(true), "ab*")]
+#[case(
+    "a{*}",
+    GlobOptions::default().treat_as_prefix(true).trailing_star_is_recursive(true),
+    "a{*}"
+)]
+#[case(
+    "{}(?i)",
+    GlobOptions::default().empty_brace(EmptyBrace::Empty).inline_flags(true),
+    "\\(?i)"
+)]
+#[case(
+    "{(}?i)",
+    GlobOptions::default().inline_flags(true),
+    "\\(?i)"
+)]
+fn test_simplify(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &str,
+) -> Result<(), FError> {
+    let glob = Glob::with_options(pattern, &options)?;
+    let simplified = glob.simplify();
+    println!("{} -> {}", pattern, simplified);
+    assert_eq!(simplified, expected);
+
+    let simple_glob = Glob::with_options(&simplified, &options)?;
+    println!("{} -> {}", glob.regex(), simple_glob.regex());
+    // Unless the original pattern is kept as it is, the regular expression
+    // only differs from the original one by the simplifications themselves.
+    let collapsed = GlobAst {
+        nodes: fglob::simplify_nodes(glob.ast().nodes.clone(), &options),
+        ..glob.ast().clone()
+    };
+    let expected_regex = if simplified == pattern {
+        glob.as_regex_str().to_owned()
+    } else {
+        fglob::ast_to_regex_string(&collapsed, &options, false)
+    };
+    assert_eq!(simple_glob.as_regex_str(), expected_regex);
+    assert_eq!(
+        simple_glob.ast().case_insensitive,
+        glob.ast().case_insensitive
+    );
+    assert_eq!(simple_glob.simplify(), simplified);
+    assert_eq!(
+        Glob::with_options(&simple_glob.simplify(), &options)?.as_regex_str(),
+        simple_glob.as_regex_str()
+    );
+    for text in glob
+        .sample_matches(10)
+        .into_iter()
+        .chain(glob.counter_examples(10))
+        .chain(simple_glob.sample_matches(10))
+        .chain(simple_glob.counter_examples(10))
+    {
+        assert_eq!(
+            simple_glob.is_match(&text),
+            glob.is_match(&text),
+            "{:?}",
+            text
+        );
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("app.log", true)]
+#[case("debug.log.1", false)]
+#[case("debug.log", false)]
+#[case("app.txt", false)]
+#[case("logs/app.log", false)]
+fn test_difference(#[case] text: &str, #[case] expected: bool) -> Result<(), FError> {
+    let logs = Glob::new("*.log")?;
+    let debug = Glob::new("debug.log")?;
+    let res = logs.difference(&debug);
+    assert_eq!(res.len(), 2);
+    assert_eq!(res.is_match(text), expected);
+    assert_eq!(
+        res.is_match(text),
+        logs.is_match(text) && !debug.is_match(text)
+    );
+    assert!(!debug.difference(&logs).is_match(text));
+
+    let mut more = res;
+    more.insert("*.txt")?;
+    assert!(more.is_match("app.txt"));
+    assert!(!more.is_match("debug.log"));
+    assert!(matches!(
+        more.into_regex_set(),
+        Err(FError::Unsupported {
+            feature: "negated_rules"
+        })
+    ));
+    Ok(())
+}
@@ -1,4 +1,6 @@
-/**
+//! Test the fnmatch-regex functionality.
+
+/*
  * Copyright (c) 2021  Peter Pentchev <roam@ringlet.net>
  * All rights reserved.
  *
@@ -23,4 +25,12 @@
  * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
  * SUCH DAMAGE.
  */
+#[cfg(feature = "dfa")]
+pub mod dfa;
+pub mod expand;
 pub mod glob;
+pub mod map;
+pub mod matcher;
+pub mod set;
+#[cfg(feature = "walkdir")]
+pub mod walk;
@@ -0,0 +1,283 @@
+//! Test the glob set functionality.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use crate::error::Error as FError;
+use crate::matcher::Glob;
+use crate::options::GlobOptions;
+use crate::set::{GlobSet, GlobSetBuilder};
+
+/// Build a set of patterns for build artifacts.
+fn build_ignored() -> Result<GlobSet, FError> {
+    let mut ignored = GlobSet::new();
+    ignored.insert("*.o")?;
+    ignored.insert("*.{a,so}")?;
+    ignored.insert_glob(Glob::with_options(
+        "target/**",
+        &GlobOptions::default().globstar(true),
+    )?);
+    Ok(ignored)
+}
+
+#[rstest::rstest]
+#[case("main.o", true)]
+#[case("libfoo.so", true)]
+#[case("target/debug/main", true)]
+#[case("main.c", false)]
+#[case("src/main.o", false)]
+fn test_is_match(#[case] text: &str, #[case] expected: bool) -> Result<(), FError> {
+    let ignored = build_ignored()?;
+    assert_eq!(ignored.is_match(text), expected);
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[case(true)]
+fn test_into_regex_set(#[case] used: bool) -> Result<(), FError> {
+    let ignored = build_ignored()?;
+    assert_eq!(ignored.len(), 3);
+    if used {
+        assert!(ignored.is_match("main.o"));
+    }
+    let set = ignored.into_regex_set()?;
+    assert_eq!(set.len(), 3);
+    assert!(set.is_match("libfoo.a"));
+    assert!(!set.is_match("libfoo.c"));
+    assert_eq!(
+        set.matches("target/x.o").into_iter().collect::<Vec<_>>(),
+        [2]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> Result<(), FError> {
+    let empty = GlobSet::new();
+    assert!(empty.is_empty());
+    assert!(!empty.is_match(""));
+    assert!(!empty.into_regex_set()?.is_match(""));
+    Ok(())
+}
+
+/// Some rules in the `.gitignore` format.
+const IGNORE_RULES: &str = "
+# Build artifacts
+*.o
+/target/
+
+  # Not a comment, but indented
+build/**
+!build/keep/**
+!important.o
+\\#hash
+\\!bang
+";
+
+#[rstest::rstest]
+#[case("main.o", true)]
+#[case("src/lib/main.o", true)]
+#[case("important.o", false)]
+#[case("src/important.o", false)]
+#[case("build/out/x", true)]
+#[case("build/keep/x", false)]
+#[case("target/", true)]
+#[case("src/target/", false)]
+#[case("#hash", true)]
+#[case("!bang", true)]
+#[case("main.c", false)]
+#[case("  # Not a comment, but indented", true)]
+fn test_from_ignore_str(#[case] text: &str, #[case] expected: bool) -> Result<(), FError> {
+    let ignored = GlobSet::from_ignore_str(IGNORE_RULES)?;
+    assert_eq!(ignored.len(), 8);
+    assert_eq!(ignored.is_match(text), expected);
+    Ok(())
+}
+
+#[test]
+fn test_from_ignore_str_override() -> Result<(), FError> {
+    let ignored = GlobSet::from_ignore_str("*.log\n!debug.log\n*.log\n")?;
+    assert!(ignored.is_match("debug.log"));
+    let reincluded = GlobSet::from_ignore_str("*.log\n!debug.log\n")?;
+    assert!(!reincluded.is_match("debug.log"));
+    assert!(reincluded.is_match("trace.log"));
+    assert!(!GlobSet::from_ignore_str("!*.log\n")?.is_match("a.log"));
+    assert!(matches!(
+        reincluded.into_regex_set(),
+        Err(FError::Unsupported {
+            feature: "negated_rules"
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_from_ignore_str_error() {
+    assert!(matches!(
+        GlobSet::from_ignore_str("# comment\n*.[ch\n"),
+        Err(FError::UnclosedClass(2))
+    ));
+}
+
+#[test]
+fn test_builder() -> Result<(), FError> {
+    let set = GlobSetBuilder::new()
+        .pattern("*.o")
+        .pattern("*.{a,so}")
+        .build()?;
+    assert_eq!(set.len(), 2);
+    assert!(set.is_match("libfoo.so"));
+    assert!(!set.is_match("target/debug/main"));
+
+    assert!(matches!(
+        GlobSetBuilder::new()
+            .pattern("*.o")
+            .pattern("*.[ch")
+            .pattern("{a,b")
+            .build(),
+        Err(FError::UnclosedClass(2))
+    ));
+    assert!(GlobSetBuilder::new().build()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_builder_lossy() {
+    let (set, errors) = GlobSetBuilder::new()
+        .options(GlobOptions::default().globstar(true))
+        .pattern("*.o")
+        .pattern("*.[ch")
+        .pattern("target/**")
+        .pattern("{a,b")
+        .pattern("a\\")
+        .pattern("*.{a,so}")
+        .build_lossy();
+    assert_eq!(set.len(), 3);
+    assert_eq!(
+        set.iter().map(Glob::pattern).collect::<Vec<_>>(),
+        ["*.o", "target/**", "*.{a,so}"]
+    );
+    assert!(set.is_match("main.o"));
+    assert!(set.is_match("target/debug/main"));
+    assert!(set.is_match("libfoo.a"));
+    assert!(!set.is_match("main.c"));
+
+    assert_eq!(
+        errors.iter().map(|&(idx, _)| idx).collect::<Vec<_>>(),
+        [1, 3, 4]
+    );
+    assert!(matches!(
+        *errors.as_slice(),
+        [
+            (_, FError::UnclosedClass(2)),
+            (_, FError::UnclosedAlternation(0)),
+            (_, FError::BareEscape),
+        ]
+    ));
+
+    let (empty, single) = GlobSetBuilder::new().pattern("[").build_lossy();
+    assert!(empty.is_empty());
+    assert_eq!(single.len(), 1);
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_normalize() -> Result<(), FError> {
+    use crate::options::NormalizationForm;
+
+    let options = GlobOptions::default().normalize(Some(NormalizationForm::Nfc));
+    let mut set = GlobSet::new();
+    set.insert_glob(Glob::with_options("caf\u{e9}/*.txt", &options)?);
+    set.insert("*.md")?;
+    assert!(set.is_match("caf\u{e9}/a.txt"));
+    assert!(set.is_match("cafe\u{301}/a.txt"));
+    assert!(set.is_match("README.md"));
+    assert!(!set.is_match("cafe/a.txt"));
+
+    let all = Glob::with_options("*.txt", &options)?;
+    let diff = all.difference(&Glob::with_options("caf\u{e9}/*", &options)?);
+    assert!(diff.is_match("a.txt"));
+    assert!(!diff.is_match("caf\u{e9}/a.txt"));
+    assert!(!diff.is_match("cafe\u{301}/a.txt"));
+
+    assert!(matches!(
+        set.into_regex_set(),
+        Err(FError::Unsupported {
+            feature: "normalize",
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_unicode() -> Result<(), FError> {
+    let options = GlobOptions::default().inline_flags(true).unicode(false);
+    let mut set = GlobSet::new();
+    set.insert_glob(Glob::with_options("(?i)k", &options)?);
+    assert!(set.is_match("K"));
+    assert!(!set.is_match("\u{212a}"));
+    set.insert("(?i)k")?;
+    assert!(set.is_match("(?i)k"));
+    assert!(!set.is_match("\u{212a}"));
+    assert!(matches!(
+        set.clone().into_regex_set(),
+        Err(FError::Unsupported {
+            feature: "mixed_unicode"
+        })
+    ));
+
+    let mut single = GlobSet::new();
+    single.insert_glob(Glob::with_options("(?i)k", &options)?);
+    let regex_set = single.into_regex_set()?;
+    assert!(regex_set.is_match("K"));
+    assert!(!regex_set.is_match("\u{212a}"));
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("foo\\ ", "foo ", true)]
+#[case("foo\\  \t", "foo ", true)]
+#[case("foo\\ ", "foo", false)]
+#[case("foo  ", "foo", true)]
+#[case("foo\\\\  ", "foo\\", true)]
+#[case("build/", "build/", true)]
+#[case("build/", "build/out/x", true)]
+#[case("build/", "src/build/out/x", true)]
+#[case("build/", "build", false)]
+#[case("/build/", "src/build/out/x", false)]
+#[case("src/build/", "src/build/out/x", true)]
+#[case("src/build/", "a/src/build/out/x", false)]
+fn test_from_ignore_str_rule(
+    #[case] rule: &str,
+    #[case] text: &str,
+    #[case] expected: bool,
+) -> Result<(), FError> {
+    assert_eq!(GlobSet::from_ignore_str(rule)?.is_match(text), expected);
+    Ok(())
+}
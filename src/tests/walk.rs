@@ -0,0 +1,135 @@
+//! Test the directory walk functionality.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+#![allow(clippy::panic_in_result_fn)]
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::matcher::Glob;
+use crate::options::GlobOptions;
+
+/// The files to create in the temporary directory tree.
+const FILES: [&str; 8] = [
+    "README.md",
+    "src/lib.rs",
+    "src/main.rs",
+    "src/util/helper.rs",
+    "src/util/mod.rs",
+    "target/debug/build.rs",
+    "tests/it.rs",
+    "tests/data/input.txt",
+];
+
+/// Create the temporary directory tree.
+fn build_tree(root: &Path) -> io::Result<()> {
+    for name in FILES {
+        let path = root.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, name)?;
+    }
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case("src/*.rs", GlobOptions::default(), &["src/lib.rs", "src/main.rs"])]
+#[case("src/util/*", GlobOptions::default(), &["src/util/helper.rs", "src/util/mod.rs"])]
+#[case("*", GlobOptions::default(), &["README.md", "src", "target", "tests"])]
+#[case("*/*.rs", GlobOptions::default(), &["src/lib.rs", "src/main.rs", "tests/it.rs"])]
+#[case(
+    "{src,tests}/*/*",
+    GlobOptions::default(),
+    &["src/util/helper.rs", "src/util/mod.rs", "tests/data/input.txt"]
+)]
+#[case(
+    "**/*.rs",
+    GlobOptions::default().globstar(true),
+    &[
+        "src/lib.rs",
+        "src/main.rs",
+        "src/util/helper.rs",
+        "src/util/mod.rs",
+        "target/debug/build.rs",
+        "tests/it.rs",
+    ]
+)]
+#[case(
+    "src/**",
+    GlobOptions::default().globstar(true),
+    &["src/lib.rs", "src/main.rs", "src/util", "src/util/helper.rs", "src/util/mod.rs"]
+)]
+#[case(
+    "mod.rs",
+    GlobOptions::default().match_at_any_level(true),
+    &["src/util/mod.rs"]
+)]
+#[case("tests/data", GlobOptions::default(), &["tests/data"])]
+#[case("missing/*.rs", GlobOptions::default(), &[])]
+#[case("./src/*.rs", GlobOptions::default(), &["src/lib.rs", "src/main.rs"])]
+#[case("./*.md", GlobOptions::default(), &["README.md"])]
+#[case("././tests/*", GlobOptions::default(), &["tests/data", "tests/it.rs"])]
+fn test_walk(
+    #[case] pattern: &str,
+    #[case] options: GlobOptions,
+    #[case] expected: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tempd = tempfile::tempdir()?;
+    let root = tempd.path();
+    build_tree(root)?;
+
+    let glob = Glob::with_options(pattern, &options)?;
+    let mut found = glob
+        .walk(root)
+        .map(|res| {
+            res.map(|path| {
+                path.strip_prefix(root)
+                    .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default()
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    found.sort();
+    println!("{} -> {:?}", pattern, found);
+    assert_eq!(found, expected);
+    Ok(())
+}
+
+#[test]
+fn test_walk_error() -> Result<(), Box<dyn std::error::Error>> {
+    let tempd = tempfile::tempdir()?;
+    let root = tempd.path().join("nonexistent");
+    let results: Vec<_> = Glob::new("*.rs")?.walk(&root).collect();
+    assert_eq!(results.len(), 1);
+    assert!(results
+        .iter()
+        .all(|res| matches!(res, Err(err) if err.kind() == io::ErrorKind::NotFound)));
+    Ok(())
+}
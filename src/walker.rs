@@ -0,0 +1,577 @@
+//! Match text strings against a parsed glob pattern directly.
+//!
+//! The elements of the pattern are matched against the text without
+//! building a regular expression, backtracking as needed. This allows
+//! variations that the compiled regular expression does not support, e.g.
+//! ignoring the case of ASCII letters, and it also allows explaining why
+//! a text string did not match: the failure that got the furthest
+//! into the pattern is reported, since that is most likely where the text
+//! diverges from what the pattern author had in mind. The wildcards may
+//! consume text without making much progress through the pattern, so
+//! the position in the text is only used to break ties.
+
+/*
+ * Copyright (c) 2022  Peter Pentchev <roam@ringlet.net>
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions
+ * are met:
+ * 1. Redistributions of source code must retain the above copyright
+ *    notice, this list of conditions and the following disclaimer.
+ * 2. Redistributions in binary form must reproduce the above copyright
+ *    notice, this list of conditions and the following disclaimer in the
+ *    documentation and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE AUTHOR AND CONTRIBUTORS ``AS IS'' AND
+ * ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED.  IN NO EVENT SHALL THE AUTHOR OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS
+ * OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION)
+ * HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT
+ * LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY
+ * OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF
+ * SUCH DAMAGE.
+ */
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::ast::{self, Class, ClassItem, GlobAst, Node};
+use crate::glob;
+use crate::options::{Anchoring, GlobOptions};
+
+/// The elements left to match after the current sequence is exhausted,
+/// e.g. the rest of the pattern after an alternation branch.
+struct Cont<'ast, 'up> {
+    /// The elements to match next.
+    nodes: &'ast [Node],
+    /// The elements to match after those.
+    next: Option<&'up Cont<'ast, 'up>>,
+}
+
+/// How far the matching has progressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Cursor {
+    /// The number of pattern elements and literal characters matched so far.
+    progress: usize,
+    /// The position in the text.
+    pos: usize,
+}
+
+impl Cursor {
+    /// Move past a pattern element that consumed the specified number of characters.
+    const fn advance(self, elements: usize, chars: usize) -> Self {
+        Self {
+            progress: self.progress + elements,
+            pos: self.pos + chars,
+        }
+    }
+}
+
+/// Match the elements of a glob pattern against a text string.
+struct Walker<'data> {
+    /// The characters of the text string.
+    text: &'data [char],
+    /// The options the pattern was parsed with.
+    options: &'data GlobOptions,
+    /// Ignore the case of ASCII letters?
+    fold_case: bool,
+    /// May a trailing `**` match newline characters?
+    dot_matches_newline: bool,
+    /// The elements already known not to match at the specified positions.
+    failed: HashSet<(*const Node, Cursor)>,
+    /// The furthest failure so far and its description.
+    furthest: Option<(Cursor, String)>,
+    /// The position of a leading dot that a `*` matched nothing before,
+    /// so that a literal dot that follows may not match it either.
+    star_dot: Option<usize>,
+    /// The regular expressions for the named classes and for
+    /// the `single_char_class` option, compiled on first use.
+    named: RefCell<HashMap<String, Option<Regex>>>,
+}
+
+/// Render a character class back in glob syntax.
+fn describe_class(class: &Class) -> String {
+    let intersections: String = class
+        .intersections
+        .iter()
+        .map(|nested| format!("&&{}", describe_class(nested)))
+        .collect();
+    let escape = |chr: char| {
+        if "!\\]-".contains(chr) {
+            format!("\\{}", chr)
+        } else {
+            chr.to_string()
+        }
+    };
+    format!(
+        "[{}{}{}]",
+        if class.negated { "!" } else { "" },
+        class
+            .items
+            .iter()
+            .map(|item| match *item {
+                ClassItem::Char(chr) => escape(chr),
+                ClassItem::Range(start, end) => format!("{}-{}", escape(start), escape(end)),
+                ClassItem::Named { ref name, negated } => {
+                    format!("[:{}{}:]", if negated { "^" } else { "" }, name)
+                }
+            })
+            .collect::<String>(),
+        intersections
+    )
+}
+
+impl Walker<'_> {
+    /// Check whether a character class contains the specified character and
+    /// all its intersections do, possibly ignoring the case of ASCII letters.
+    fn class_contains(&self, class: &Class, chr: char) -> bool {
+        let contains = |wanted: char| {
+            class.items.iter().any(|item| match *item {
+                ClassItem::Char(other) => other == wanted,
+                ClassItem::Range(start, end) => start <= wanted && wanted <= end,
+                ClassItem::Named { ref name, negated } => self.named_matches(name, negated, wanted),
+            })
+        };
+        let found = contains(chr)
+            || (self.fold_case
+                && (contains(chr.to_ascii_lowercase()) || contains(chr.to_ascii_uppercase())));
+        found != class.negated
+            && class
+                .intersections
+                .iter()
+                .all(|nested| self.class_contains(nested, chr))
+    }
+
+    /// Check whether a character class matches the specified character,
+    /// which may not be the separator unless the options allow it.
+    fn class_matches(&self, class: &Class, chr: char) -> bool {
+        (chr != self.options.separator
+            || self.options.treat_slash_as_ordinary
+            || self.options.classes_match_separator)
+            && self.class_contains(class, chr)
+    }
+
+    /// Check whether a named class, e.g. `[:alpha:]`, matches the specified character.
+    fn named_matches(&self, name: &str, negated: bool, chr: char) -> bool {
+        glob::named_class(name, negated)
+            .is_some_and(|source| self.regex_matches(format!("^[{}]$", source), chr))
+    }
+
+    /// Check whether a regular expression, compiled on first use, matches
+    /// the specified character.
+    fn regex_matches(&self, source: String, chr: char) -> bool {
+        self.named
+            .borrow_mut()
+            .entry(source)
+            .or_insert_with_key(|key| Regex::new(key).ok())
+            .as_ref()
+            .is_some_and(|re| re.is_match(chr.encode_utf8(&mut [0; 4])))
+    }
+
+    /// Record a failure to find the expected thing at the specified position.
+    /// Always returns false, so that it may be used as the result of a match.
+    fn fail(&mut self, cur: Cursor, expected: &str, len: usize) -> bool {
+        if self.furthest.as_ref().is_none_or(|&(prev, _)| cur > prev) {
+            let found = if cur.pos < self.text.len() {
+                format!(
+                    "'{}'",
+                    self.text.iter().skip(cur.pos).take(len).collect::<String>()
+                )
+            } else {
+                "the end of the text".to_owned()
+            };
+            self.furthest = Some((
+                cur,
+                format!(
+                    "expected {} at position {} but found {}",
+                    expected, cur.pos, found
+                ),
+            ));
+        }
+        false
+    }
+
+    /// Count the characters up to the next separator or the end of the text.
+    fn segment_len(&self, pos: usize) -> usize {
+        self.text
+            .iter()
+            .skip(pos)
+            .take_while(|chr| **chr != self.options.separator)
+            .count()
+    }
+
+    /// Check whether the character at the specified position is a dot that
+    /// starts a path component and must be matched literally.
+    fn hidden_at(&self, pos: usize) -> bool {
+        self.options.literal_leading_dot
+            && self.text.get(pos) == Some(&'.')
+            && (pos == 0 || self.text.get(pos - 1) == Some(&self.options.separator))
+    }
+
+    /// Check whether a path component matched by a globstar starts with
+    /// a dot that must be matched literally.
+    fn hidden_component(&self, comp: &str) -> bool {
+        self.options.literal_leading_dot && comp.starts_with('.')
+    }
+
+    /// Count the characters that the `*` and `?` wildcards may match.
+    fn wildcard_len(&self, pos: usize) -> usize {
+        if self.hidden_at(pos) {
+            0
+        } else if self.options.treat_slash_as_ordinary {
+            self.text.len().saturating_sub(pos)
+        } else {
+            self.segment_len(pos)
+        }
+    }
+
+    /// Match a sequence of elements, then the ones that follow it.
+    fn walk(&mut self, nodes: &[Node], cont: Option<&Cont<'_, '_>>, cur: Cursor) -> bool {
+        match (nodes.split_first(), cont) {
+            (Some((node, rest)), _) if self.star_dot == Some(cur.pos) => {
+                // Do not record the failures, since the same elements may
+                // match the leading dot when not reached through a `*`.
+                self.walk_node(node, rest, cont, cur)
+            }
+            (Some((node, rest)), _) => {
+                let key = (node as *const Node, cur);
+                if self.failed.contains(&key) {
+                    return false;
+                }
+                let res = self.walk_node(node, rest, cont, cur);
+                if !res {
+                    self.failed.insert(key);
+                }
+                res
+            }
+            (None, Some(next)) => self.walk(next.nodes, next.next, cur),
+            (None, None) => {
+                cur.pos == self.text.len()
+                    || !matches!(self.options.anchoring, Anchoring::Full)
+                    || self.fail(cur, "the end of the text", self.text.len() - cur.pos)
+            }
+        }
+    }
+
+    /// Match `count` characters that are not slashes, then the rest.
+    fn walk_any(
+        &mut self,
+        rest: &[Node],
+        cont: Option<&Cont<'_, '_>>,
+        cur: Cursor,
+        count: usize,
+    ) -> bool {
+        if let Some(class) = self.options.single_char_class {
+            let good = self
+                .text
+                .iter()
+                .skip(cur.pos)
+                .take(count)
+                .take_while(|chr| self.regex_matches(format!("^(?:{})$", class), **chr))
+                .count();
+            return if good < count {
+                self.fail(
+                    cur.advance(good, good),
+                    &format!("a character matching {}", class),
+                    1,
+                )
+            } else {
+                self.walk(rest, cont, cur.advance(1, count))
+            };
+        }
+        let good = self.wildcard_len(cur.pos);
+        if good < count {
+            let expected = if self.options.treat_slash_as_ordinary {
+                "any character".to_owned()
+            } else if self.hidden_at(cur.pos + good) {
+                "any character except a leading '.'".to_owned()
+            } else {
+                format!("any character except '{}'", self.options.separator)
+            };
+            self.fail(cur.advance(good, good), &expected, 1)
+        } else {
+            self.walk(rest, cont, cur.advance(1, count))
+        }
+    }
+
+    /// Let a recursive `*` or `**` at the end of the pattern match nothing
+    /// if anything may follow the match anyway, as with the `(None, None)`
+    /// case of [`Walker::walk`], so that e.g. a hidden path component after
+    /// it does not prevent a match at the start of the text.
+    fn walk_empty_tail(&mut self, rest: &[Node], cont: Option<&Cont<'_, '_>>, cur: Cursor) -> bool {
+        !matches!(self.options.anchoring, Anchoring::Full)
+            && self.walk(rest, cont, cur.advance(1, 0))
+    }
+
+    /// Match a recursive `*` at the end of the pattern, then the rest;
+    /// the whole remaining text must be consumed, unless the pattern does
+    /// not need to match up to its end.
+    fn walk_star_tail(&mut self, rest: &[Node], cont: Option<&Cont<'_, '_>>, cur: Cursor) -> bool {
+        if self.walk_empty_tail(rest, cont, cur) {
+            return true;
+        }
+        let remaining = self.text.len() - cur.pos;
+        if self.options.treat_slash_as_ordinary {
+            return self.walk(rest, cont, cur.advance(1, remaining));
+        }
+        if self.options.literal_leading_dot {
+            let text: String = self.text.iter().skip(cur.pos).collect();
+            return if self.hidden_at(cur.pos)
+                || text
+                    .split(self.options.separator)
+                    .skip(1)
+                    .any(|comp| self.hidden_component(comp))
+            {
+                self.fail(cur, "path components that do not start with '.'", remaining)
+            } else {
+                self.walk(rest, cont, cur.advance(1, remaining))
+            };
+        }
+        match self.text.iter().skip(cur.pos).position(|chr| *chr == '\n') {
+            Some(offset) if !self.dot_matches_newline => {
+                self.fail(cur.advance(1, offset), "any character except a newline", 1)
+            }
+            _ => self.walk(rest, cont, cur.advance(1, remaining)),
+        }
+    }
+
+    /// Match a `**` component at the end of the pattern, then the rest.
+    /// The parser only produces these at the very end, so the whole
+    /// remaining text must be consumed, unless the pattern does not need
+    /// to match up to its end.
+    fn walk_globstar_tail(
+        &mut self,
+        rest: &[Node],
+        cont: Option<&Cont<'_, '_>>,
+        cur: Cursor,
+    ) -> bool {
+        if self.walk_empty_tail(rest, cont, cur) {
+            return true;
+        }
+        let remaining = self.text.len() - cur.pos;
+        match self.options.max_globstar_depth {
+            None if self.options.literal_leading_dot => {
+                let text: String = self.text.iter().skip(cur.pos).collect();
+                if text
+                    .split(self.options.separator)
+                    .any(|comp| self.hidden_component(comp))
+                {
+                    self.fail(cur, "path components that do not start with '.'", remaining)
+                } else {
+                    self.walk(rest, cont, cur.advance(1, remaining))
+                }
+            }
+            None => match self.text.iter().skip(cur.pos).position(|chr| *chr == '\n') {
+                Some(offset) if !self.dot_matches_newline => {
+                    self.fail(cur.advance(1, offset), "any character except a newline", 1)
+                }
+                _ => self.walk(rest, cont, cur.advance(1, remaining)),
+            },
+            Some(0) => self.walk(rest, cont, cur.advance(1, 0)),
+            Some(depth) => {
+                let text: String = self.text.iter().skip(cur.pos).collect();
+                let others: Vec<&str> = text.split(self.options.separator).skip(1).collect();
+                if others.len() < depth
+                    && others.iter().all(|comp| !comp.is_empty())
+                    && !text
+                        .split(self.options.separator)
+                        .any(|comp| self.hidden_component(comp))
+                {
+                    self.walk(rest, cont, cur.advance(1, remaining))
+                } else {
+                    self.fail(
+                        cur,
+                        &format!("at most {} path components", depth),
+                        remaining,
+                    )
+                }
+            }
+        }
+    }
+
+    /// Match a `**/` component, i.e. any number of leading directories, then the rest.
+    fn walk_globstar_dirs(
+        &mut self,
+        rest: &[Node],
+        cont: Option<&Cont<'_, '_>>,
+        cur: Cursor,
+    ) -> bool {
+        let mut pos = cur.pos;
+        let mut depth = 0_usize;
+        loop {
+            if self.walk(rest, cont, cur.advance(1, pos - cur.pos)) {
+                return true;
+            }
+            if self.options.max_globstar_depth == Some(depth) {
+                return false;
+            }
+            let run = self.segment_len(pos);
+            if run == 0
+                || self.hidden_at(pos)
+                || self.text.get(pos + run) != Some(&self.options.separator)
+            {
+                return false;
+            }
+            pos += run + 1;
+            depth += 1;
+        }
+    }
+
+    /// Match a single element, then the rest.
+    fn walk_node(
+        &mut self,
+        node: &Node,
+        rest: &[Node],
+        cont: Option<&Cont<'_, '_>>,
+        cur: Cursor,
+    ) -> bool {
+        match *node {
+            Node::Literal(ref lit) if self.star_dot == Some(cur.pos) && lit.starts_with('.') => {
+                self.fail(cur, "any character except a leading '.'", 1)
+            }
+            Node::Literal(ref lit) => {
+                let len = lit.chars().count();
+                let good = self
+                    .text
+                    .iter()
+                    .skip(cur.pos)
+                    .zip(lit.chars())
+                    .take_while(|&(&left, right)| {
+                        left == right || (self.fold_case && left.eq_ignore_ascii_case(&right))
+                    })
+                    .count();
+                if good == len {
+                    self.walk(rest, cont, cur.advance(len, len))
+                } else {
+                    self.fail(cur.advance(good, 0), &format!("literal '{}'", lit), len)
+                }
+            }
+            Node::AnyChar => self.walk_any(rest, cont, cur, 1),
+            Node::AnyChars(count) => self.walk_any(rest, cont, cur, count),
+            Node::Star if self.hidden_at(cur.pos) => {
+                // Only an empty match, and the dot may not be matched by
+                // a literal dot that follows either.
+                let outer = self.star_dot.replace(cur.pos);
+                let res = self.walk(rest, cont, cur.advance(1, 0));
+                self.star_dot = outer;
+                res
+            }
+            Node::Star => {
+                let run = self.wildcard_len(cur.pos);
+                (0..=run)
+                    .rev()
+                    .any(|len| self.walk(rest, cont, cur.advance(1, len)))
+            }
+            Node::GlobstarDirs => self.walk_globstar_dirs(rest, cont, cur),
+            Node::GlobstarTail => self.walk_globstar_tail(rest, cont, cur),
+            Node::StarTail => self.walk_star_tail(rest, cont, cur),
+            Node::Class(ref class) => match self.text.get(cur.pos) {
+                Some(&chr) if self.class_matches(class, chr) && !self.hidden_at(cur.pos) => {
+                    self.walk(rest, cont, cur.advance(1, 1))
+                }
+                _ => self.fail(
+                    cur,
+                    &format!("a character matching {}", describe_class(class)),
+                    1,
+                ),
+            },
+            Node::Alternation(ref branches) => {
+                let next = Cont {
+                    nodes: rest,
+                    next: cont,
+                };
+                branches
+                    .iter()
+                    .any(|branch| self.walk(branch, Some(&next), cur))
+            }
+        }
+    }
+}
+
+/// Match the text against the parsed pattern, also without a trailing
+/// separator if the `optional_trailing_separator` option is enabled.
+/// Return `None` if it matches, or a description of the furthest failure.
+fn walk(ast: &GlobAst, options: &GlobOptions, text: &str, fold_case: bool) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let failure = walk_chars(ast, options, &chars, fold_case)?;
+    match chars.split_last() {
+        Some((&last, rest))
+            if options.optional_trailing_separator
+                && matches!(options.anchoring, Anchoring::Full)
+                && last == options.separator =>
+        {
+            walk_chars(ast, options, rest, fold_case).map(|_| failure)
+        }
+        _ => Some(failure),
+    }
+}
+
+/// Match a sequence of characters against the parsed pattern.
+/// Return `None` if it matches, or a description of the furthest failure.
+fn walk_chars(
+    ast: &GlobAst,
+    options: &GlobOptions,
+    chars: &[char],
+    fold_case: bool,
+) -> Option<String> {
+    let starts: Vec<usize> = if matches!(options.anchoring, Anchoring::Unanchored) {
+        (0..=chars.len()).collect()
+    } else if ast.any_level {
+        // The leading directories are matched by `.*/` with the separator.
+        std::iter::once(0)
+            .chain(
+                chars
+                    .iter()
+                    .enumerate()
+                    .take_while(|&(_, chr)| ast.dot_matches_newline || *chr != '\n')
+                    .filter(|&(_, chr)| *chr == options.separator)
+                    .map(|(idx, _)| idx + 1),
+            )
+            .collect()
+    } else {
+        vec![0]
+    };
+    let mut walker = Walker {
+        text: chars,
+        options,
+        fold_case: fold_case || ast.case_insensitive,
+        dot_matches_newline: ast.dot_matches_newline,
+        failed: HashSet::new(),
+        furthest: None,
+        star_dot: None,
+        named: RefCell::new(HashMap::new()),
+    };
+    // A trailing `/**` may also match nothing at all, leaving only the parent.
+    let parent = ast::globstar_parent(&ast.nodes, options.separator)
+        .filter(|_| options.collapse_globstar_edges);
+    if starts.into_iter().any(|pos| {
+        walker.walk(&ast.nodes, None, Cursor { progress: 0, pos })
+            || parent
+                .as_ref()
+                .is_some_and(|nodes| walker.walk(nodes, None, Cursor { progress: 0, pos }))
+    }) {
+        return None;
+    }
+    Some(walker.furthest.map_or_else(
+        || "the text does not match the pattern".to_owned(),
+        |(_, message)| message,
+    ))
+}
+
+/// Explain why the text does not match the parsed pattern.
+/// Return `None` if it does match after all.
+pub(crate) fn explain(ast: &GlobAst, options: &GlobOptions, text: &str) -> Option<String> {
+    walk(ast, options, text, false)
+}
+
+/// Check whether the text matches the parsed pattern, ignoring the case of
+/// ASCII letters.
+pub(crate) fn matches_ignore_ascii_case(ast: &GlobAst, options: &GlobOptions, text: &str) -> bool {
+    walk(ast, options, text, true).is_none()
+}